@@ -1,6 +1,6 @@
-use std::collections::HashMap;
 use std::path::PathBuf;
 
+pub use catalog::file_catalog::CatalogOptions;
 use catalog::file_catalog::FileCatalog;
 use catalog::{Catalog, InMemoryCatalog};
 use exec::Executor;
@@ -16,9 +16,14 @@ impl<C: Catalog, E: Executor> Session<C, E> {
     pub fn new(catalog: C, executor: E) -> Self {
         Self { catalog, executor }
     }
-    pub fn execute(&mut self, ast: ASTNode) -> ExecutionResult {
+    pub fn execute(&mut self, ast: ASTNode) -> ExecutionResult<'_> {
         self.executor.execute(&mut self.catalog, ast)
     }
+
+    /// True between BEGIN and COMMIT/ROLLBACK.
+    pub fn in_transaction(&self) -> bool {
+        self.catalog.in_transaction()
+    }
 }
 
 impl Session<InMemoryCatalog, QueryExecutor> {
@@ -27,16 +32,11 @@ impl Session<InMemoryCatalog, QueryExecutor> {
     }
 }
 
-impl Session<FileCatalog, QueryExecutor> {
-    pub fn file_backed(data_dir: PathBuf) -> Self {
-        Self::new(
-            FileCatalog {
-                root_dir: data_dir,
-                current_db: None,
-                tables: HashMap::new(),
-                table_states: HashMap::new(),
-            },
-            QueryExecutor,
-        )
+/// A session over databases on disk.
+pub type FileSession = Session<FileCatalog, QueryExecutor>;
+
+impl FileSession {
+    pub fn file_backed(data_dir: PathBuf, options: CatalogOptions) -> Self {
+        Self::new(FileCatalog::with_options(data_dir, options), QueryExecutor)
     }
 }
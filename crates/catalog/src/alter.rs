@@ -0,0 +1,248 @@
+use sql::ast::ASTValue;
+use storage::{
+    Record, Table,
+    record::{EvalContext, coerce_value},
+    types::Column,
+};
+
+use crate::{
+    Catalog,
+    error::{CatalogError, Result},
+    file_catalog::{FileCatalog, constraint_indexes},
+    index::index_rel_path,
+};
+
+// ALTER TABLE ... ADD / DROP / RENAME COLUMN.
+//
+// Heap rows are never rewritten by an ALTER. ADD and DROP bump the table's
+// schema version; every payload is stamped with the version it was written
+// under and decoded with that version's layout (see Table::decode_row), so
+// old rows skip dropped columns and read added ones as the value the column
+// was added with. An UPDATE rewrites its row in the current layout.
+impl FileCatalog {
+    pub(crate) fn alter_add_column(&mut self, table_name: &str, column: Column) -> Result<()> {
+        let table = self.table_to_alter(table_name)?;
+        if table.columns.iter().any(|c| c.name == column.name) {
+            return Err(CatalogError::ColumnExists {
+                table: table_name.to_string(),
+                column: column.name,
+            });
+        }
+        if column.primary_key || column.auto_increment {
+            return Err(invalid_alter(
+                table_name,
+                "cannot add a PRIMARY KEY or AUTOINCREMENT column".to_string(),
+            ));
+        }
+        let missing = match &column.default {
+            Some(default) => Record::new(0)
+                .evaluate(&default.to_expr(), &EvalContext::now())
+                .and_then(|v| coerce_value(v, &column.data_type))
+                .map_err(|e| invalid_alter(table_name, format!("{} in DEFAULT", e)))?,
+            None => ASTValue::Null,
+        };
+        if !column.nullable
+            && missing == ASTValue::Null
+            && !self.table_states[table_name].row_index.is_empty()
+        {
+            return Err(invalid_alter(
+                table_name,
+                format!(
+                    "column {} is NOT NULL without a DEFAULT and the table has rows",
+                    column.name
+                ),
+            ));
+        }
+
+        let before = table;
+        let mut table = before.clone();
+        let unique = column.unique.then(|| column.name.clone());
+        table
+            .add_column(column, missing)
+            .map_err(|e| invalid_alter(table_name, e))?;
+        let index = match unique {
+            Some(col) => constraint_indexes(&table)?
+                .into_iter()
+                .find(|(_, c)| *c == col),
+            None => None,
+        };
+        self.replace_table(table)?;
+
+        // Every existing row reads the added value, so a UNIQUE column with a
+        // non-NULL DEFAULT fails here on a table of more than one row.
+        if let Some((index_name, col)) = index
+            && let Err(e) = self.build_index(table_name, &index_name, &col, true)
+        {
+            self.replace_table(before)?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn alter_drop_column(&mut self, table_name: &str, column: &str) -> Result<()> {
+        let mut table = self.table_to_alter(table_name)?;
+        let Some(col) = table.columns.iter().find(|c| c.name == column) else {
+            return Err(CatalogError::ColumnDoesNotExist {
+                table: table_name.to_string(),
+                column: column.to_string(),
+            });
+        };
+        if table.columns.len() == 1 {
+            return Err(invalid_alter(
+                table_name,
+                format!("cannot drop {}, the only column", column),
+            ));
+        }
+        if col.primary_key {
+            return Err(invalid_alter(
+                table_name,
+                format!("cannot drop PRIMARY KEY column {}", column),
+            ));
+        }
+        let in_foreign_key = table
+            .foreign_keys
+            .iter()
+            .any(|fk| fk.columns.iter().any(|c| c == column))
+            || self
+                .referencing(table_name)
+                .iter()
+                .any(|(_, fk)| fk.ref_columns.iter().any(|c| c == column));
+        if in_foreign_key {
+            return Err(invalid_alter(
+                table_name,
+                format!("column {} is part of a FOREIGN KEY", column),
+            ));
+        }
+
+        let indexes: Vec<String> = self.table_states[table_name]
+            .indexes
+            .iter()
+            .filter(|m| m.column == column)
+            .map(|m| m.name.clone())
+            .collect();
+        for index_name in indexes {
+            self.remove_index(table_name, &index_name)?;
+        }
+        table
+            .drop_column(column)
+            .map_err(|e| invalid_alter(table_name, e))?;
+        self.replace_table(table)
+    }
+
+    pub(crate) fn alter_rename_column(
+        &mut self,
+        table_name: &str,
+        from: &str,
+        to: &str,
+    ) -> Result<()> {
+        let mut table = self.table_to_alter(table_name)?;
+        if !table.columns.iter().any(|c| c.name == from) {
+            return Err(CatalogError::ColumnDoesNotExist {
+                table: table_name.to_string(),
+                column: from.to_string(),
+            });
+        }
+        if table.columns.iter().any(|c| c.name == to) {
+            return Err(CatalogError::ColumnExists {
+                table: table_name.to_string(),
+                column: to.to_string(),
+            });
+        }
+        table
+            .rename_column(from, to)
+            .map_err(|e| invalid_alter(table_name, e))?;
+        // Index metas first: their unit restores them if it fails, and the
+        // schema is still untouched then.
+        self.rename_index_column(table_name, from, to)?;
+        if let Err(e) = self.rename_in_schemas(table_name, table, from, to) {
+            self.rename_index_column(table_name, to, from)?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    // Rename `from` to `to` in `table` and in the foreign keys naming it.
+    fn rename_in_schemas(
+        &mut self,
+        table_name: &str,
+        mut table: Table,
+        from: &str,
+        to: &str,
+    ) -> Result<()> {
+        let rename = |cols: &mut Vec<String>| {
+            for c in cols.iter_mut().filter(|c| *c == from) {
+                *c = to.to_string();
+            }
+        };
+        for fk in &mut table.foreign_keys {
+            rename(&mut fk.columns);
+            if fk.ref_table == table_name {
+                rename(&mut fk.ref_columns);
+            }
+        }
+        self.replace_table(table)?;
+
+        // Child tables name the column in their foreign keys.
+        let children: Vec<String> = self
+            .referencing(table_name)
+            .into_iter()
+            .map(|(child, _)| child)
+            .filter(|child| child != table_name)
+            .collect();
+        for child in children {
+            let mut child = self.tables[&child].clone();
+            for fk in &mut child.foreign_keys {
+                if fk.ref_table == table_name {
+                    rename(&mut fk.ref_columns);
+                }
+            }
+            self.replace_table(child)?;
+        }
+        Ok(())
+    }
+
+    // Point the table's indexes on `from` at `to`.
+    fn rename_index_column(&mut self, table_name: &str, from: &str, to: &str) -> Result<()> {
+        self.write_unit(|cat| {
+            let mut metas = Vec::new();
+            if let Some(st) = cat.table_states.get_mut(table_name) {
+                for meta in st.indexes.iter_mut().filter(|m| m.column == from) {
+                    meta.column = to.to_string();
+                    metas.push(meta.clone());
+                }
+            }
+            metas.iter().try_for_each(|meta| {
+                cat.write_file_page(index_rel_path(table_name, &meta.name), 0, &meta.to_page())
+            })
+        })
+    }
+
+    // A copy of the table, checking it may be altered now.
+    fn table_to_alter(&mut self, table_name: &str) -> Result<Table> {
+        if self.in_transaction() {
+            return Err(CatalogError::TransactionActive);
+        }
+        if self.current_db.is_none() {
+            return Err(CatalogError::NoCurrentDatabase);
+        }
+        self.get_table(table_name)
+            .cloned()
+            .ok_or_else(|| CatalogError::TableDoesNotExist {
+                name: table_name.to_string(),
+            })
+    }
+
+    // Persist `table`'s schema and make it the one in use.
+    fn replace_table(&mut self, table: Table) -> Result<()> {
+        self.store_schema(&table)?;
+        self.tables.insert(table.name.clone(), table);
+        Ok(())
+    }
+}
+
+fn invalid_alter(table: &str, reason: String) -> CatalogError {
+    CatalogError::InvalidAlter {
+        table: table.to_string(),
+        reason,
+    }
+}
@@ -0,0 +1,363 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use storage::page::PAGE_SIZE;
+
+use crate::error::{CatalogError, Result};
+
+// Bounded page cache shared by heap and index files.
+//
+// Frames are filled lazily up to `capacity` and then recycled with the clock
+// (second-chance) algorithm: every access sets a frame's reference bit, and
+// the hand clears bits until it finds an unpinned, unreferenced frame.
+//
+// Only committed page images enter the pool (reads from disk, or pages whose
+// WAL unit is already fsynced), so a dirty victim can be written back at any
+// time without breaking write-ahead ordering. flush_all() is the checkpoint
+// half: write every dirty frame, then fsync the files that were written.
+pub const DEFAULT_POOL_PAGES: usize = 1024;
+
+pub type FrameId = usize;
+
+type PageKey = (PathBuf, u32);
+
+struct Frame {
+    key: Option<PageKey>,
+    data: Box<[u8; PAGE_SIZE]>,
+    pin_count: u32,
+    dirty: bool,
+    referenced: bool,
+}
+
+pub struct BufferPool {
+    capacity: usize,
+    frames: Vec<Frame>,
+    lookup: HashMap<PageKey, FrameId>,
+    hand: usize,
+    files: HashMap<PathBuf, File>,
+    // Files written by evictions or flushes since the last fsync.
+    unsynced: HashSet<PathBuf>,
+}
+
+impl BufferPool {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            frames: Vec::new(),
+            lookup: HashMap::new(),
+            hand: 0,
+            files: HashMap::new(),
+            unsynced: HashSet::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Pin `page_id` of `path`, reading it from disk on a miss. The frame
+    /// stays resident until every pin is released with `unpin`.
+    pub fn pin(&mut self, path: &Path, page_id: u32) -> Result<FrameId> {
+        let key = (path.to_path_buf(), page_id);
+        if let Some(&fid) = self.lookup.get(&key) {
+            let frame = &mut self.frames[fid];
+            frame.pin_count += 1;
+            frame.referenced = true;
+            return Ok(fid);
+        }
+
+        let fid = self.victim()?;
+        let mut data = std::mem::replace(&mut self.frames[fid].data, Box::new([0u8; PAGE_SIZE]));
+        if let Err(e) = self.read_from_disk(path, page_id, &mut data) {
+            self.frames[fid].data = data;
+            return Err(e);
+        }
+        self.install(fid, key, data, false);
+        self.frames[fid].pin_count = 1;
+        Ok(fid)
+    }
+
+    pub fn unpin(&mut self, fid: FrameId, dirty: bool) {
+        let frame = &mut self.frames[fid];
+        debug_assert!(frame.pin_count > 0, "unpin of unpinned frame");
+        frame.pin_count = frame.pin_count.saturating_sub(1);
+        frame.dirty |= dirty;
+    }
+
+    pub fn page(&self, fid: FrameId) -> &[u8; PAGE_SIZE] {
+        &self.frames[fid].data
+    }
+
+    /// Mutable access to a pinned page; the caller passes `dirty = true` to
+    /// `unpin` once done.
+    pub fn page_mut(&mut self, fid: FrameId) -> &mut [u8; PAGE_SIZE] {
+        &mut self.frames[fid].data
+    }
+
+    /// Install a committed page image as the current (dirty) copy of the page.
+    pub fn put(&mut self, path: &Path, page_id: u32, image: &[u8; PAGE_SIZE]) -> Result<()> {
+        let key = (path.to_path_buf(), page_id);
+        if let Some(&fid) = self.lookup.get(&key) {
+            let frame = &mut self.frames[fid];
+            *frame.data = *image;
+            frame.dirty = true;
+            frame.referenced = true;
+            return Ok(());
+        }
+        let fid = self.victim()?;
+        self.install(fid, key, Box::new(*image), true);
+        Ok(())
+    }
+
+    /// Write every dirty frame back and fsync the files touched since the
+    /// last flush.
+    pub fn flush_all(&mut self) -> Result<()> {
+        for fid in 0..self.frames.len() {
+            self.write_back(fid)?;
+        }
+        for path in std::mem::take(&mut self.unsynced) {
+            if let Some(f) = self.files.get(&path) {
+                f.sync_all()
+                    .map_err(|source| CatalogError::SyncFile { path, source })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget every cached page of `path` without writing it, e.g. after the
+    /// file was rewritten or removed behind the pool's back.
+    pub fn discard_file(&mut self, path: &Path) {
+        self.discard_where(|p| p == path);
+    }
+
+    /// Forget every cached page of the files under `dir`, e.g. before the
+    /// directory is removed.
+    pub fn discard_dir(&mut self, dir: &Path) {
+        self.discard_where(|p| p.starts_with(dir));
+    }
+
+    /// Drop all frames and file handles. Callers flush first.
+    pub fn clear(&mut self) {
+        debug_assert!(self.frames.iter().all(|f| !f.dirty && f.pin_count == 0));
+        self.frames.clear();
+        self.lookup.clear();
+        self.files.clear();
+        self.unsynced.clear();
+        self.hand = 0;
+    }
+
+    fn discard_where(&mut self, matches: impl Fn(&Path) -> bool) {
+        for frame in &mut self.frames {
+            if frame.key.as_ref().is_some_and(|(p, _)| matches(p)) {
+                debug_assert_eq!(frame.pin_count, 0, "discarding a pinned page");
+                frame.key = None;
+                frame.dirty = false;
+                frame.referenced = false;
+            }
+        }
+        self.lookup.retain(|(p, _), _| !matches(p));
+        self.files.retain(|p, _| !matches(p));
+        self.unsynced.retain(|p| !matches(p));
+    }
+
+    fn install(&mut self, fid: FrameId, key: PageKey, data: Box<[u8; PAGE_SIZE]>, dirty: bool) {
+        self.lookup.insert(key.clone(), fid);
+        let frame = &mut self.frames[fid];
+        frame.key = Some(key);
+        frame.data = data;
+        frame.dirty = dirty;
+        frame.referenced = true;
+    }
+
+    // Find a free frame: grow while under capacity, otherwise run the clock.
+    // The victim is written back (if dirty) and unmapped.
+    fn victim(&mut self) -> Result<FrameId> {
+        if self.frames.len() < self.capacity {
+            self.frames.push(Frame {
+                key: None,
+                data: Box::new([0u8; PAGE_SIZE]),
+                pin_count: 0,
+                dirty: false,
+                referenced: false,
+            });
+            return Ok(self.frames.len() - 1);
+        }
+
+        // Two sweeps clear every reference bit; a third finding nothing means
+        // all frames are pinned.
+        for _ in 0..self.frames.len() * 3 {
+            let fid = self.hand;
+            self.hand = (self.hand + 1) % self.frames.len();
+            let frame = &mut self.frames[fid];
+            if frame.pin_count > 0 {
+                continue;
+            }
+            if frame.referenced {
+                frame.referenced = false;
+                continue;
+            }
+            self.write_back(fid)?;
+            if let Some(key) = self.frames[fid].key.take() {
+                self.lookup.remove(&key);
+            }
+            return Ok(fid);
+        }
+        Err(CatalogError::BufferPoolFull {
+            capacity: self.capacity,
+        })
+    }
+
+    fn write_back(&mut self, fid: FrameId) -> Result<()> {
+        let frame = &self.frames[fid];
+        if !frame.dirty {
+            return Ok(());
+        }
+        let (path, page_id) = frame.key.clone().expect("dirty frame without a page");
+        let f = open_file(&mut self.files, &path, true)?;
+        f.seek(SeekFrom::Start(page_id as u64 * PAGE_SIZE as u64))
+            .map_err(|source| CatalogError::SeekFile {
+                path: path.clone(),
+                source,
+            })?;
+        f.write_all(&self.frames[fid].data[..])
+            .map_err(|source| CatalogError::WriteFile {
+                path: path.clone(),
+                source,
+            })?;
+        self.frames[fid].dirty = false;
+        self.unsynced.insert(path);
+        Ok(())
+    }
+
+    fn read_from_disk(
+        &mut self,
+        path: &Path,
+        page_id: u32,
+        buf: &mut [u8; PAGE_SIZE],
+    ) -> Result<()> {
+        let f = open_file(&mut self.files, path, false)?;
+        f.seek(SeekFrom::Start(page_id as u64 * PAGE_SIZE as u64))
+            .map_err(|source| CatalogError::SeekFile {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        f.read_exact(buf).map_err(|source| CatalogError::ReadFile {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+// Cached handle for `path`; `create` also creates the file (and its
+// directory) for write-back of pages that never reached disk.
+fn open_file<'a>(
+    files: &'a mut HashMap<PathBuf, File>,
+    path: &Path,
+    create: bool,
+) -> Result<&'a mut File> {
+    if !files.contains_key(path) {
+        if create && let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|source| CatalogError::CreateDir {
+                path: parent.to_path_buf(),
+                source,
+            })?;
+        }
+        let f = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(create)
+            .truncate(false)
+            .open(path)
+            .map_err(|source| CatalogError::OpenFile {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        files.insert(path.to_path_buf(), f);
+    }
+    Ok(files.get_mut(path).expect("file handle just inserted"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_file(tag: &str, pages: u8) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("meridb-pool-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("heap.0001");
+        let mut bytes = Vec::new();
+        for i in 0..pages {
+            bytes.extend_from_slice(&[i; PAGE_SIZE]);
+        }
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_dirty_page_survives_eviction() {
+        let path = temp_file("evict", 4);
+        let mut pool = BufferPool::new(2);
+        pool.put(&path, 0, &[9u8; PAGE_SIZE]).unwrap();
+        for pid in 1..4 {
+            let fid = pool.pin(&path, pid).unwrap();
+            assert_eq!(pool.page(fid)[0], pid as u8);
+            pool.unpin(fid, false);
+        }
+        // Page 0 was evicted and written back; re-reading it hits the disk.
+        let fid = pool.pin(&path, 0).unwrap();
+        assert_eq!(pool.page(fid)[0], 9);
+        pool.unpin(fid, false);
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_pinned_pages_are_not_evicted() {
+        let path = temp_file("pinned", 3);
+        let mut pool = BufferPool::new(2);
+        let a = pool.pin(&path, 0).unwrap();
+        let b = pool.pin(&path, 1).unwrap();
+        assert!(matches!(
+            pool.pin(&path, 2),
+            Err(CatalogError::BufferPoolFull { capacity: 2 })
+        ));
+        pool.unpin(b, false);
+        let c = pool.pin(&path, 2).unwrap();
+        assert_eq!(pool.page(a)[0], 0);
+        assert_eq!(pool.page(c)[0], 2);
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_flush_all_writes_dirty_pages() {
+        let path = temp_file("flush", 2);
+        let mut pool = BufferPool::new(8);
+        let fid = pool.pin(&path, 1).unwrap();
+        pool.page_mut(fid).fill(7);
+        pool.unpin(fid, true);
+        pool.flush_all().unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert!(bytes[PAGE_SIZE..].iter().all(|&b| b == 7));
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_discard_dir_drops_dirty_pages_unwritten() {
+        let path = temp_file("discard", 2);
+        let mut pool = BufferPool::new(8);
+        pool.put(&path, 0, &[5u8; PAGE_SIZE]).unwrap();
+        pool.discard_dir(path.parent().unwrap());
+        pool.flush_all().unwrap();
+
+        let fid = pool.pin(&path, 0).unwrap();
+        assert_eq!(pool.page(fid)[0], 0);
+        pool.unpin(fid, false);
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
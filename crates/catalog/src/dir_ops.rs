@@ -62,3 +62,33 @@ pub fn fsync_dir(path: &Path) -> Result<()> {
         source,
     })
 }
+
+/// Remove `dir` and everything below it. The directory is first renamed into
+/// `trash`, so a crash part way through leaves either all of `dir` in place
+/// or a leftover in `trash`, which the next removal sweeps, never a
+/// half-deleted `dir`.
+pub fn remove_dir_via_trash(dir: &Path, trash: &Path) -> Result<()> {
+    if trash.exists() {
+        fs::remove_dir_all(trash).map_err(|source| CatalogError::RemoveDir {
+            path: trash.to_path_buf(),
+            source,
+        })?;
+    }
+    fs::create_dir_all(trash).map_err(|source| CatalogError::CreateDir {
+        path: trash.to_path_buf(),
+        source,
+    })?;
+
+    let target = trash.join(dir.file_name().unwrap_or_default());
+    fs::rename(dir, &target).map_err(|source| CatalogError::Rename {
+        from: dir.to_path_buf(),
+        to: target.clone(),
+        source,
+    })?;
+    fsync_dir(dir.parent().unwrap_or(Path::new(".")))?;
+
+    fs::remove_dir_all(trash).map_err(|source| CatalogError::RemoveDir {
+        path: trash.to_path_buf(),
+        source,
+    })
+}
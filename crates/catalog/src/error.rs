@@ -48,6 +48,12 @@ pub enum CatalogError {
         source: std::io::Error,
     },
 
+    #[snafu(display("Failed to remove directory {path:?}: {source}"))]
+    RemoveDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
     #[snafu(display("Failed to fsync directory {path:?}: {source}"))]
     FsyncDir {
         path: PathBuf,
@@ -116,4 +122,90 @@ pub enum CatalogError {
 
     #[snafu(display("Table does not exist: {name}"))]
     TableDoesNotExist { name: String },
+
+    #[snafu(display("Table already exists: {name}"))]
+    TableExists { name: String },
+
+    #[snafu(display("Table {table} is referenced by a FOREIGN KEY of {child}"))]
+    TableReferenced { table: String, child: String },
+
+    #[snafu(display("A transaction is already in progress"))]
+    TransactionActive,
+
+    #[snafu(display("No transaction in progress"))]
+    NoTransaction,
+
+    #[snafu(display("The transaction was aborted by a failed statement; ROLLBACK to end it"))]
+    TransactionAborted,
+
+    #[snafu(display("Column '{column}' does not exist in table '{table}'"))]
+    ColumnDoesNotExist { table: String, column: String },
+
+    #[snafu(display("Column '{column}' already exists in table '{table}'"))]
+    ColumnExists { table: String, column: String },
+
+    #[snafu(display("Cannot alter table {table}: {reason}"))]
+    InvalidAlter { table: String, reason: String },
+
+    #[snafu(display("Index already exists: {name}"))]
+    IndexExists { name: String },
+
+    #[snafu(display("Index does not exist: {name}"))]
+    IndexDoesNotExist { name: String },
+
+    #[snafu(display("{operation} is not supported by this catalog"))]
+    Unsupported { operation: &'static str },
+
+    #[snafu(display("Buffer pool exhausted: all {capacity} pages are pinned"))]
+    BufferPoolFull { capacity: usize },
+
+    #[snafu(display("Index key too large: {len} bytes (max {max})"))]
+    IndexKeyTooLarge { len: usize, max: usize },
+
+    #[snafu(display(
+        "{constraint} constraint violated: duplicate value {value} in {table}.{column}"
+    ))]
+    UniqueViolation {
+        constraint: &'static str,
+        table: String,
+        column: String,
+        value: String,
+    },
+
+    #[snafu(display("Table {table} declares more than one PRIMARY KEY column"))]
+    MultiplePrimaryKeys { table: String },
+
+    #[snafu(display("Index {name} enforces a PRIMARY KEY or UNIQUE constraint"))]
+    IndexBacksConstraint { name: String },
+
+    #[snafu(display("Invalid AUTOINCREMENT on {table}: {reason}"))]
+    InvalidAutoIncrement { table: String, reason: String },
+
+    #[snafu(display("AUTOINCREMENT values of {table} are exhausted"))]
+    SequenceExhausted { table: String },
+
+    #[snafu(display("Invalid foreign key on {table}: {reason}"))]
+    InvalidForeignKey { table: String, reason: String },
+
+    #[snafu(display(
+        "FOREIGN KEY constraint violated: {table}.{column} = {value} has no matching row in {ref_table}.{ref_column}"
+    ))]
+    ForeignKeyViolation {
+        table: String,
+        column: String,
+        value: String,
+        ref_table: String,
+        ref_column: String,
+    },
+
+    #[snafu(display(
+        "FOREIGN KEY constraint violated: {table}.{column} = {value} is still referenced from {child_table}.{child_column}"
+    ))]
+    RowStillReferenced {
+        table: String,
+        column: String,
+        value: String,
+        child_table: String,
+        child_column: String,
+    },
 }
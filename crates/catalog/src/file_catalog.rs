@@ -1,34 +1,69 @@
 use std::{
-    collections::HashMap,
-    fs::{self, OpenOptions},
-    io::{Read, Seek, SeekFrom, Write},
-    path::PathBuf,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{Seek, SeekFrom, Write},
+    ops::Bound,
+    path::{Path, PathBuf},
     time::{SystemTime, UNIX_EPOCH},
 };
 
+use sql::ast::ASTValue;
 use storage::{
-    Table,
+    Record, Table,
+    btree::IndexMeta,
     page::{
-        HEADER_LEN, PAGE_SIZE, SLOT_LEN, iter_slots, page_append, page_overwrite_if_fits,
-        page_set_tombstone,
+        HEADER_LEN, PAGE_SIZE, SLOT_LEN, SLOT_TOMBSTONE, SLOT_VERSIONED, iter_slots, page_append,
+        page_overwrite_if_fits, page_set_tombstone,
     },
-    record::{deserialize_record_for_page, serialize_record_for_page},
-    types::{RowId, TupleLoc},
+    types::{Column, RowId, TupleLoc},
 };
+use types::tokens::DataType;
 
 use crate::{
-    Catalog,
-    dir_ops::{atomic_write_file, create_db_dirs},
+    Catalog, PageScan,
+    buffer_pool::{BufferPool, DEFAULT_POOL_PAGES},
+    dir_ops::{atomic_write_file, create_db_dirs, fsync_dir, remove_dir_via_trash},
     error::{CatalogError, Result},
+    index::{index_rel_path, key_range, load_index_metas},
     meta_codec::{decode_meta, encode_meta},
     table_schema_codec::{decode_schema, encode_schema},
+    wal::{self, PageWrite, WAL_CHECKPOINT_BYTES, Wal, write_page_at},
 };
 
+// Directory, in the data root and in each database, that removed
+// databases, tables and heap segments are moved into before deletion.
+const TRASH_DIR: &str = ".trash";
+
+/// Default size of one heap segment file (heap.0001, heap.0002, ...).
+pub const DEFAULT_SEGMENT_BYTES: u64 = 1 << 30;
+
+/// Tunables for a file-backed catalog.
+#[derive(Debug, Clone, Copy)]
+pub struct CatalogOptions {
+    /// Buffer pool capacity in pages.
+    pub pool_pages: usize,
+    /// Heap segment size in bytes, rounded down to whole pages (at least one).
+    pub segment_bytes: u64,
+}
+
+impl Default for CatalogOptions {
+    fn default() -> Self {
+        Self {
+            pool_pages: DEFAULT_POOL_PAGES,
+            segment_bytes: DEFAULT_SEGMENT_BYTES,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct TableState {
     pub row_index: HashMap<RowId, TupleLoc>,
-    pub free_space: HashMap<u32, usize>, // page_id -> free bytes
-    pub next_page_id: u32,
+    pub free_space: HashMap<(u32, u32), usize>, // (seg, page_id) -> free bytes
+    pub seg_pages: Vec<u32>,                    // page count of heap.0001, heap.0002, ...
     pub next_row_id: RowId,
+    pub indexes: Vec<IndexMeta>,
+    pub next_auto: i64, // next AUTOINCREMENT value, see sequence.rs
 }
 
 pub struct FileCatalog {
@@ -36,27 +71,50 @@ pub struct FileCatalog {
     pub current_db: Option<String>,
     pub tables: HashMap<String, Table>,
     pub table_states: HashMap<String, TableState>,
+    wal: Option<Wal>,
+    // Pages modified by the current unit of work, keyed by (path relative to
+    // the database directory, page_id). Reads see these before the heap file.
+    write_set: BTreeMap<(PathBuf, u32), Box<[u8; PAGE_SIZE]>>,
+    // Committed pages of heap and index files; flushed on checkpoint.
+    pool: RefCell<BufferPool>,
+    // Pages per heap segment before a table rolls over to the next file.
+    segment_pages: u32,
+    // Table states as of BEGIN; restored on ROLLBACK. While set, staged pages
+    // are kept until COMMIT instead of being committed per operation.
+    txn_snapshot: Option<HashMap<String, TableState>>,
+    // Set when a statement failed inside the transaction: its changes are
+    // gone and only ROLLBACK (or COMMIT, which rolls back) ends it.
+    txn_aborted: bool,
 }
 
 impl FileCatalog {
     pub fn new(root_dir: PathBuf) -> Self {
+        Self::with_options(root_dir, CatalogOptions::default())
+    }
+
+    pub fn with_options(root_dir: PathBuf, options: CatalogOptions) -> Self {
+        let segment_pages = (options.segment_bytes / PAGE_SIZE as u64).clamp(1, u32::MAX as u64);
         Self {
             root_dir,
             current_db: None,
             tables: HashMap::new(),
             table_states: HashMap::new(),
+            wal: None,
+            write_set: BTreeMap::new(),
+            pool: RefCell::new(BufferPool::new(options.pool_pages)),
+            segment_pages: segment_pages as u32,
+            txn_snapshot: None,
+            txn_aborted: false,
         }
     }
 }
 
 impl Catalog for FileCatalog {
     fn create_database(&mut self, name: &str) -> Result<()> {
-        if name.is_empty()
-            || name.len() > 128
-            || !name
-                .chars()
-                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
-        {
+        if self.txn_snapshot.is_some() {
+            return Err(CatalogError::TransactionActive);
+        }
+        if !is_valid_db_name(name) {
             return Err(CatalogError::InvalidName {
                 name: name.to_string(),
             });
@@ -74,13 +132,20 @@ impl Catalog for FileCatalog {
         let final_meta = db_dir.join("metadata.mdb");
         atomic_write_file(&tmp, &final_meta, &meta_bytes)?;
 
+        self.checkpoint()?;
+        self.pool.get_mut().clear();
+        self.wal = Some(Wal::open(&db_dir)?);
         self.current_db = Some(name.to_string());
         self.tables.clear();
+        self.table_states.clear();
 
         Ok(())
     }
 
-    fn create_table(&mut self, name: String, table: Table) -> Result<()> {
+    fn create_table(&mut self, name: String, mut table: Table) -> Result<()> {
+        if self.txn_snapshot.is_some() {
+            return Err(CatalogError::TransactionActive);
+        }
         let db = match &self.current_db {
             Some(db) => db,
             None => return Err(CatalogError::NoCurrentDatabase),
@@ -89,8 +154,23 @@ impl Catalog for FileCatalog {
         if !is_valid_ident(&name) {
             return Err(CatalogError::InvalidName { name });
         }
-
         let tables_dir = self.root_dir.join(db).join("tables");
+        let table_dir = tables_dir.join(&name);
+        if self.tables.contains_key(&name) || table_dir.exists() {
+            return Err(CatalogError::TableExists { name });
+        }
+        check_auto_increment(&table)?;
+        let constraint_indexes = constraint_indexes(&table)?;
+        self.resolve_foreign_keys(&mut table)?;
+        if let Some((index_name, _)) = constraint_indexes
+            .iter()
+            .find(|(index_name, _)| self.find_index(index_name).is_some())
+        {
+            return Err(CatalogError::IndexExists {
+                name: index_name.clone(),
+            });
+        }
+
         if !tables_dir.exists() {
             fs::create_dir_all(&tables_dir).map_err(|source| CatalogError::CreateDir {
                 path: tables_dir.clone(),
@@ -100,22 +180,12 @@ impl Catalog for FileCatalog {
             return Err(CatalogError::TablesDirNotDir { path: tables_dir });
         }
 
-        let table_dir = tables_dir.join(&name);
-        if table_dir.exists() {
-            return Err(CatalogError::AlreadyExists {
-                name,
-                path: table_dir,
-            });
-        }
         fs::create_dir_all(&table_dir).map_err(|source| CatalogError::CreateDir {
             path: table_dir.clone(),
             source,
         })?;
 
-        let schema_bytes = encode_schema(&table.name, &table.columns);
-        let tmp = table_dir.join("schema.tmp");
-        let final_schema = table_dir.join("schema.tbl");
-        atomic_write_file(&tmp, &final_schema, &schema_bytes)?;
+        self.store_schema(&table)?;
         let data_dir = table_dir.join("data");
         fs::create_dir_all(&data_dir).map_err(|source| CatalogError::CreateDir {
             path: data_dir.clone(),
@@ -127,13 +197,98 @@ impl Catalog for FileCatalog {
             .or_insert(TableState {
                 row_index: HashMap::new(),
                 free_space: HashMap::new(),
-                next_page_id: 0,
+                seg_pages: Vec::new(),
                 next_row_id: 1, // start RowIds at 1
+                indexes: Vec::new(),
+                next_auto: 1,
             });
+        self.store_tables_count()?;
 
+        for (index_name, column) in constraint_indexes {
+            self.build_index(&table.name, &index_name, &column, true)?;
+        }
         Ok(())
     }
 
+    fn drop_table(&mut self, name: &str) -> Result<()> {
+        self.table_to_remove(name)?;
+        if let Some((child, _)) = self
+            .referencing(name)
+            .into_iter()
+            .find(|(child, _)| child != name)
+        {
+            return Err(CatalogError::TableReferenced {
+                table: name.to_string(),
+                child,
+            });
+        }
+
+        // Replaying older log records would recreate the files.
+        self.checkpoint()?;
+        let table_dir = self.table_dir(name);
+        self.pool.get_mut().discard_dir(&table_dir);
+        remove_dir_via_trash(&table_dir, &self.trash_dir())?;
+        self.tables.remove(name);
+        self.table_states.remove(name);
+        self.store_tables_count()
+    }
+
+    fn truncate_table(&mut self, name: &str) -> Result<()> {
+        self.table_to_remove(name)?;
+        // Rows of the table referencing itself go along with their parents.
+        if let Some((child, _)) = self
+            .referencing(name)
+            .into_iter()
+            .find(|(child, _)| child != name && !self.table_states[child].row_index.is_empty())
+        {
+            return Err(CatalogError::TableReferenced {
+                table: name.to_string(),
+                child,
+            });
+        }
+
+        // Moving the heap directory away is the commit point: a table
+        // without one is finished off on the next USE (see
+        // `finish_truncate`), so a failure from here on closes the database.
+        self.checkpoint()?;
+        let data_dir = self.table_dir(name).join("data");
+        self.pool.get_mut().discard_dir(&data_dir);
+        let db_dir = self
+            .root_dir
+            .join(self.current_db.as_ref().expect("No current DB"));
+        let result = remove_dir_via_trash(&data_dir, &self.trash_dir()).and_then(|()| {
+            let state = self.table_states.get_mut(name).expect("no table state");
+            state.row_index.clear();
+            state.free_space.clear();
+            state.seg_pages.clear();
+            self.finish_truncate(name)
+        });
+        if result.is_err() && !data_dir.is_dir() {
+            self.close_database(&db_dir);
+        }
+        result
+    }
+
+    fn drop_database(&mut self, name: &str) -> Result<()> {
+        if self.txn_snapshot.is_some() {
+            return Err(CatalogError::TransactionActive);
+        }
+        let db_dir = self.root_dir.join(name);
+        if !is_valid_db_name(name) || !db_dir.join("metadata.mdb").is_file() {
+            return Err(CatalogError::DatabaseDirMissing { path: db_dir });
+        }
+
+        if self.current_db.as_deref() == Some(name) {
+            self.checkpoint()?;
+            self.pool.get_mut().clear();
+            self.wal = None;
+            self.current_db = None;
+            self.tables.clear();
+            self.table_states.clear();
+        }
+        remove_dir_via_trash(&db_dir, &self.root_dir.join(TRASH_DIR))
+    }
+
     fn get_table(&mut self, name: &str) -> Option<&Table> {
         if !self.tables.contains_key(name) {
             let _ = self.load_table_schema_if_exists(name);
@@ -198,6 +353,9 @@ impl Catalog for FileCatalog {
     }
 
     fn use_database(&mut self, name: &str) -> Result<()> {
+        if self.txn_snapshot.is_some() {
+            return Err(CatalogError::TransactionActive);
+        }
         let db_dir = self.root_dir.join(name);
         if !db_dir.exists() {
             return Err(CatalogError::DatabaseDirMissing { path: db_dir });
@@ -219,8 +377,12 @@ impl Catalog for FileCatalog {
             source: Box::new(source),
         })?;
 
+        self.checkpoint()?;
+        self.pool.get_mut().clear();
+        self.wal = Some(recover(&db_dir)?);
         self.current_db = Some(name.to_string());
         self.tables.clear();
+        self.table_states.clear();
 
         // Load table schemas from data/<db>/tables/<table>/schema.tbl
         let tables_dir = self.root_dir.join(name).join("tables");
@@ -247,75 +409,88 @@ impl Catalog for FileCatalog {
                     source,
                 })?;
 
-                let (_tname, cols) = decode_schema(&bytes)?;
+                let mut table = decode_schema(&bytes)?;
                 let tname = path
                     .file_name()
                     .and_then(|s| s.to_str())
                     .unwrap_or("")
                     .to_string();
-                let table = storage::Table::new(tname.clone(), cols);
+                table.name = tname.clone();
                 self.tables.insert(tname.clone(), table);
-                let mut max_rowid: RowId = 0;
-                let mut state = TableState {
-                    row_index: HashMap::new(),
-                    free_space: HashMap::new(),
-                    next_page_id: 0,
-                    next_row_id: 0,
-                };
+                let mut seg_pages = Vec::new();
+                while let Ok(m) = fs::metadata(self.heap_path(&tname, seg_pages.len() as u32 + 1)) {
+                    seg_pages.push((m.len() / PAGE_SIZE as u64) as u32);
+                }
+                let next_auto = self.load_sequence(&tname)?;
+                self.table_states.insert(
+                    tname.clone(),
+                    TableState {
+                        row_index: HashMap::new(),
+                        free_space: HashMap::new(),
+                        seg_pages,
+                        next_row_id: 0,
+                        indexes: load_index_metas(&path)?,
+                        next_auto,
+                    },
+                );
+                if !path.join("data").is_dir() {
+                    self.finish_truncate(&tname)?;
+                }
 
-                let pages = self.seq_scan_pages(&tname)?;
-                if let Some(tbl) = self.tables.get_mut(&tname.clone()) {
-                    for (page_id, page) in pages.into_iter().enumerate() {
-                        let pid = page_id as u32;
-                        let slots =
-                            iter_slots(&page).map_err(|e| CatalogError::InvalidMetadata {
+                let table = self.tables[&tname].clone();
+                let mut max_rowid: RowId = 0;
+                let mut row_index = HashMap::new();
+                let mut free_space = HashMap::new();
+                for item in self.scan_pages(&tname)? {
+                    let (seg, pid, page) = item?;
+                    let slots = iter_slots(&page).map_err(|e| CatalogError::InvalidMetadata {
+                        path: path.clone(),
+                        source: Box::new(std::io::Error::other(e)),
+                    })?;
+                    let mut max_end = HEADER_LEN;
+                    let mut rc = 0usize;
+                    for (sid, (off, len, flags)) in slots.enumerate() {
+                        rc += 1;
+                        if flags & SLOT_TOMBSTONE != 0 {
+                            continue;
+                        }
+                        let start = off as usize;
+                        let end = start + len as usize;
+                        let payload = &page[start..end];
+                        let (rowid, _rec) = table.decode_row(flags, payload).map_err(|e| {
+                            CatalogError::InvalidMetadata {
                                 path: path.clone(),
                                 source: Box::new(std::io::Error::other(e)),
-                            })?;
-                        let mut max_end = HEADER_LEN;
-                        let mut rc = 0usize;
-                        for (sid, (off, len, flags)) in slots.enumerate() {
-                            rc += 1;
-                            if flags != 0 {
-                                continue;
-                            }
-                            let start = off as usize;
-                            let end = start + len as usize;
-                            let payload = &page[start..end];
-                            let (rowid, mut rec) =
-                                deserialize_record_for_page(payload, &tbl.columns).map_err(
-                                    |e| CatalogError::InvalidMetadata {
-                                        path: path.clone(),
-                                        source: Box::new(std::io::Error::other(e)),
-                                    },
-                                )?;
-                            rec.id = rowid;
-                            if rowid > max_rowid {
-                                max_rowid = rowid;
-                            }
-                            state.row_index.insert(
-                                rowid,
-                                TupleLoc {
-                                    seg: 1,
-                                    page_id: pid,
-                                    slot_id: sid as u16,
-                                    flags,
-                                },
-                            );
-                            if end > max_end {
-                                max_end = end;
                             }
+                        })?;
+                        if rowid > max_rowid {
+                            max_rowid = rowid;
+                        }
+                        row_index.insert(
+                            rowid,
+                            TupleLoc {
+                                seg,
+                                page_id: pid,
+                                slot_id: sid as u16,
+                                flags,
+                            },
+                        );
+                        if end > max_end {
+                            max_end = end;
                         }
-
-                        let slot_dir_start = PAGE_SIZE - rc * SLOT_LEN;
-                        let free = slot_dir_start.saturating_sub(max_end);
-                        state.free_space.insert(pid, free);
-                        state.next_page_id = state.next_page_id.max(pid + 1);
                     }
+
+                    let slot_dir_start = PAGE_SIZE - rc * SLOT_LEN;
+                    free_space.insert((seg, pid), slot_dir_start.saturating_sub(max_end));
                 }
 
+                let state = self
+                    .table_states
+                    .get_mut(&tname)
+                    .expect("state just inserted");
+                state.row_index = row_index;
+                state.free_space = free_space;
                 state.next_row_id = max_rowid.saturating_add(1);
-                self.table_states.insert(tname.clone(), state);
             }
         }
         Ok(())
@@ -354,12 +529,11 @@ impl Catalog for FileCatalog {
                 }
             };
             let path = entry.path();
-            if path.is_dir() {
-                if let Some(name_os) = path.file_name() {
-                    if let Some(name) = name_os.to_str() {
-                        out.push(name.to_string());
-                    }
-                }
+            if path.is_dir()
+                && let Some(name_os) = path.file_name()
+                && let Some(name) = name_os.to_str()
+            {
+                out.push(name.to_string());
             }
         }
 
@@ -368,6 +542,10 @@ impl Catalog for FileCatalog {
     }
 
     fn save_table(&mut self, table_name: &str) -> Result<()> {
+        // save_table rewrites heap.0001 wholesale, so older WAL images must
+        // not be replayed on top of it.
+        self.checkpoint()?;
+
         let db = match &self.current_db {
             Some(db) => db,
             None => return Err(CatalogError::NoCurrentDatabase),
@@ -418,12 +596,10 @@ impl Catalog for FileCatalog {
             })?;
 
         for (page_id, page) in table.pages() {
-            let bytes = page
-                .to_bytes(table.columns())
-                .map_err(|e| CatalogError::WriteFile {
-                    path: seg_path.clone(),
-                    source: std::io::Error::other(e),
-                })?;
+            let bytes = page.to_bytes(table).map_err(|e| CatalogError::WriteFile {
+                path: seg_path.clone(),
+                source: std::io::Error::other(e),
+            })?;
 
             let offset = (*page_id as u64) * (storage::page::PAGE_SIZE as u64);
             seg.seek(SeekFrom::Start(offset))
@@ -443,6 +619,7 @@ impl Catalog for FileCatalog {
             source,
         })?;
         drop(seg);
+        self.pool.get_mut().discard_file(&seg_path);
 
         let dir_f = OpenOptions::new()
             .read(true)
@@ -459,43 +636,24 @@ impl Catalog for FileCatalog {
         Ok(())
     }
 
-    fn seq_scan_pages(&self, table_name: &str) -> Result<Vec<[u8; storage::page::PAGE_SIZE]>> {
-        let db = self
-            .current_db
-            .as_ref()
-            .ok_or(CatalogError::NoCurrentDatabase)?;
-        let seg_path = self
-            .root_dir
-            .join(db)
-            .join("tables")
-            .join(table_name)
-            .join("data")
-            .join("heap.0001");
-        if !seg_path.exists() {
-            return Ok(Vec::new());
-        }
-        let mut f = OpenOptions::new()
-            .read(true)
-            .open(&seg_path)
-            .map_err(|source| CatalogError::OpenFile {
-                path: seg_path.clone(),
-                source,
-            })?;
-        let mut pages = Vec::new();
-        loop {
-            let mut buf = [0u8; PAGE_SIZE];
-            match f.read_exact(&mut buf) {
-                Ok(_) => pages.push(buf),
-                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
-                Err(source) => {
-                    return Err(CatalogError::ReadFile {
-                        path: seg_path.clone(),
-                        source,
-                    });
-                }
-            }
+    fn scan_pages(&self, table_name: &str) -> Result<PageScan<'_>> {
+        if self.current_db.is_none() {
+            return Err(CatalogError::NoCurrentDatabase);
         }
-        Ok(pages)
+        let seg_pages = self
+            .table_states
+            .get(table_name)
+            .map(|st| st.seg_pages.clone())
+            .unwrap_or_default();
+        let pages = seg_pages
+            .into_iter()
+            .zip(1u32..)
+            .flat_map(|(count, seg)| (0..count).map(move |pid| (seg, pid)));
+        let table_name = table_name.to_string();
+        Ok(Box::new(pages.map(move |(seg, pid)| {
+            self.read_page(&table_name, seg, pid)
+                .map(|page| (seg, pid, page))
+        })))
     }
 
     fn next_row_id(&mut self, table_name: &str) -> Result<RowId> {
@@ -509,6 +667,13 @@ impl Catalog for FileCatalog {
         Ok(rid)
     }
 
+    fn next_auto_value(&mut self, table_name: &str, above: i64) -> Result<i64> {
+        self.write_unit(|cat| {
+            cat.advance_sequence(table_name, above)?;
+            cat.stage_next_auto(table_name)
+        })
+    }
+
     fn get_tuple_loc(&self, table_name: &str, row_id: RowId) -> Result<Option<TupleLoc>> {
         let state = match self.table_states.get(table_name) {
             Some(s) => s,
@@ -523,30 +688,359 @@ impl Catalog for FileCatalog {
         row_id: RowId,
         rec: &storage::Record,
     ) -> Result<TupleLoc> {
+        self.write_unit(|cat| cat.stage_append(table_name, row_id, rec))
+    }
+
+    fn append_records(
+        &mut self,
+        table_name: &str,
+        rows: &[(RowId, Record)],
+    ) -> Result<Vec<TupleLoc>> {
+        self.write_unit(|cat| cat.stage_append_batch(table_name, rows))
+    }
+
+    fn update_record(
+        &mut self,
+        table_name: &str,
+        old: TupleLoc,
+        row_id: RowId,
+        rec: &storage::Record,
+    ) -> Result<TupleLoc> {
+        self.write_unit(|cat| cat.stage_update(table_name, old, row_id, rec))
+    }
+
+    fn tombstone(&mut self, table_name: &str, old: TupleLoc) -> Result<()> {
+        self.write_unit(|cat| cat.stage_tombstone(table_name, old))
+    }
+
+    fn fetch_record(&self, table_name: &str, loc: TupleLoc) -> Result<Option<Record>> {
+        self.read_record(table_name, loc)
+    }
+
+    fn create_index(&mut self, table_name: &str, index_name: &str, column: &str) -> Result<()> {
+        if self.txn_snapshot.is_some() {
+            return Err(CatalogError::TransactionActive);
+        }
+        self.build_index(table_name, index_name, column, false)
+    }
+
+    fn drop_index(&mut self, index_name: &str) -> Result<()> {
+        if self.txn_snapshot.is_some() {
+            return Err(CatalogError::TransactionActive);
+        }
+        let Some(table_name) = self.find_index(index_name) else {
+            return Err(CatalogError::IndexDoesNotExist {
+                name: index_name.to_string(),
+            });
+        };
+        let backs_constraint = self.table_states[&table_name]
+            .indexes
+            .iter()
+            .any(|m| m.name == index_name && m.unique);
+        if backs_constraint {
+            return Err(CatalogError::IndexBacksConstraint {
+                name: index_name.to_string(),
+            });
+        }
+        self.remove_index(&table_name, index_name)
+    }
+
+    fn add_column(&mut self, table_name: &str, column: Column) -> Result<()> {
+        self.alter_add_column(table_name, column)
+    }
+
+    fn drop_column(&mut self, table_name: &str, column: &str) -> Result<()> {
+        self.alter_drop_column(table_name, column)
+    }
+
+    fn rename_column(&mut self, table_name: &str, from: &str, to: &str) -> Result<()> {
+        self.alter_rename_column(table_name, from, to)
+    }
+
+    fn decode_row(&self, table_name: &str, flags: u8, payload: &[u8]) -> Result<(RowId, Record)> {
         let tbl = self
             .tables
             .get(table_name)
             .ok_or_else(|| CatalogError::TableDoesNotExist {
                 name: table_name.to_string(),
             })?;
-        let payload = serialize_record_for_page(row_id, rec, &tbl.columns).map_err(|e| {
-            CatalogError::InvalidMetadata {
-                path: self.heap_path(table_name),
+        tbl.decode_row(flags, payload)
+            .map_err(|e| CatalogError::InvalidMetadata {
+                path: self.table_dir(table_name).join("data"),
                 source: Box::new(std::io::Error::other(e)),
+            })
+    }
+
+    fn index_lookup(
+        &self,
+        table_name: &str,
+        column: &str,
+        lo: Bound<&ASTValue>,
+        hi: Bound<&ASTValue>,
+    ) -> Result<Option<Vec<RowId>>> {
+        let Some(meta) = self
+            .table_states
+            .get(table_name)
+            .and_then(|st| st.indexes.iter().find(|m| m.column == column))
+        else {
+            return Ok(None);
+        };
+        let Some((lo, hi)) = key_range(lo, hi) else {
+            return Ok(Some(Vec::new()));
+        };
+        let rows = self.btree_range(
+            table_name,
+            meta,
+            lo.as_ref().map(|k| k.as_slice()),
+            hi.as_ref().map(|k| k.as_slice()),
+        )?;
+        Ok(Some(rows))
+    }
+
+    fn index_name(&self, table_name: &str, column: &str) -> Option<String> {
+        self.table_states
+            .get(table_name)
+            .and_then(|st| st.indexes.iter().find(|m| m.column == column))
+            .map(|m| m.name.clone())
+    }
+
+    fn begin_transaction(&mut self) -> Result<()> {
+        if self.current_db.is_none() {
+            return Err(CatalogError::NoCurrentDatabase);
+        }
+        if self.txn_snapshot.is_some() {
+            return Err(CatalogError::TransactionActive);
+        }
+        self.txn_snapshot = Some(self.table_states.clone());
+        Ok(())
+    }
+
+    fn commit_transaction(&mut self) -> Result<()> {
+        if self.txn_snapshot.is_none() {
+            return Err(CatalogError::NoTransaction);
+        }
+        if self.txn_aborted {
+            self.rollback_transaction()?;
+            return Err(CatalogError::TransactionAborted);
+        }
+        let res = self.commit_writes();
+        if res.is_err() {
+            self.rollback_transaction()?;
+            return res;
+        }
+        self.txn_snapshot = None;
+        Ok(())
+    }
+
+    fn rollback_transaction(&mut self) -> Result<()> {
+        let snapshot = self
+            .txn_snapshot
+            .take()
+            .ok_or(CatalogError::NoTransaction)?;
+        self.table_states = snapshot;
+        self.write_set.clear();
+        self.txn_aborted = false;
+        Ok(())
+    }
+
+    fn abort_transaction(&mut self) -> Result<()> {
+        let snapshot = self
+            .txn_snapshot
+            .as_ref()
+            .ok_or(CatalogError::NoTransaction)?;
+        self.table_states = snapshot.clone();
+        self.write_set.clear();
+        self.txn_aborted = true;
+        Ok(())
+    }
+
+    fn in_transaction(&self) -> bool {
+        self.txn_snapshot.is_some()
+    }
+
+    fn transaction_aborted(&self) -> bool {
+        self.txn_aborted
+    }
+}
+
+impl FileCatalog {
+    // Create `index_name` over `column` and fill it from the live rows.
+    pub(crate) fn build_index(
+        &mut self,
+        table_name: &str,
+        index_name: &str,
+        column: &str,
+        unique: bool,
+    ) -> Result<()> {
+        if self.current_db.is_none() {
+            return Err(CatalogError::NoCurrentDatabase);
+        }
+        if !is_valid_ident(index_name) {
+            return Err(CatalogError::InvalidName {
+                name: index_name.to_string(),
+            });
+        }
+        let Some(table) = self.get_table(table_name) else {
+            return Err(CatalogError::TableDoesNotExist {
+                name: table_name.to_string(),
+            });
+        };
+        if !table.columns.iter().any(|c| c.name == column) {
+            return Err(CatalogError::ColumnDoesNotExist {
+                table: table_name.to_string(),
+                column: column.to_string(),
+            });
+        }
+        if self.find_index(index_name).is_some() {
+            return Err(CatalogError::IndexExists {
+                name: index_name.to_string(),
+            });
+        }
+
+        let meta = IndexMeta {
+            name: index_name.to_string(),
+            column: column.to_string(),
+            root: 1,
+            page_count: 2,
+            unique,
+        };
+        self.write_unit(|cat| {
+            let state = cat
+                .table_states
+                .get_mut(table_name)
+                .expect("no table state");
+            state.indexes.push(meta.clone());
+            let rows: Vec<TupleLoc> = state.row_index.values().copied().collect();
+
+            cat.init_index_file(table_name, &meta)?;
+            for loc in rows {
+                if let Some(rec) = cat.read_record(table_name, loc)? {
+                    cat.check_unique(table_name, rec.id, &rec)?;
+                    cat.index_insert_row(table_name, rec.id, &rec)?;
+                }
             }
+            Ok(())
+        })
+    }
+
+    // Delete an index file and forget it.
+    pub(crate) fn remove_index(&mut self, table_name: &str, index_name: &str) -> Result<()> {
+        // Replaying older log records would recreate the file.
+        self.checkpoint()?;
+
+        let db = self
+            .current_db
+            .as_ref()
+            .ok_or(CatalogError::NoCurrentDatabase)?;
+        let path = self
+            .root_dir
+            .join(db)
+            .join(index_rel_path(table_name, index_name));
+        self.pool.get_mut().discard_file(&path);
+        fs::remove_file(&path).map_err(|source| CatalogError::WriteFile {
+            path: path.clone(),
+            source,
         })?;
-        let need = payload.len();
+        if let Some(st) = self.table_states.get_mut(table_name) {
+            st.indexes.retain(|m| m.name != index_name);
+        }
+        Ok(())
+    }
+
+    fn stage_append(
+        &mut self,
+        table_name: &str,
+        row_id: RowId,
+        rec: &storage::Record,
+    ) -> Result<TupleLoc> {
+        let mut locs = self.stage_append_batch(table_name, &[(row_id, rec.clone())])?;
+        Ok(locs.remove(0))
+    }
+
+    // Append rows in order, filling the heap page in hand before picking the
+    // next one, so each page is read and staged once per batch.
+    fn stage_append_batch(
+        &mut self,
+        table_name: &str,
+        rows: &[(RowId, Record)],
+    ) -> Result<Vec<TupleLoc>> {
+        let mut current = None;
+        let mut locs = Vec::with_capacity(rows.len());
+        for (row_id, rec) in rows {
+            self.check_unique(table_name, *row_id, rec)?;
+            self.check_references(table_name, rec)?;
+            self.bump_sequence(table_name, rec)?;
+            locs.push(self.place_record(table_name, *row_id, rec, &mut current)?);
+            self.index_insert_row(table_name, *row_id, rec)?;
+        }
+        if let Some((seg, pid, buf)) = current {
+            self.write_page(table_name, seg, pid, &buf)?;
+        }
+        Ok(locs)
+    }
+
+    fn stage_heap_append(
+        &mut self,
+        table_name: &str,
+        row_id: RowId,
+        rec: &storage::Record,
+    ) -> Result<TupleLoc> {
+        let mut current = None;
+        let loc = self.place_record(table_name, row_id, rec, &mut current)?;
+        if let Some((seg, pid, buf)) = current {
+            self.write_page(table_name, seg, pid, &buf)?;
+        }
+        Ok(loc)
+    }
 
-        let pid = self.choose_page_for(table_name, need)?;
-        let mut buf = self.read_page(table_name, pid)?;
-        let slot_id =
-            page_append(&mut buf, &payload).map_err(|e| CatalogError::InvalidMetadata {
-                path: self.heap_path(table_name),
+    // Append one record to the page in `current` if it fits; otherwise stage
+    // that page and load the one the FSM picks. The caller stages the last
+    // page left in `current`.
+    fn place_record(
+        &mut self,
+        table_name: &str,
+        row_id: RowId,
+        rec: &storage::Record,
+        current: &mut Option<(u32, u32, [u8; PAGE_SIZE])>,
+    ) -> Result<TupleLoc> {
+        let tbl = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| CatalogError::TableDoesNotExist {
+                name: table_name.to_string(),
+            })?;
+        let payload = tbl
+            .encode_row(row_id, rec)
+            .map_err(|e| CatalogError::InvalidMetadata {
+                path: self.table_dir(table_name).join("data"),
                 source: Box::new(std::io::Error::other(e)),
             })?;
+        let need = payload.len();
 
-        self.write_page(table_name, pid, &buf)?;
-        let heap_path = self.heap_path(table_name);
+        let fits = current.as_ref().is_some_and(|(seg, pid, _)| {
+            self.table_states[table_name]
+                .free_space
+                .get(&(*seg, *pid))
+                .is_some_and(|free| *free >= need + SLOT_LEN)
+        });
+        if !fits {
+            if let Some((seg, pid, buf)) = current.take() {
+                self.write_page(table_name, seg, pid, &buf)?;
+            }
+            let (seg, pid) = self.choose_page_for(table_name, need)?;
+            *current = Some((seg, pid, self.read_page(table_name, seg, pid)?));
+        }
+        let (seg, pid, buf) = current.as_mut().expect("page in hand");
+        let (seg, pid) = (*seg, *pid);
+        let slot_id = page_append(buf, &payload, SLOT_VERSIONED).map_err(|e| {
+            CatalogError::InvalidMetadata {
+                path: self.heap_path(table_name, seg),
+                source: Box::new(std::io::Error::other(e)),
+            }
+        })?;
+        let buf = *buf;
+
+        let heap_path = self.heap_path(table_name, seg);
 
         // Update state
         let st = self.table_states.get_mut(table_name).expect("no state");
@@ -566,19 +1060,19 @@ impl Catalog for FileCatalog {
         }
         let slot_dir_start = PAGE_SIZE - rc * SLOT_LEN;
         let free = slot_dir_start.saturating_sub(max_end);
-        st.free_space.insert(pid, free);
+        st.free_space.insert((seg, pid), free);
 
         let loc = TupleLoc {
-            seg: 1,
+            seg,
             page_id: pid,
             slot_id,
-            flags: 0,
+            flags: SLOT_VERSIONED,
         };
         st.row_index.insert(row_id, loc);
         Ok(loc)
     }
 
-    fn update_record(
+    pub(crate) fn stage_update(
         &mut self,
         table_name: &str,
         old: TupleLoc,
@@ -591,43 +1085,52 @@ impl Catalog for FileCatalog {
             .ok_or_else(|| CatalogError::TableDoesNotExist {
                 name: table_name.to_string(),
             })?;
-        let payload = serialize_record_for_page(row_id, rec, &tbl.columns).map_err(|e| {
-            CatalogError::InvalidMetadata {
-                path: self.heap_path(table_name),
+        let payload = tbl
+            .encode_row(row_id, rec)
+            .map_err(|e| CatalogError::InvalidMetadata {
+                path: self.heap_path(table_name, old.seg),
                 source: Box::new(std::io::Error::other(e)),
-            }
-        })?;
+            })?;
+        self.check_unique(table_name, row_id, rec)?;
+        self.check_references(table_name, rec)?;
+        let old_rec = self.read_record(table_name, old)?;
+        if let Some(old_rec) = &old_rec {
+            self.check_referenced_update(table_name, old_rec, rec)?;
+        }
 
         // Try in-place overwrite
-        let mut buf = self.read_page(table_name, old.page_id)?;
-        match page_overwrite_if_fits(&mut buf, old.slot_id, &payload) {
+        let mut buf = self.read_page(table_name, old.seg, old.page_id)?;
+        match page_overwrite_if_fits(&mut buf, old.slot_id, &payload, SLOT_VERSIONED) {
             Ok(true) => {
-                self.write_page(table_name, old.page_id, &buf)?;
+                self.write_page(table_name, old.seg, old.page_id, &buf)?;
                 // State unchanged except free_space if you wish to re-evaluate (optional).
                 let loc = TupleLoc {
-                    seg: 1,
+                    seg: old.seg,
                     page_id: old.page_id,
                     slot_id: old.slot_id,
-                    flags: 0,
+                    flags: SLOT_VERSIONED,
                 };
                 self.table_states
                     .get_mut(table_name)
                     .unwrap()
                     .row_index
                     .insert(row_id, loc);
+                if let Some(old_rec) = old_rec {
+                    self.index_update_row(table_name, row_id, &old_rec, rec)?;
+                }
                 Ok(loc)
             }
             Ok(false) => {
                 // Append new version; then tombstone old
-                let new_loc = self.append_record(table_name, row_id, rec)?;
-                let mut old_buf = self.read_page(table_name, old.page_id)?;
+                let new_loc = self.stage_heap_append(table_name, row_id, rec)?;
+                let mut old_buf = self.read_page(table_name, old.seg, old.page_id)?;
                 page_set_tombstone(&mut old_buf, old.slot_id).map_err(|e| {
                     CatalogError::InvalidMetadata {
-                        path: self.heap_path(table_name),
+                        path: self.heap_path(table_name, old.seg),
                         source: Box::new(std::io::Error::other(e)),
                     }
                 })?;
-                self.write_page(table_name, old.page_id, &old_buf)?;
+                self.write_page(table_name, old.seg, old.page_id, &old_buf)?;
 
                 // Update index
                 self.table_states
@@ -635,22 +1138,32 @@ impl Catalog for FileCatalog {
                     .unwrap()
                     .row_index
                     .insert(row_id, new_loc);
+                match old_rec {
+                    Some(old_rec) => self.index_update_row(table_name, row_id, &old_rec, rec)?,
+                    None => self.index_insert_row(table_name, row_id, rec)?,
+                }
                 Ok(new_loc)
             }
             Err(e) => Err(CatalogError::InvalidMetadata {
-                path: self.heap_path(table_name),
+                path: self.heap_path(table_name, old.seg),
                 source: Box::new(std::io::Error::other(e)),
             }),
         }
     }
 
-    fn tombstone(&mut self, table_name: &str, old: TupleLoc) -> Result<()> {
-        let mut buf = self.read_page(table_name, old.page_id)?;
+    pub(crate) fn stage_tombstone(&mut self, table_name: &str, old: TupleLoc) -> Result<()> {
+        // Already gone, e.g. removed by an ON DELETE CASCADE earlier in the
+        // same statement.
+        let Some(old_rec) = self.read_record(table_name, old)? else {
+            return Ok(());
+        };
+        self.index_delete_row(table_name, old_rec.id, &old_rec)?;
+        let mut buf = self.read_page(table_name, old.seg, old.page_id)?;
         page_set_tombstone(&mut buf, old.slot_id).map_err(|e| CatalogError::InvalidMetadata {
-            path: self.heap_path(table_name),
+            path: self.heap_path(table_name, old.seg),
             source: Box::new(std::io::Error::other(e)),
         })?;
-        self.write_page(table_name, old.page_id, &buf)?;
+        self.write_page(table_name, old.seg, old.page_id, &buf)?;
 
         // Update index (mark dead by removing)
         if let Some(st) = self.table_states.get_mut(table_name) {
@@ -664,88 +1177,316 @@ impl Catalog for FileCatalog {
                 st.row_index.remove(&rid);
             }
         }
-        Ok(())
+        self.apply_on_delete(table_name, &old_rec)
     }
 }
 
 impl FileCatalog {
-    fn table_dir(&self, table_name: &str) -> PathBuf {
+    pub(crate) fn table_dir(&self, table_name: &str) -> PathBuf {
         let db = self.current_db.as_ref().expect("No current DB");
         self.root_dir.join(db).join("tables").join(table_name)
     }
 
-    fn heap_path(&self, table_name: &str) -> PathBuf {
-        self.table_dir(table_name).join("data").join("heap.0001")
+    // Replace the table's schema.tbl atomically.
+    pub(crate) fn store_schema(&self, table: &Table) -> Result<()> {
+        let table_dir = self.table_dir(&table.name);
+        atomic_write_file(
+            &table_dir.join("schema.tmp"),
+            &table_dir.join("schema.tbl"),
+            &encode_schema(table),
+        )
     }
 
-    fn read_page(&self, table_name: &str, page_id: u32) -> Result<[u8; PAGE_SIZE]> {
-        let path = self.heap_path(table_name);
-        let mut f = OpenOptions::new()
-            .read(true)
-            .open(&path)
-            .map_err(|source| CatalogError::OpenFile {
-                path: path.clone(),
-                source,
-            })?;
-        let mut buf = [0u8; PAGE_SIZE];
-        let off = (page_id as u64) * (PAGE_SIZE as u64);
-        f.seek(SeekFrom::Start(off))
-            .map_err(|source| CatalogError::SeekFile {
-                path: path.clone(),
-                source,
-            })?;
-        f.read_exact(&mut buf)
-            .map_err(|source| CatalogError::ReadFile {
-                path: path.clone(),
-                source,
-            })?;
-        Ok(buf)
+    // Where removed directories of the current database pass on their way
+    // out; see remove_dir_via_trash.
+    fn trash_dir(&self) -> PathBuf {
+        let db = self.current_db.as_ref().expect("No current DB");
+        self.root_dir.join(db).join(TRASH_DIR)
     }
 
-    fn write_page(&self, table_name: &str, page_id: u32, buf: &[u8; PAGE_SIZE]) -> Result<()> {
-        let path = self.heap_path(table_name);
+    // Complete a TRUNCATE whose heap directory is already gone: reset every
+    // index to a lone empty root, cut the stale pages off the index files,
+    // and only then create the empty heap directory. Running it again after
+    // a crash is harmless.
+    fn finish_truncate(&mut self, name: &str) -> Result<()> {
+        let metas = self.write_unit(|cat| {
+            let state = cat.table_states.get_mut(name).expect("no table state");
+            for meta in &mut state.indexes {
+                meta.root = 1;
+                meta.page_count = 2;
+            }
+            let metas = state.indexes.clone();
+            metas
+                .iter()
+                .try_for_each(|meta| cat.init_index_file(name, meta))?;
+            Ok(metas)
+        })?;
+        self.checkpoint()?;
 
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent).map_err(|source| CatalogError::CreateDir {
-                path: parent.to_path_buf(),
-                source,
-            })?;
+        let db_dir = self
+            .root_dir
+            .join(self.current_db.as_ref().expect("No current DB"));
+        for meta in &metas {
+            let path = db_dir.join(index_rel_path(name, &meta.name));
+            self.pool.get_mut().discard_file(&path);
+            OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .and_then(|f| f.set_len(2 * PAGE_SIZE as u64))
+                .map_err(|source| CatalogError::WriteFile {
+                    path: path.clone(),
+                    source,
+                })?;
         }
 
-        let mut f = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&path)
-            .map_err(|source| CatalogError::OpenFile {
-                path: path.clone(),
-                source,
-            })?;
-        let off = (page_id as u64) * (PAGE_SIZE as u64);
-        f.seek(SeekFrom::Start(off))
-            .map_err(|source| CatalogError::SeekFile {
-                path: path.clone(),
-                source,
-            })?;
-        f.write_all(buf).map_err(|source| CatalogError::WriteFile {
-            path: path.clone(),
+        let table_dir = self.table_dir(name);
+        let data_dir = table_dir.join("data");
+        fs::create_dir_all(&data_dir).map_err(|source| CatalogError::CreateDir {
+            path: data_dir.clone(),
             source,
         })?;
-        f.flush()
-            .map_err(|source| CatalogError::WriteFile { path, source })?;
+        fsync_dir(&table_dir)
+    }
+
+    // Check that `table_name` may be dropped or truncated now.
+    fn table_to_remove(&mut self, table_name: &str) -> Result<()> {
+        if self.txn_snapshot.is_some() {
+            return Err(CatalogError::TransactionActive);
+        }
+        if self.current_db.is_none() {
+            return Err(CatalogError::NoCurrentDatabase);
+        }
+        if self.get_table(table_name).is_none() {
+            return Err(CatalogError::TableDoesNotExist {
+                name: table_name.to_string(),
+            });
+        }
         Ok(())
     }
 
-    // Create a zeroed page at the next page_id in state and return page_id
-    fn allocate_new_page(&mut self, table_name: &str) -> Result<u32> {
-        let pid = {
-            let state = self
-                .table_states
-                .get_mut(table_name)
-                .expect("no table state");
-            state.next_page_id
+    // Rewrite metadata.mdb with the current number of tables.
+    fn store_tables_count(&self) -> Result<()> {
+        let db = self
+            .current_db
+            .as_ref()
+            .ok_or(CatalogError::NoCurrentDatabase)?;
+        let db_dir = self.root_dir.join(db);
+        let meta_path = db_dir.join("metadata.mdb");
+        let bytes = fs::read(&meta_path).map_err(|source| CatalogError::ReadFile {
+            path: meta_path.clone(),
+            source,
+        })?;
+        let meta = decode_meta(&bytes).map_err(|source| CatalogError::InvalidMetadata {
+            path: meta_path.clone(),
+            source: Box::new(source),
+        })?;
+        let tables_count = u32::try_from(self.list_tables()?.len()).unwrap_or(u32::MAX);
+        atomic_write_file(
+            &db_dir.join("metadata.tmp"),
+            &meta_path,
+            &encode_meta(&meta.name, meta.created_at, tables_count),
+        )
+    }
+
+    fn heap_path(&self, table_name: &str, seg: u32) -> PathBuf {
+        let db = self.current_db.as_ref().expect("No current DB");
+        self.root_dir.join(db).join(heap_rel_path(table_name, seg))
+    }
+
+    fn read_page(&self, table_name: &str, seg: u32, page_id: u32) -> Result<[u8; PAGE_SIZE]> {
+        self.read_file_page(&heap_rel_path(table_name, seg), page_id)
+    }
+
+    fn write_page(
+        &mut self,
+        table_name: &str,
+        seg: u32,
+        page_id: u32,
+        buf: &[u8; PAGE_SIZE],
+    ) -> Result<()> {
+        self.write_file_page(heap_rel_path(table_name, seg), page_id, buf)
+    }
+
+    // Table owning the named index, if any.
+    fn find_index(&self, index_name: &str) -> Option<String> {
+        self.table_states
+            .iter()
+            .find(|(_, st)| st.indexes.iter().any(|m| m.name == index_name))
+            .map(|(t, _)| t.clone())
+    }
+
+    // Decode the live tuple at `loc`; None if the slot is missing or dead.
+    pub(crate) fn read_record(&self, table_name: &str, loc: TupleLoc) -> Result<Option<Record>> {
+        let tbl = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| CatalogError::TableDoesNotExist {
+                name: table_name.to_string(),
+            })?;
+        let buf = self.read_page(table_name, loc.seg, loc.page_id)?;
+        let bad = |e: String| CatalogError::InvalidMetadata {
+            path: self.heap_path(table_name, loc.seg),
+            source: Box::new(std::io::Error::other(e)),
+        };
+        let Some((off, len, flags)) = iter_slots(&buf).map_err(bad)?.nth(loc.slot_id as usize)
+        else {
+            return Ok(None);
+        };
+        if flags & SLOT_TOMBSTONE != 0 {
+            return Ok(None);
+        }
+        let payload = &buf[off as usize..off as usize + len as usize];
+        let (_, rec) = tbl.decode_row(flags, payload).map_err(bad)?;
+        Ok(Some(rec))
+    }
+
+    // Read a page of any file under the database directory, preferring the
+    // copy staged by the current unit of work.
+    pub(crate) fn read_file_page(&self, rel: &Path, page_id: u32) -> Result<[u8; PAGE_SIZE]> {
+        if let Some(staged) = self.write_set.get(&(rel.to_path_buf(), page_id)) {
+            return Ok(**staged);
+        }
+
+        let db = self
+            .current_db
+            .as_ref()
+            .ok_or(CatalogError::NoCurrentDatabase)?;
+        let path = self.root_dir.join(db).join(rel);
+        let mut pool = self.pool.borrow_mut();
+        let fid = pool.pin(&path, page_id)?;
+        let buf = *pool.page(fid);
+        pool.unpin(fid, false);
+        Ok(buf)
+    }
+
+    // Stage a page image in the write set; it reaches its file only after the
+    // WAL holding it has been fsynced (see commit_writes).
+    pub(crate) fn write_file_page(
+        &mut self,
+        rel: PathBuf,
+        page_id: u32,
+        buf: &[u8; PAGE_SIZE],
+    ) -> Result<()> {
+        self.write_set.insert((rel, page_id), Box::new(*buf));
+        Ok(())
+    }
+
+    // Run `f` as one unit of work, like a statement in its own transaction:
+    // its staged pages are committed if it succeeds, and dropped along with
+    // every change it made to the table states if it or the commit fails.
+    // Inside a transaction both are deferred to COMMIT / ROLLBACK.
+    pub(crate) fn write_unit<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.txn_snapshot.is_some() {
+            return f(self);
+        }
+        let snapshot = self.table_states.clone();
+        let res = f(self).and_then(|v| self.commit_writes().map(|()| v));
+        if res.is_err() {
+            self.table_states = snapshot;
+            self.write_set.clear();
+        }
+        res
+    }
+
+    // Log every staged page plus a commit record, fsync the log, then hand
+    // the pages to the buffer pool, which writes them back lazily. An error
+    // means nothing was committed; once the log is synced the write stands,
+    // whatever happens after it.
+    fn commit_writes(&mut self) -> Result<()> {
+        if self.write_set.is_empty() {
+            return Ok(());
+        }
+        let db_dir = match &self.current_db {
+            Some(db) => self.root_dir.join(db),
+            None => return Err(CatalogError::NoCurrentDatabase),
         };
+        let writes: Vec<PageWrite> = std::mem::take(&mut self.write_set)
+            .into_iter()
+            .map(|((file, page_id), image)| PageWrite {
+                file,
+                page_id,
+                image,
+            })
+            .collect();
+
+        let wal = self.wal.as_mut().ok_or(CatalogError::NoCurrentDatabase)?;
+        wal.log_commit(&writes)?;
+        self.apply_committed(&db_dir, &writes);
+        Ok(())
+    }
+
+    // Make committed pages the current ones. A page the pool cannot take is
+    // written to its file directly; if even that fails the cached pages no
+    // longer match the table states, so the database is closed and the next
+    // USE rebuilds it from its files and log. A failed checkpoint is left
+    // for the next one, as the log still holds the pages.
+    fn apply_committed(&mut self, db_dir: &Path, writes: &[PageWrite]) {
+        for w in writes {
+            let path = db_dir.join(&w.file);
+            if self.pool.get_mut().put(&path, w.page_id, &w.image).is_ok() {
+                continue;
+            }
+            let written = write_page_at(&path, w.page_id, &w.image).and_then(|f| {
+                f.sync_all()
+                    .map_err(|source| CatalogError::SyncFile { path, source })
+            });
+            if written.is_err() {
+                self.close_database(db_dir);
+                return;
+            }
+        }
+        if self
+            .wal
+            .as_ref()
+            .is_some_and(|w| w.len() > WAL_CHECKPOINT_BYTES)
+        {
+            let _ = self.checkpoint();
+        }
+    }
+
+    // Forget the current database without writing anything back: every
+    // committed page not yet in its file is still in the log.
+    fn close_database(&mut self, db_dir: &Path) {
+        self.pool.get_mut().discard_dir(db_dir);
+        self.wal = None;
+        self.current_db = None;
+        self.tables.clear();
+        self.table_states.clear();
+        self.write_set.clear();
+        self.txn_snapshot = None;
+        self.txn_aborted = false;
+    }
+
+    // Make every committed page durable in its file, then drop the log
+    // records covering them.
+    fn checkpoint(&mut self) -> Result<()> {
+        self.pool.get_mut().flush_all()?;
+        if let Some(wal) = self.wal.as_mut()
+            && !wal.is_empty()
+        {
+            wal.truncate()?;
+        }
+        Ok(())
+    }
+
+    // Create an empty page at the end of the last segment, starting a new
+    // segment once the last one holds `segment_pages` pages.
+    fn allocate_new_page(&mut self, table_name: &str) -> Result<(u32, u32)> {
+        let segment_pages = self.segment_pages;
+        let state = self
+            .table_states
+            .get_mut(table_name)
+            .expect("no table state");
+        if state
+            .seg_pages
+            .last()
+            .is_none_or(|&count| count >= segment_pages)
+        {
+            state.seg_pages.push(0);
+        }
+        let seg = state.seg_pages.len() as u32;
+        let pid = *state.seg_pages.last().unwrap();
+
         let mut buf = [0u8; PAGE_SIZE];
 
         //magic "HPG0", version 1, record_count=0
@@ -753,30 +1494,30 @@ impl FileCatalog {
         buf[4..8].copy_from_slice(&1u32.to_le_bytes());
         // record_count at [12..14] already zero
 
-        self.write_page(table_name, pid, &buf)?;
+        self.write_page(table_name, seg, pid, &buf)?;
 
         let state = self
             .table_states
             .get_mut(table_name)
             .expect("no table state");
-        state.free_space.insert(pid, PAGE_SIZE - HEADER_LEN);
-        state.next_page_id = pid + 1;
-        Ok(pid)
+        state.free_space.insert((seg, pid), PAGE_SIZE - HEADER_LEN);
+        state.seg_pages[seg as usize - 1] = pid + 1;
+        Ok((seg, pid))
     }
 
-    // Pick a page_id with enough space: needed = payload.len() + SLOT_LEN
-    fn choose_page_for(&mut self, table_name: &str, need_bytes: usize) -> Result<u32> {
+    // Pick a (seg, page_id) with enough space: needed = payload.len() + SLOT_LEN
+    fn choose_page_for(&mut self, table_name: &str, need_bytes: usize) -> Result<(u32, u32)> {
         let state = self
             .table_states
             .get_mut(table_name)
             .expect("no table state");
-        if let Some((&pid, _)) = state
+        if let Some((&page, _)) = state
             .free_space
             .iter()
             .filter(|(_, free)| **free >= need_bytes + SLOT_LEN)
             .min_by_key(|(_, free)| **free)
         {
-            return Ok(pid);
+            return Ok(page);
         }
         self.allocate_new_page(table_name)
     }
@@ -797,19 +1538,83 @@ impl FileCatalog {
             path: schema_path.clone(),
             source,
         })?;
-        let (tname, cols) = decode_schema(&bytes)?;
-        let table = storage::Table::new(tname.clone(), cols);
+        let table = decode_schema(&bytes)?;
+        let tname = table.name.clone();
+        let next_auto = self.load_sequence(&tname)?;
         self.tables.insert(tname.clone(), table);
         self.table_states.entry(tname).or_insert(TableState {
             row_index: HashMap::new(),
             free_space: HashMap::new(),
-            next_page_id: 0,
+            seg_pages: Vec::new(),
             next_row_id: 1,
+            indexes: load_index_metas(&tdir)?,
+            next_auto,
         });
         Ok(true)
     }
 }
 
+// Heap segment path relative to the database directory, as recorded in the
+// WAL: tables/<table>/data/heap.NNNN (segments are numbered from 1).
+fn heap_rel_path(table_name: &str, seg: u32) -> PathBuf {
+    Path::new("tables")
+        .join(table_name)
+        .join("data")
+        .join(format!("heap.{:04}", seg))
+}
+
+// Unique indexes backing the table's PRIMARY KEY and UNIQUE columns, as
+// (index name, column): <table>_pkey and <table>_<column>_key.
+pub(crate) fn constraint_indexes(table: &Table) -> Result<Vec<(String, String)>> {
+    if table.columns.iter().filter(|c| c.primary_key).count() > 1 {
+        return Err(CatalogError::MultiplePrimaryKeys {
+            table: table.name.clone(),
+        });
+    }
+    Ok(table
+        .columns
+        .iter()
+        .filter_map(|c| {
+            if c.primary_key {
+                Some((format!("{}_pkey", table.name), c.name.clone()))
+            } else if c.unique {
+                Some((format!("{}_{}_key", table.name, c.name), c.name.clone()))
+            } else {
+                None
+            }
+        })
+        .collect())
+}
+
+// At most one AUTOINCREMENT column per table, and it must be an INTEGER.
+fn check_auto_increment(table: &Table) -> Result<()> {
+    let mut auto = table.columns.iter().filter(|c| c.auto_increment);
+    if let Some(col) = auto.next() {
+        if col.data_type != DataType::INTEGER {
+            return Err(CatalogError::InvalidAutoIncrement {
+                table: table.name.clone(),
+                reason: format!("{} must be INTEGER", col.name),
+            });
+        }
+        if auto.next().is_some() {
+            return Err(CatalogError::InvalidAutoIncrement {
+                table: table.name.clone(),
+                reason: "only one column may be AUTOINCREMENT".to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+// Database names: [A-Za-z0-9_-]{1,128}
+fn is_valid_db_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 128
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
 // Simple identifier validation: [A-Za-z_][A-Za-z0-9_]{0,127}
 fn is_valid_ident(name: &str) -> bool {
     if name.is_empty() || name.len() > 128 {
@@ -827,3 +1632,261 @@ fn is_valid_ident(name: &str) -> bool {
     }
     true
 }
+
+// Redo committed page images left in the WAL by a crash, make them durable,
+// and hand back an empty log ready for new writes.
+fn recover(db_dir: &Path) -> Result<Wal> {
+    let writes = wal::replay(db_dir)?;
+    let mut touched: HashMap<PathBuf, File> = HashMap::new();
+    for w in &writes {
+        let path = db_dir.join(&w.file);
+        let f = write_page_at(&path, w.page_id, &w.image)?;
+        touched.insert(path, f);
+    }
+    for (path, f) in touched {
+        f.sync_all()
+            .map_err(|source| CatalogError::SyncFile { path, source })?;
+    }
+
+    let mut log = Wal::open(db_dir)?;
+    if !log.is_empty() {
+        log.truncate()?;
+    }
+    Ok(log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(tag: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("meridb-cat-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // A catalog in a fresh database holding `t (id INTEGER PRIMARY KEY, v TEXT)`.
+    fn catalog_with_table(root: &Path, options: CatalogOptions) -> FileCatalog {
+        let mut cat = FileCatalog::with_options(root.to_path_buf(), options);
+        cat.create_database("db").unwrap();
+        let mut id = Column::new("id".to_string(), DataType::INTEGER, false);
+        id.primary_key = true;
+        let v = Column::new("v".to_string(), DataType::TEXT, true);
+        cat.create_table("t".to_string(), Table::new("t".to_string(), vec![id, v]))
+            .unwrap();
+        cat
+    }
+
+    fn row(id: i64, v: &str) -> Record {
+        let mut rec = Record::new(id as RowId);
+        rec.set_value("id", ASTValue::Int(id));
+        rec.set_value("v", ASTValue::String(v.to_string()));
+        rec
+    }
+
+    fn ids(cat: &FileCatalog) -> Vec<i64> {
+        let mut ids: Vec<i64> = cat.table_states["t"]
+            .row_index
+            .values()
+            .map(
+                |&loc| match cat.fetch_record("t", loc).unwrap().unwrap().get_value("id") {
+                    Some(ASTValue::Int(id)) => *id,
+                    other => panic!("unexpected id {:?}", other),
+                },
+            )
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn test_commit_and_rollback() {
+        let root = temp_root("txn");
+        let mut cat = catalog_with_table(&root, CatalogOptions::default());
+        cat.begin_transaction().unwrap();
+        cat.append_record("t", 1, &row(1, "a")).unwrap();
+        cat.commit_transaction().unwrap();
+
+        cat.begin_transaction().unwrap();
+        cat.append_record("t", 2, &row(2, "b")).unwrap();
+        assert_eq!(ids(&cat), vec![1, 2]);
+        cat.rollback_transaction().unwrap();
+        assert_eq!(ids(&cat), vec![1]);
+        assert!(!cat.in_transaction());
+        assert!(cat.rollback_transaction().is_err());
+
+        drop(cat);
+        let mut cat = FileCatalog::new(root.clone());
+        cat.use_database("db").unwrap();
+        assert_eq!(ids(&cat), vec![1]);
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_failed_statement_inside_transaction() {
+        let root = temp_root("txn-fail");
+        let mut cat = catalog_with_table(&root, CatalogOptions::default());
+        cat.begin_transaction().unwrap();
+        cat.append_record("t", 1, &row(1, "a")).unwrap();
+        assert!(cat.append_record("t", 2, &row(1, "dup")).is_err());
+        // The transaction stays open until the caller rolls it back.
+        assert!(cat.in_transaction());
+        cat.rollback_transaction().unwrap();
+        assert_eq!(ids(&cat), Vec::<i64>::new());
+
+        cat.append_record("t", 3, &row(1, "a")).unwrap();
+        assert_eq!(ids(&cat), vec![1]);
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_failed_write_outside_transaction_restores_state() {
+        let root = temp_root("unit-fail");
+        let mut cat = catalog_with_table(&root, CatalogOptions::default());
+        cat.append_record("t", 1, &row(1, "a")).unwrap();
+
+        // The second row of the batch violates the PRIMARY KEY after the
+        // first was staged.
+        let batch = [(2, row(2, "b")), (3, row(1, "dup"))];
+        assert!(cat.append_records("t", &batch).is_err());
+        let state = &cat.table_states["t"];
+        assert_eq!(state.row_index.len(), 1);
+        assert!(cat.write_set.is_empty());
+        assert_eq!(ids(&cat), vec![1]);
+        assert_eq!(
+            cat.index_lookup(
+                "t",
+                "id",
+                Bound::Included(&ASTValue::Int(2)),
+                Bound::Included(&ASTValue::Int(2))
+            )
+            .unwrap(),
+            Some(Vec::new())
+        );
+
+        cat.append_records("t", &[(4, row(2, "b"))]).unwrap();
+        assert_eq!(ids(&cat), vec![1, 2]);
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_index_names_belong_to_one_database() {
+        let root = temp_root("index-names");
+        let mut cat = catalog_with_table(&root, CatalogOptions::default());
+        cat.create_index("t", "by_v", "v").unwrap();
+        let mut u = cat.get_table("t").unwrap().clone();
+        u.name = "u".to_string();
+        cat.create_database("other").unwrap();
+        cat.create_table("u".to_string(), u).unwrap();
+        cat.create_index("u", "by_v", "v").unwrap();
+        cat.use_database("db").unwrap();
+        cat.use_database("other").unwrap();
+        assert!(cat.get_table("t").is_none());
+        cat.drop_index("by_v").unwrap();
+        cat.create_index("u", "by_v", "v").unwrap();
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_logged_commit_stands_when_the_pool_is_full() {
+        let root = temp_root("pool-full");
+        let options = CatalogOptions {
+            pool_pages: 1,
+            ..CatalogOptions::default()
+        };
+        let mut cat = catalog_with_table(&root, options);
+        let other = root.join("db").join("other.bin");
+        fs::write(&other, [0u8; PAGE_SIZE]).unwrap();
+
+        cat.begin_transaction().unwrap();
+        cat.append_record("t", 1, &row(1, "a")).unwrap();
+        // The only frame is pinned, so no committed page fits in the pool.
+        let fid = cat.pool.get_mut().pin(&other, 0).unwrap();
+        cat.commit_transaction().unwrap();
+        cat.pool.get_mut().unpin(fid, false);
+        assert!(!cat.in_transaction());
+        assert_eq!(ids(&cat), vec![1]);
+
+        let mut reopened = FileCatalog::new(root.clone());
+        reopened.use_database("db").unwrap();
+        assert_eq!(ids(&reopened), vec![1]);
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_truncate_is_finished_after_a_crash() {
+        let root = temp_root("truncate-crash");
+        let mut cat = catalog_with_table(&root, CatalogOptions::default());
+        let rows: Vec<(RowId, Record)> = (1..=50).map(|i| (i as RowId, row(i, "a"))).collect();
+        cat.append_records("t", &rows).unwrap();
+
+        // Stop right after the heap directory was moved away.
+        cat.checkpoint().unwrap();
+        let data_dir = root.join("db/tables/t/data");
+        remove_dir_via_trash(&data_dir, &cat.trash_dir()).unwrap();
+        drop(cat);
+
+        let mut cat = FileCatalog::new(root.clone());
+        cat.use_database("db").unwrap();
+        assert!(data_dir.is_dir());
+        assert_eq!(ids(&cat), Vec::<i64>::new());
+        // The primary key index no longer holds the old rows.
+        cat.check_unique("t", 1, &row(7, "b")).unwrap();
+        cat.append_records("t", &[(1, row(7, "b"))]).unwrap();
+        assert_eq!(ids(&cat), vec![7]);
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_rows_span_segments_after_reopen() {
+        let root = temp_root("segments");
+        let options = CatalogOptions {
+            segment_bytes: PAGE_SIZE as u64,
+            ..CatalogOptions::default()
+        };
+        let mut cat = catalog_with_table(&root, options);
+        let filler = "x".repeat(1000);
+        let rows: Vec<(RowId, Record)> = (1..=20).map(|i| (i as RowId, row(i, &filler))).collect();
+        cat.append_records("t", &rows).unwrap();
+        // Each one-page segment holds a few rows.
+        assert!(cat.table_states["t"].seg_pages.len() > 2);
+        cat.update_record(
+            "t",
+            cat.get_tuple_loc("t", 1).unwrap().unwrap(),
+            1,
+            &row(1, "moved"),
+        )
+        .unwrap();
+        drop(cat);
+
+        let mut cat = FileCatalog::with_options(root.clone(), options);
+        cat.use_database("db").unwrap();
+        let segs = cat.table_states["t"].seg_pages.len();
+        assert!(segs > 2);
+        assert!(
+            root.join("db/tables/t/data")
+                .join(format!("heap.{:04}", segs))
+                .is_file()
+        );
+        assert_eq!(ids(&cat), (1..=20).collect::<Vec<_>>());
+        let loc = cat.get_tuple_loc("t", 1).unwrap().unwrap();
+        assert_eq!(
+            cat.fetch_record("t", loc).unwrap().unwrap().get_value("v"),
+            Some(&ASTValue::String("moved".to_string()))
+        );
+        let scanned: usize = cat
+            .scan_pages("t")
+            .unwrap()
+            .map(|page| {
+                let (_, _, buf) = page.unwrap();
+                iter_slots(&buf)
+                    .unwrap()
+                    .filter(|&(_, _, flags)| flags & SLOT_TOMBSTONE == 0)
+                    .count()
+            })
+            .sum();
+        assert_eq!(scanned, 20);
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,456 @@
+use std::ops::Bound;
+
+use sql::ast::{ASTValue, ForeignKey, ReferentialAction};
+use storage::{
+    Record, Table,
+    btree::value_key_bounds,
+    types::{RowId, TupleLoc},
+};
+
+use crate::{
+    error::{CatalogError, Result},
+    file_catalog::FileCatalog,
+    index::display_value,
+};
+
+// FOREIGN KEY / REFERENCES constraints.
+//
+// A foreign key is one child column referencing a PRIMARY KEY or UNIQUE
+// column of the parent, so every parent probe is a unique-index lookup.
+// Child rows are found through an index on the child column when there is
+// one, otherwise by scanning the child table. NULL references are never
+// checked.
+impl FileCatalog {
+    /// Validate the foreign keys of a table about to be created and fill in
+    /// omitted referenced columns with the parent's primary key.
+    pub(crate) fn resolve_foreign_keys(&self, table: &mut Table) -> Result<()> {
+        let mut foreign_keys = std::mem::take(&mut table.foreign_keys);
+        let res = foreign_keys
+            .iter_mut()
+            .try_for_each(|fk| self.resolve_foreign_key(table, fk));
+        table.foreign_keys = foreign_keys;
+        res
+    }
+
+    fn resolve_foreign_key(&self, table: &Table, fk: &mut ForeignKey) -> Result<()> {
+        let invalid = |reason: String| CatalogError::InvalidForeignKey {
+            table: table.name.clone(),
+            reason,
+        };
+        let parent = if fk.ref_table == table.name {
+            table
+        } else {
+            self.tables
+                .get(&fk.ref_table)
+                .ok_or_else(|| CatalogError::TableDoesNotExist {
+                    name: fk.ref_table.clone(),
+                })?
+        };
+        if fk.ref_columns.is_empty() {
+            let pk = parent
+                .columns
+                .iter()
+                .find(|c| c.primary_key)
+                .ok_or_else(|| {
+                    invalid(format!("{} has no PRIMARY KEY to reference", parent.name))
+                })?;
+            fk.ref_columns.push(pk.name.clone());
+        }
+        if fk.columns.len() != 1 || fk.ref_columns.len() != 1 {
+            return Err(invalid(
+                "composite foreign keys are not supported".to_string(),
+            ));
+        }
+
+        let column = table
+            .columns
+            .iter()
+            .find(|c| c.name == fk.columns[0])
+            .ok_or_else(|| CatalogError::ColumnDoesNotExist {
+                table: table.name.clone(),
+                column: fk.columns[0].clone(),
+            })?;
+        let ref_column = parent
+            .columns
+            .iter()
+            .find(|c| c.name == fk.ref_columns[0])
+            .ok_or_else(|| CatalogError::ColumnDoesNotExist {
+                table: parent.name.clone(),
+                column: fk.ref_columns[0].clone(),
+            })?;
+        if !ref_column.primary_key && !ref_column.unique {
+            return Err(invalid(format!(
+                "{}.{} is neither PRIMARY KEY nor UNIQUE",
+                parent.name, ref_column.name
+            )));
+        }
+        if column.data_type != ref_column.data_type {
+            return Err(invalid(format!(
+                "{} is {} but {}.{} is {}",
+                column.name, column.data_type, parent.name, ref_column.name, ref_column.data_type
+            )));
+        }
+        if fk.on_delete == ReferentialAction::SetNull && !column.nullable {
+            return Err(invalid(format!(
+                "ON DELETE SET NULL needs {} to be nullable",
+                column.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fail with `ForeignKeyViolation` if `rec` references a parent row that
+    /// does not exist.
+    pub(crate) fn check_references(&self, table_name: &str, rec: &Record) -> Result<()> {
+        let Some(table) = self.tables.get(table_name) else {
+            return Ok(());
+        };
+        for fk in &table.foreign_keys {
+            let (column, ref_column) = (&fk.columns[0], &fk.ref_columns[0]);
+            let Some(value) = rec.get_value(column).filter(|v| **v != ASTValue::Null) else {
+                continue;
+            };
+            // A row may reference itself.
+            if fk.ref_table == table_name && rec.get_value(ref_column) == Some(value) {
+                continue;
+            }
+            if self
+                .rows_with_value(&fk.ref_table, ref_column, value)?
+                .is_empty()
+            {
+                return Err(CatalogError::ForeignKeyViolation {
+                    table: table_name.to_string(),
+                    column: column.clone(),
+                    value: display_value(value),
+                    ref_table: fk.ref_table.clone(),
+                    ref_column: ref_column.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Refuse to change a referenced value while child rows still point at it.
+    pub(crate) fn check_referenced_update(
+        &self,
+        table_name: &str,
+        old: &Record,
+        new: &Record,
+    ) -> Result<()> {
+        for (child, fk) in self.referencing(table_name) {
+            let ref_column = &fk.ref_columns[0];
+            let Some(before) = old.get_value(ref_column).filter(|v| **v != ASTValue::Null) else {
+                continue;
+            };
+            if new.get_value(ref_column) == Some(before) {
+                continue;
+            }
+            let rows = self.rows_with_value(&child, &fk.columns[0], before)?;
+            if rows
+                .iter()
+                .any(|&(rid, _)| !(child == table_name && rid == old.id))
+            {
+                return Err(still_referenced(table_name, &child, &fk, before));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply every ON DELETE action for the parent row `old`, which has
+    /// already been removed from its table.
+    pub(crate) fn apply_on_delete(&mut self, table_name: &str, old: &Record) -> Result<()> {
+        for (child, fk) in self.referencing(table_name) {
+            let (column, ref_column) = (&fk.columns[0], &fk.ref_columns[0]);
+            let Some(value) = old.get_value(ref_column).filter(|v| **v != ASTValue::Null) else {
+                continue;
+            };
+            let rows = self.rows_with_value(&child, column, value)?;
+            if rows.is_empty() {
+                continue;
+            }
+            match fk.on_delete {
+                ReferentialAction::Restrict => {
+                    return Err(still_referenced(table_name, &child, &fk, value));
+                }
+                ReferentialAction::Cascade => {
+                    for (_, loc) in rows {
+                        self.stage_tombstone(&child, loc)?;
+                    }
+                }
+                ReferentialAction::SetNull => {
+                    for (rid, loc) in rows {
+                        if let Some(mut rec) = self.read_record(&child, loc)? {
+                            rec.set_value(column, ASTValue::Null);
+                            self.stage_update(&child, loc, rid, &rec)?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // (child table, foreign key) for every key referencing `table_name`.
+    pub(crate) fn referencing(&self, table_name: &str) -> Vec<(String, ForeignKey)> {
+        self.tables
+            .iter()
+            .flat_map(|(child, t)| {
+                t.foreign_keys
+                    .iter()
+                    .filter(|fk| fk.ref_table == table_name)
+                    .map(move |fk| (child.clone(), fk.clone()))
+            })
+            .collect()
+    }
+
+    // Live rows of `table_name` whose `column` equals `value`.
+    fn rows_with_value(
+        &self,
+        table_name: &str,
+        column: &str,
+        value: &ASTValue,
+    ) -> Result<Vec<(RowId, TupleLoc)>> {
+        let Some(state) = self.table_states.get(table_name) else {
+            return Ok(Vec::new());
+        };
+        if let Some(meta) = state.indexes.iter().find(|m| m.column == column) {
+            let Some((lo, hi)) = value_key_bounds(value) else {
+                return Ok(Vec::new());
+            };
+            let rows = self.btree_range(
+                table_name,
+                meta,
+                Bound::Included(lo.as_slice()),
+                Bound::Included(hi.as_slice()),
+            )?;
+            return Ok(rows
+                .into_iter()
+                .filter_map(|rid| state.row_index.get(&rid).map(|loc| (rid, *loc)))
+                .collect());
+        }
+
+        let mut rows = Vec::new();
+        for (&rid, &loc) in &state.row_index {
+            if let Some(rec) = self.read_record(table_name, loc)?
+                && rec.get_value(column) == Some(value)
+            {
+                rows.push((rid, loc));
+            }
+        }
+        Ok(rows)
+    }
+}
+
+fn still_referenced(
+    table_name: &str,
+    child: &str,
+    fk: &ForeignKey,
+    value: &ASTValue,
+) -> CatalogError {
+    CatalogError::RowStillReferenced {
+        table: table_name.to_string(),
+        column: fk.ref_columns[0].clone(),
+        value: display_value(value),
+        child_table: child.to_string(),
+        child_column: fk.columns[0].clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use storage::types::Column;
+    use types::tokens::DataType;
+
+    use super::*;
+    use crate::Catalog;
+
+    fn temp_catalog(tag: &str) -> (PathBuf, FileCatalog) {
+        let root = std::env::temp_dir().join(format!("meridb-fk-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let mut cat = FileCatalog::new(root.clone());
+        cat.create_database("db").unwrap();
+        (root, cat)
+    }
+
+    // `name (id INTEGER PRIMARY KEY)`, plus `pid INTEGER NULL REFERENCES
+    // parent ON DELETE on_delete` when a parent is given.
+    fn create(cat: &mut FileCatalog, name: &str, parent: Option<(&str, ReferentialAction)>) {
+        let mut id = Column::new("id".to_string(), DataType::INTEGER, false);
+        id.primary_key = true;
+        let mut table = Table::new(name.to_string(), vec![id]);
+        if let Some((ref_table, on_delete)) = parent {
+            table
+                .columns
+                .push(Column::new("pid".to_string(), DataType::INTEGER, true));
+            table.foreign_keys.push(ForeignKey {
+                columns: vec!["pid".to_string()],
+                ref_table: ref_table.to_string(),
+                ref_columns: Vec::new(),
+                on_delete,
+            });
+        }
+        cat.create_table(name.to_string(), table).unwrap();
+    }
+
+    // A row; a child row without `pid` leaves it NULL.
+    fn record(id: i64, pid: Option<i64>) -> Record {
+        let mut rec = Record::new(id as RowId);
+        rec.set_value("id", ASTValue::Int(id));
+        if let Some(pid) = pid {
+            rec.set_value("pid", ASTValue::Int(pid));
+        }
+        rec
+    }
+
+    fn insert(cat: &mut FileCatalog, table: &str, id: i64, pid: Option<i64>) -> Result<()> {
+        cat.append_records(table, &[(id as RowId, record(id, pid))])
+            .map(drop)
+    }
+
+    fn update(cat: &mut FileCatalog, table: &str, id: i64, pid: Option<i64>) -> Result<()> {
+        let loc = cat.get_tuple_loc(table, id as RowId)?.unwrap();
+        cat.update_record(table, loc, id as RowId, &record(id, pid))
+            .map(drop)
+    }
+
+    fn delete(cat: &mut FileCatalog, table: &str, id: i64) -> Result<()> {
+        let loc = cat.get_tuple_loc(table, id as RowId)?.unwrap();
+        cat.tombstone(table, loc)
+    }
+
+    // (id, pid) of every live row, by id.
+    fn rows(cat: &FileCatalog, table: &str) -> Vec<(i64, Option<i64>)> {
+        let mut rows: Vec<_> = cat.table_states[table]
+            .row_index
+            .values()
+            .map(|&loc| {
+                let rec = cat.read_record(table, loc).unwrap().unwrap();
+                let int = |column| match rec.get_value(column) {
+                    Some(ASTValue::Int(i)) => Some(*i),
+                    _ => None,
+                };
+                (int("id").unwrap(), int("pid"))
+            })
+            .collect();
+        rows.sort_unstable();
+        rows
+    }
+
+    #[test]
+    fn test_restrict_keeps_referenced_parents() {
+        let (root, mut cat) = temp_catalog("restrict");
+        create(&mut cat, "p", None);
+        create(&mut cat, "c", Some(("p", ReferentialAction::Restrict)));
+        insert(&mut cat, "p", 1, None).unwrap();
+        insert(&mut cat, "p", 2, None).unwrap();
+        insert(&mut cat, "c", 10, Some(1)).unwrap();
+
+        assert!(matches!(
+            delete(&mut cat, "p", 1),
+            Err(CatalogError::RowStillReferenced { ref child_table, .. }) if child_table == "c"
+        ));
+        assert_eq!(rows(&cat, "p"), [(1, None), (2, None)]);
+        delete(&mut cat, "p", 2).unwrap();
+        // Once the child lets go, the parent can go too.
+        update(&mut cat, "c", 10, None).unwrap();
+        delete(&mut cat, "p", 1).unwrap();
+        assert!(rows(&cat, "p").is_empty());
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_cascade_reaches_every_level() {
+        let (root, mut cat) = temp_catalog("cascade");
+        create(&mut cat, "p", None);
+        create(&mut cat, "c", Some(("p", ReferentialAction::Cascade)));
+        create(&mut cat, "g", Some(("c", ReferentialAction::Cascade)));
+        insert(&mut cat, "p", 1, None).unwrap();
+        insert(&mut cat, "p", 2, None).unwrap();
+        for (id, pid) in [(10, 1), (11, 1), (20, 2)] {
+            insert(&mut cat, "c", id, Some(pid)).unwrap();
+        }
+        for (id, pid) in [(100, 10), (101, 11), (102, 11), (200, 20)] {
+            insert(&mut cat, "g", id, Some(pid)).unwrap();
+        }
+
+        delete(&mut cat, "p", 1).unwrap();
+        assert_eq!(rows(&cat, "p"), [(2, None)]);
+        assert_eq!(rows(&cat, "c"), [(20, Some(2))]);
+        assert_eq!(rows(&cat, "g"), [(200, Some(20))]);
+
+        // A RESTRICT further down stops the whole delete.
+        create(&mut cat, "r", Some(("g", ReferentialAction::Restrict)));
+        insert(&mut cat, "r", 1, Some(200)).unwrap();
+        assert!(delete(&mut cat, "p", 2).is_err());
+        assert_eq!(rows(&cat, "p"), [(2, None)]);
+        assert_eq!(rows(&cat, "c"), [(20, Some(2))]);
+        assert_eq!(rows(&cat, "g"), [(200, Some(20))]);
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_set_null_clears_the_reference() {
+        let (root, mut cat) = temp_catalog("set-null");
+        create(&mut cat, "p", None);
+        create(&mut cat, "c", Some(("p", ReferentialAction::SetNull)));
+        insert(&mut cat, "p", 1, None).unwrap();
+        insert(&mut cat, "p", 2, None).unwrap();
+        for (id, pid) in [(10, Some(1)), (11, Some(2)), (12, Some(1)), (13, None)] {
+            insert(&mut cat, "c", id, pid).unwrap();
+        }
+
+        delete(&mut cat, "p", 1).unwrap();
+        assert_eq!(
+            rows(&cat, "c"),
+            [(10, None), (11, Some(2)), (12, None), (13, None)]
+        );
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_dangling_references_are_refused() {
+        let (root, mut cat) = temp_catalog("dangling");
+        create(&mut cat, "p", None);
+        create(&mut cat, "c", Some(("p", ReferentialAction::Restrict)));
+        insert(&mut cat, "p", 1, None).unwrap();
+
+        assert!(matches!(
+            insert(&mut cat, "c", 10, Some(2)),
+            Err(CatalogError::ForeignKeyViolation { ref ref_table, .. }) if ref_table == "p"
+        ));
+        insert(&mut cat, "c", 10, None).unwrap();
+        insert(&mut cat, "c", 11, Some(1)).unwrap();
+        assert!(matches!(
+            update(&mut cat, "c", 11, Some(2)),
+            Err(CatalogError::ForeignKeyViolation { .. })
+        ));
+        assert!(matches!(
+            update(&mut cat, "c", 10, Some(3)),
+            Err(CatalogError::ForeignKeyViolation { .. })
+        ));
+        assert_eq!(rows(&cat, "c"), [(10, None), (11, Some(1))]);
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_rolled_back_children_do_not_hold_parents() {
+        let (root, mut cat) = temp_catalog("rollback");
+        create(&mut cat, "p", None);
+        create(&mut cat, "c", Some(("p", ReferentialAction::Restrict)));
+        cat.create_index("c", "c_pid", "pid").unwrap();
+        insert(&mut cat, "p", 1, None).unwrap();
+
+        cat.begin_transaction().unwrap();
+        insert(&mut cat, "c", 10, Some(1)).unwrap();
+        assert!(delete(&mut cat, "p", 1).is_err());
+        cat.rollback_transaction().unwrap();
+
+        delete(&mut cat, "p", 1).unwrap();
+        assert!(rows(&cat, "p").is_empty());
+        assert!(rows(&cat, "c").is_empty());
+        fs::remove_dir_all(&root).ok();
+    }
+}
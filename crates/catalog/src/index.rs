@@ -0,0 +1,609 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+    ops::Bound,
+    path::{Path, PathBuf},
+};
+
+use sql::ast::ASTValue;
+use storage::{
+    Record,
+    btree::{IndexMeta, MAX_KEY_LEN, Node, index_key, key_row_id, value_key_bounds},
+    page::PAGE_SIZE,
+    types::RowId,
+};
+
+use crate::{
+    error::{CatalogError, Result},
+    file_catalog::FileCatalog,
+};
+
+// B+Tree secondary indexes: data/<db>/tables/<table>/<index>.idx
+//
+// Pages are read and written through the catalog's write set, so index
+// changes commit (and roll back) in the same WAL unit as the heap change
+// that caused them. Deletes remove leaf entries without rebalancing; empty
+// leaves stay linked and are skipped by scans.
+const INDEX_FILE_EXT: &str = "idx";
+
+pub(crate) fn index_rel_path(table_name: &str, index_name: &str) -> PathBuf {
+    Path::new("tables")
+        .join(table_name)
+        .join(format!("{}.{}", index_name, INDEX_FILE_EXT))
+}
+
+fn corrupt(rel: &Path, e: String) -> CatalogError {
+    CatalogError::InvalidMetadata {
+        path: rel.to_path_buf(),
+        source: Box::new(std::io::Error::other(e)),
+    }
+}
+
+impl FileCatalog {
+    fn load_node(&self, rel: &Path, page_id: u32) -> Result<Node> {
+        let buf = self.read_file_page(rel, page_id)?;
+        Node::from_page(&buf).map_err(|e| corrupt(rel, e))
+    }
+
+    fn store_node(&mut self, rel: &Path, page_id: u32, node: &Node) -> Result<()> {
+        let buf = node.to_page().map_err(|e| corrupt(rel, e))?;
+        self.write_file_page(rel.to_path_buf(), page_id, &buf)
+    }
+
+    fn index_meta_mut(&mut self, table_name: &str, index_name: &str) -> Result<&mut IndexMeta> {
+        self.table_states
+            .get_mut(table_name)
+            .and_then(|st| st.indexes.iter_mut().find(|m| m.name == index_name))
+            .ok_or_else(|| CatalogError::IndexDoesNotExist {
+                name: index_name.to_string(),
+            })
+    }
+
+    // Write a fresh index file (meta page + empty root leaf) into the write set.
+    pub(crate) fn init_index_file(&mut self, table_name: &str, meta: &IndexMeta) -> Result<()> {
+        let rel = index_rel_path(table_name, &meta.name);
+        self.write_file_page(rel.clone(), 0, &meta.to_page())?;
+        self.store_node(&rel, meta.root, &Node::new_leaf())
+    }
+
+    /// Insert `key` into the named index; an identical key is left as is.
+    pub(crate) fn btree_insert(
+        &mut self,
+        table_name: &str,
+        index_name: &str,
+        key: Vec<u8>,
+    ) -> Result<()> {
+        if key.len() > MAX_KEY_LEN {
+            return Err(CatalogError::IndexKeyTooLarge {
+                len: key.len(),
+                max: MAX_KEY_LEN,
+            });
+        }
+        let mut meta = self.index_meta_mut(table_name, index_name)?.clone();
+        let rel = index_rel_path(table_name, index_name);
+
+        // Descend, remembering the path for splits.
+        let mut path: Vec<(u32, Node, usize)> = Vec::new();
+        let mut pid = meta.root;
+        let mut node = self.load_node(&rel, pid)?;
+        while !node.leaf {
+            let ci = node.child_for(&key);
+            let child = node.children[ci];
+            path.push((pid, node, ci));
+            pid = child;
+            node = self.load_node(&rel, pid)?;
+        }
+
+        let pos = match node.keys.binary_search(&key) {
+            Ok(_) => return Ok(()),
+            Err(pos) => pos,
+        };
+        node.keys.insert(pos, key);
+        if node.fits() {
+            return self.store_node(&rel, pid, &node);
+        }
+
+        // Split the leaf and push separators up as long as parents overflow.
+        let mid = split_point(&node);
+        let right_pid = allocate(&mut meta);
+        let right = Node {
+            leaf: true,
+            keys: node.keys.split_off(mid),
+            children: Vec::new(),
+            next: node.next,
+        };
+        node.next = right_pid;
+        let mut sep = right.keys[0].clone();
+        self.store_node(&rel, pid, &node)?;
+        self.store_node(&rel, right_pid, &right)?;
+        let mut new_pid = right_pid;
+
+        loop {
+            let Some((ppid, mut parent, ci)) = path.pop() else {
+                // Root split: grow the tree by one level.
+                let root_pid = allocate(&mut meta);
+                let mut root = Node::new_internal(meta.root);
+                root.keys.push(sep);
+                root.children.push(new_pid);
+                self.store_node(&rel, root_pid, &root)?;
+                meta.root = root_pid;
+                break;
+            };
+            parent.keys.insert(ci, sep);
+            parent.children.insert(ci + 1, new_pid);
+            if parent.fits() {
+                self.store_node(&rel, ppid, &parent)?;
+                break;
+            }
+
+            let mid = split_point(&parent);
+            let mut right_keys = parent.keys.split_off(mid);
+            let right_children = parent.children.split_off(mid + 1);
+            sep = right_keys.remove(0);
+            let mut right = Node::new_internal(right_children[0]);
+            right.keys = right_keys;
+            right.children = right_children;
+            new_pid = allocate(&mut meta);
+            self.store_node(&rel, ppid, &parent)?;
+            self.store_node(&rel, new_pid, &right)?;
+        }
+
+        self.write_file_page(rel, 0, &meta.to_page())?;
+        *self.index_meta_mut(table_name, index_name)? = meta;
+        Ok(())
+    }
+
+    /// Remove `key` from the named index if present.
+    pub(crate) fn btree_delete(
+        &mut self,
+        table_name: &str,
+        index_name: &str,
+        key: &[u8],
+    ) -> Result<()> {
+        let root = self.index_meta_mut(table_name, index_name)?.root;
+        let rel = index_rel_path(table_name, index_name);
+        let (pid, mut leaf) = self.find_leaf(&rel, root, Some(key))?;
+        if let Ok(pos) = leaf.keys.binary_search_by(|k| k.as_slice().cmp(key)) {
+            leaf.keys.remove(pos);
+            self.store_node(&rel, pid, &leaf)?;
+        }
+        Ok(())
+    }
+
+    /// RowIds of all keys within `(lo, hi)`, in key order.
+    pub(crate) fn btree_range(
+        &self,
+        table_name: &str,
+        meta: &IndexMeta,
+        lo: Bound<&[u8]>,
+        hi: Bound<&[u8]>,
+    ) -> Result<Vec<RowId>> {
+        let rel = index_rel_path(table_name, &meta.name);
+        let start = match lo {
+            Bound::Included(k) | Bound::Excluded(k) => Some(k),
+            Bound::Unbounded => None,
+        };
+        let (_, mut leaf) = self.find_leaf(&rel, meta.root, start)?;
+
+        let mut out = Vec::new();
+        loop {
+            for key in &leaf.keys {
+                let k = key.as_slice();
+                let above_lo = match lo {
+                    Bound::Included(l) => k >= l,
+                    Bound::Excluded(l) => k > l,
+                    Bound::Unbounded => true,
+                };
+                if !above_lo {
+                    continue;
+                }
+                let below_hi = match hi {
+                    Bound::Included(h) => k <= h,
+                    Bound::Excluded(h) => k < h,
+                    Bound::Unbounded => true,
+                };
+                if !below_hi {
+                    return Ok(out);
+                }
+                out.push(key_row_id(k));
+            }
+            if leaf.next == 0 {
+                return Ok(out);
+            }
+            leaf = self.load_node(&rel, leaf.next)?;
+        }
+    }
+
+    // Leaf that would hold `key`, or the leftmost leaf when `key` is None.
+    fn find_leaf(&self, rel: &Path, root: u32, key: Option<&[u8]>) -> Result<(u32, Node)> {
+        let mut pid = root;
+        let mut node = self.load_node(rel, pid)?;
+        while !node.leaf {
+            let ci = match key {
+                Some(k) => node.child_for(k),
+                None => 0,
+            };
+            pid = node.children[ci];
+            node = self.load_node(rel, pid)?;
+        }
+        Ok((pid, node))
+    }
+
+    /// Add `rec` to every index on `table_name`.
+    pub(crate) fn index_insert_row(
+        &mut self,
+        table_name: &str,
+        row_id: RowId,
+        rec: &Record,
+    ) -> Result<()> {
+        for (name, column) in self.index_columns(table_name) {
+            if let Some(key) = rec.get_value(&column).and_then(|v| index_key(v, row_id)) {
+                self.btree_insert(table_name, &name, key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove `rec` from every index on `table_name`.
+    pub(crate) fn index_delete_row(
+        &mut self,
+        table_name: &str,
+        row_id: RowId,
+        rec: &Record,
+    ) -> Result<()> {
+        for (name, column) in self.index_columns(table_name) {
+            if let Some(key) = rec.get_value(&column).and_then(|v| index_key(v, row_id)) {
+                self.btree_delete(table_name, &name, &key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move a row's entries from `old` to `new` in indexes whose column changed.
+    pub(crate) fn index_update_row(
+        &mut self,
+        table_name: &str,
+        row_id: RowId,
+        old: &Record,
+        new: &Record,
+    ) -> Result<()> {
+        for (name, column) in self.index_columns(table_name) {
+            let before = old.get_value(&column).unwrap_or(&ASTValue::Null);
+            let after = new.get_value(&column).unwrap_or(&ASTValue::Null);
+            if before == after {
+                continue;
+            }
+            if let Some(key) = index_key(before, row_id) {
+                self.btree_delete(table_name, &name, &key)?;
+            }
+            if let Some(key) = index_key(after, row_id) {
+                self.btree_insert(table_name, &name, key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fail with `UniqueViolation` if writing `rec` as `row_id` would repeat
+    /// a value held by another row in a unique index. NULLs are not indexed,
+    /// so any number of rows may leave a UNIQUE column NULL.
+    pub(crate) fn check_unique(&self, table_name: &str, row_id: RowId, rec: &Record) -> Result<()> {
+        let Some(state) = self.table_states.get(table_name) else {
+            return Ok(());
+        };
+        for meta in state.indexes.iter().filter(|m| m.unique) {
+            let Some(value) = rec.get_value(&meta.column) else {
+                continue;
+            };
+            let Some((lo, hi)) = value_key_bounds(value) else {
+                continue;
+            };
+            let rows = self.btree_range(
+                table_name,
+                meta,
+                Bound::Included(lo.as_slice()),
+                Bound::Included(hi.as_slice()),
+            )?;
+            if rows.iter().any(|&rid| rid != row_id) {
+                let primary_key = self.tables.get(table_name).is_some_and(|t| {
+                    t.columns
+                        .iter()
+                        .any(|c| c.name == meta.column && c.primary_key)
+                });
+                return Err(CatalogError::UniqueViolation {
+                    constraint: if primary_key { "PRIMARY KEY" } else { "UNIQUE" },
+                    table: table_name.to_string(),
+                    column: meta.column.clone(),
+                    value: display_value(value),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn index_columns(&self, table_name: &str) -> Vec<(String, String)> {
+        self.table_states
+            .get(table_name)
+            .map(|st| {
+                st.indexes
+                    .iter()
+                    .map(|m| (m.name.clone(), m.column.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+pub(crate) fn display_value(v: &ASTValue) -> String {
+    match v {
+        ASTValue::Int(i) => i.to_string(),
+        ASTValue::Float(f) => f.to_string(),
+        ASTValue::String(s) => format!("'{}'", s),
+        ASTValue::Boolean(b) => b.to_string(),
+        ASTValue::Null => "NULL".to_string(),
+        ASTValue::Decimal(_) | ASTValue::Date(_) | ASTValue::Time(_) | ASTValue::Timestamp(_) => {
+            v.to_string()
+        }
+    }
+}
+
+fn allocate(meta: &mut IndexMeta) -> u32 {
+    let pid = meta.page_count;
+    meta.page_count += 1;
+    pid
+}
+
+// Split position by bytes rather than key count so both halves fit even when
+// key sizes vary.
+fn split_point(node: &Node) -> usize {
+    let per_entry = if node.leaf { 2 } else { 6 };
+    let total: usize = node.keys.iter().map(|k| k.len() + per_entry).sum();
+    let mut acc = 0;
+    for (i, k) in node.keys.iter().enumerate() {
+        acc += k.len() + per_entry;
+        if acc * 2 >= total {
+            return (i + 1).clamp(1, node.keys.len() - 1);
+        }
+    }
+    node.keys.len() / 2
+}
+
+type KeyBound = Bound<Vec<u8>>;
+
+/// Translate value bounds into key bounds covering every row id. Returns None
+/// when a bound is NULL, since no comparison with NULL can match.
+pub(crate) fn key_range(
+    lo: Bound<&ASTValue>,
+    hi: Bound<&ASTValue>,
+) -> Option<(KeyBound, KeyBound)> {
+    let lo = match lo {
+        Bound::Included(v) => Bound::Included(value_key_bounds(v)?.0),
+        Bound::Excluded(v) => Bound::Excluded(value_key_bounds(v)?.1),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+    let hi = match hi {
+        Bound::Included(v) => Bound::Included(value_key_bounds(v)?.1),
+        Bound::Excluded(v) => Bound::Excluded(value_key_bounds(v)?.0),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+    Some((lo, hi))
+}
+
+/// Read the meta page of every index file in a table directory.
+pub(crate) fn load_index_metas(table_dir: &Path) -> Result<Vec<IndexMeta>> {
+    let mut out = Vec::new();
+    let rd = fs::read_dir(table_dir).map_err(|source| CatalogError::ReadDir {
+        path: table_dir.to_path_buf(),
+        source,
+    })?;
+    for entry in rd {
+        let entry = entry.map_err(|source| CatalogError::ReadDir {
+            path: table_dir.to_path_buf(),
+            source,
+        })?;
+        let path = entry.path();
+        if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some(INDEX_FILE_EXT) {
+            continue;
+        }
+        let mut f = File::open(&path).map_err(|source| CatalogError::OpenFile {
+            path: path.clone(),
+            source,
+        })?;
+        let mut buf = [0u8; PAGE_SIZE];
+        f.read_exact(&mut buf)
+            .map_err(|source| CatalogError::ReadFile {
+                path: path.clone(),
+                source,
+            })?;
+        let meta = IndexMeta::from_page(&buf).map_err(|e| corrupt(&path, e))?;
+        out.push(meta);
+    }
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use storage::{Table, types::Column};
+    use types::tokens::DataType;
+
+    use super::*;
+    use crate::Catalog;
+
+    // A catalog holding `t (id INTEGER PRIMARY KEY, v TEXT NULL)` with a
+    // plain index `by_v` on v, in a fresh temporary directory.
+    fn catalog_with_index(tag: &str) -> (PathBuf, FileCatalog) {
+        let root = std::env::temp_dir().join(format!("meridb-idx-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let mut cat = FileCatalog::new(root.clone());
+        cat.create_database("db").unwrap();
+        let mut id = Column::new("id".to_string(), DataType::INTEGER, false);
+        id.primary_key = true;
+        let v = Column::new("v".to_string(), DataType::TEXT, true);
+        cat.create_table("t".to_string(), Table::new("t".to_string(), vec![id, v]))
+            .unwrap();
+        cat.create_index("t", "by_v", "v").unwrap();
+        (root, cat)
+    }
+
+    // Wide keys, so a few hundred of them need several levels.
+    fn wide(n: usize) -> ASTValue {
+        ASTValue::String(format!("{:04}{}", n, "x".repeat(600)))
+    }
+
+    fn meta(cat: &FileCatalog) -> IndexMeta {
+        cat.table_states["t"]
+            .indexes
+            .iter()
+            .find(|m| m.name == "by_v")
+            .unwrap()
+            .clone()
+    }
+
+    fn all_rows(cat: &FileCatalog) -> Vec<RowId> {
+        cat.btree_range("t", &meta(cat), Bound::Unbounded, Bound::Unbounded)
+            .unwrap()
+    }
+
+    fn depth(cat: &FileCatalog) -> usize {
+        let rel = index_rel_path("t", "by_v");
+        let mut node = cat.load_node(&rel, meta(cat).root).unwrap();
+        let mut depth = 1;
+        while !node.leaf {
+            node = cat.load_node(&rel, node.children[0]).unwrap();
+            depth += 1;
+        }
+        depth
+    }
+
+    #[test]
+    fn test_insert_splits_leaves_and_internal_nodes() {
+        let (root, mut cat) = catalog_with_index("insert");
+        // Insert out of order; row id n holds value n.
+        for i in 0..300 {
+            let n = i * 7 % 300;
+            let key = index_key(&wide(n), n as RowId).unwrap();
+            cat.btree_insert("t", "by_v", key).unwrap();
+        }
+        assert!(depth(&cat) >= 3);
+        assert_eq!(all_rows(&cat), (0..300).collect::<Vec<RowId>>());
+
+        // An identical key is not added twice.
+        cat.btree_insert("t", "by_v", index_key(&wide(5), 5).unwrap())
+            .unwrap();
+        assert_eq!(all_rows(&cat).len(), 300);
+        assert!(matches!(
+            cat.btree_insert("t", "by_v", vec![0; MAX_KEY_LEN + 1]),
+            Err(CatalogError::IndexKeyTooLarge { .. })
+        ));
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_delete_leaves_the_other_keys() {
+        let (root, mut cat) = catalog_with_index("delete");
+        for n in 0..200 {
+            let key = index_key(&wide(n), n as RowId).unwrap();
+            cat.btree_insert("t", "by_v", key).unwrap();
+        }
+        // Empty whole leaves at the start and in the middle.
+        for n in (0..40).chain(100..140).chain((140..200).step_by(2)) {
+            let key = index_key(&wide(n), n as RowId).unwrap();
+            cat.btree_delete("t", "by_v", &key).unwrap();
+        }
+        // Missing keys, including a value held under another row id.
+        cat.btree_delete("t", "by_v", &index_key(&wide(0), 0).unwrap())
+            .unwrap();
+        cat.btree_delete("t", "by_v", &index_key(&wide(50), 51).unwrap())
+            .unwrap();
+
+        let expected: Vec<RowId> = (40..100).chain((141..200).step_by(2)).collect();
+        assert_eq!(all_rows(&cat), expected);
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_range_crosses_leaves_and_keeps_every_row_id_of_a_value() {
+        let (root, mut cat) = catalog_with_index("range");
+        // Values 0..60, each held by three rows: 3n, 3n + 1 and 3n + 2.
+        for rid in (0..180).rev() {
+            let key = index_key(&wide(rid as usize / 3), rid).unwrap();
+            cat.btree_insert("t", "by_v", key).unwrap();
+        }
+        assert!(depth(&cat) >= 2);
+
+        let range = |lo: Bound<&ASTValue>, hi: Bound<&ASTValue>| {
+            let (lo, hi) = key_range(lo, hi).unwrap();
+            cat.btree_range(
+                "t",
+                &meta(&cat),
+                lo.as_ref().map(Vec::as_slice),
+                hi.as_ref().map(Vec::as_slice),
+            )
+            .unwrap()
+        };
+        let (v10, v20) = (wide(10), wide(20));
+        assert_eq!(
+            range(Bound::Included(&v10), Bound::Excluded(&v20)),
+            (30..60).collect::<Vec<RowId>>()
+        );
+        assert_eq!(
+            range(Bound::Excluded(&v10), Bound::Included(&v20)),
+            (33..63).collect::<Vec<RowId>>()
+        );
+        assert_eq!(
+            range(Bound::Included(&v20), Bound::Included(&v20)),
+            [60, 61, 62]
+        );
+        assert_eq!(
+            range(Bound::Unbounded, Bound::Excluded(&v10)),
+            (0..30).collect::<Vec<RowId>>()
+        );
+        assert_eq!(
+            range(Bound::Excluded(&v20), Bound::Unbounded),
+            (63..180).collect::<Vec<RowId>>()
+        );
+        assert!(range(Bound::Included(&v20), Bound::Excluded(&v10)).is_empty());
+        assert!(key_range(Bound::Included(&ASTValue::Null), Bound::Unbounded).is_none());
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_check_unique() {
+        let (root, mut cat) = catalog_with_index("unique");
+        cat.build_index("t", "v_key", "v", true).unwrap();
+        let row = |id: i64, v: ASTValue| {
+            let mut rec = Record::new(id as RowId);
+            rec.set_value("id", ASTValue::Int(id));
+            rec.set_value("v", v);
+            rec
+        };
+        let a = ASTValue::String("a".to_string());
+        cat.append_records("t", &[(1, row(1, a.clone())), (2, row(2, ASTValue::Null))])
+            .unwrap();
+
+        // The row itself may keep its values.
+        cat.check_unique("t", 1, &row(1, a.clone())).unwrap();
+        let err = cat.check_unique("t", 3, &row(3, a.clone())).unwrap_err();
+        assert!(matches!(
+            err,
+            CatalogError::UniqueViolation { constraint: "UNIQUE", ref column, .. } if column == "v"
+        ));
+        let err = cat
+            .check_unique("t", 3, &row(1, ASTValue::String("b".to_string())))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CatalogError::UniqueViolation { constraint: "PRIMARY KEY", ref column, .. } if column == "id"
+        ));
+
+        // NULLs never collide; the plain index allows repeated values.
+        cat.check_unique("t", 3, &row(3, ASTValue::Null)).unwrap();
+        cat.check_unique("t", 3, &row(3, ASTValue::String("b".to_string())))
+            .unwrap();
+        cat.btree_insert("t", "by_v", index_key(&a, 3).unwrap())
+            .unwrap();
+        assert_eq!(all_rows(&cat), [1, 3]);
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -1,29 +1,51 @@
-use std::{collections::HashMap, path::PathBuf};
+use sql::ast::ASTValue;
+use std::{collections::HashMap, ops::Bound};
 use storage::{
     page::PAGE_SIZE,
     table::Table,
-    types::{RowId, TupleLoc},
+    types::{Column, RowId, TupleLoc},
 };
 
 use crate::error::{CatalogError, Result};
 
+pub mod alter;
+pub mod buffer_pool;
 pub mod dir_ops;
 pub mod error;
 pub mod file_catalog;
+pub mod foreign_key;
+pub mod index;
 pub mod meta_codec;
+pub mod sequence;
 pub mod table_schema_codec;
+pub mod wal;
+
+/// `(seg, page_id, page)` for every heap page of a table.
+pub type PageScan<'a> = Box<dyn Iterator<Item = Result<(u32, u32, [u8; PAGE_SIZE])>> + 'a>;
 
 pub trait Catalog {
     fn use_database(&mut self, name: &str) -> Result<()>;
     fn create_database(&mut self, name: &str) -> Result<()>;
     fn create_table(&mut self, name: String, table: Table) -> Result<()>;
+    /// Remove the table with its rows, indexes and AUTOINCREMENT counter.
+    fn drop_table(&mut self, name: &str) -> Result<()>;
+    /// Remove every row of the table. The schema and indexes stay, emptied,
+    /// and the AUTOINCREMENT counter carries on where it was.
+    fn truncate_table(&mut self, name: &str) -> Result<()>;
+    /// Remove the database and everything in it.
+    fn drop_database(&mut self, name: &str) -> Result<()>;
     fn get_table(&mut self, name: &str) -> Option<&Table>;
     fn get_table_mut(&mut self, name: &str) -> Option<&mut Table>;
     fn list_databases(&self) -> Result<Vec<String>>;
     fn list_tables(&self) -> Result<Vec<String>>;
     fn save_table(&mut self, table_name: &str) -> Result<()>;
-    fn seq_scan_pages(&self, table_name: &str) -> Result<Vec<[u8; PAGE_SIZE]>>;
+    /// Heap pages of `table_name` in segment and page order, read one at a
+    /// time through the buffer pool.
+    fn scan_pages(&self, table_name: &str) -> Result<PageScan<'_>>;
     fn next_row_id(&mut self, table_name: &str) -> Result<RowId>;
+    /// Next value of the table's AUTOINCREMENT column, moving the counter
+    /// past `above` first if it has not got there yet.
+    fn next_auto_value(&mut self, table_name: &str, above: i64) -> Result<i64>;
     fn get_tuple_loc(&self, table_name: &str, row_id: RowId) -> Result<Option<TupleLoc>>;
     fn append_record(
         &mut self,
@@ -31,6 +53,12 @@ pub trait Catalog {
         row_id: RowId,
         rec: &storage::Record,
     ) -> Result<TupleLoc>;
+    /// Append a batch of rows, reading and staging each heap page once.
+    fn append_records(
+        &mut self,
+        table_name: &str,
+        rows: &[(RowId, storage::Record)],
+    ) -> Result<Vec<TupleLoc>>;
     fn update_record(
         &mut self,
         table_name: &str,
@@ -39,6 +67,44 @@ pub trait Catalog {
         rec: &storage::Record,
     ) -> Result<TupleLoc>;
     fn tombstone(&mut self, table_name: &str, old: TupleLoc) -> Result<()>;
+    fn fetch_record(&self, table_name: &str, loc: TupleLoc) -> Result<Option<storage::Record>>;
+    fn create_index(&mut self, table_name: &str, index_name: &str, column: &str) -> Result<()>;
+    fn drop_index(&mut self, index_name: &str) -> Result<()>;
+    /// Append `column` to the table. Rows already stored read it as its
+    /// DEFAULT, or NULL, until they are next written.
+    fn add_column(&mut self, table_name: &str, column: Column) -> Result<()>;
+    /// Remove `column` and the indexes on it.
+    fn drop_column(&mut self, table_name: &str, column: &str) -> Result<()>;
+    /// Rename a column along with the indexes and foreign keys naming it.
+    fn rename_column(&mut self, table_name: &str, from: &str, to: &str) -> Result<()>;
+    /// Decode a heap payload of `table_name` found in a slot with `flags`,
+    /// whichever schema version it was written under.
+    fn decode_row(
+        &self,
+        table_name: &str,
+        flags: u8,
+        payload: &[u8],
+    ) -> Result<(RowId, storage::Record)>;
+    /// RowIds whose `column` value lies within the bounds, in index order, or
+    /// None if no index covers `column`.
+    fn index_lookup(
+        &self,
+        table_name: &str,
+        column: &str,
+        lo: Bound<&ASTValue>,
+        hi: Bound<&ASTValue>,
+    ) -> Result<Option<Vec<RowId>>>;
+    /// Name of the index covering `column`, if any.
+    fn index_name(&self, table_name: &str, column: &str) -> Option<String>;
+    fn begin_transaction(&mut self) -> Result<()>;
+    fn commit_transaction(&mut self) -> Result<()>;
+    fn rollback_transaction(&mut self) -> Result<()>;
+    /// Undo the open transaction's changes after a statement in it failed.
+    /// The transaction stays open, refusing further statements, until
+    /// ROLLBACK; a COMMIT then rolls back too and reports the abort.
+    fn abort_transaction(&mut self) -> Result<()>;
+    fn in_transaction(&self) -> bool;
+    fn transaction_aborted(&self) -> bool;
 }
 
 #[derive(Default)]
@@ -57,14 +123,29 @@ impl Catalog for InMemoryCatalog {
     }
     fn create_table(&mut self, name: String, table: Table) -> Result<()> {
         if self.tables.contains_key(&name) {
-            return Err(CatalogError::AlreadyExists {
-                name,
-                path: PathBuf::new(),
-            });
+            return Err(CatalogError::TableExists { name });
         }
         self.tables.insert(name, table);
         Ok(())
     }
+    fn drop_table(&mut self, name: &str) -> Result<()> {
+        self.tables
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| CatalogError::TableDoesNotExist {
+                name: name.to_string(),
+            })
+    }
+    fn truncate_table(&mut self, _name: &str) -> Result<()> {
+        Err(CatalogError::Unsupported {
+            operation: "truncate_table",
+        })
+    }
+    fn drop_database(&mut self, _name: &str) -> Result<()> {
+        Err(CatalogError::Unsupported {
+            operation: "drop_database",
+        })
+    }
     fn get_table(&mut self, name: &str) -> Option<&Table> {
         self.tables.get(name)
     }
@@ -72,16 +153,24 @@ impl Catalog for InMemoryCatalog {
         self.tables.get_mut(name)
     }
     fn list_databases(&self) -> Result<Vec<String>> {
-        unimplemented!()
+        Err(CatalogError::Unsupported {
+            operation: "list_databases",
+        })
     }
     fn list_tables(&self) -> Result<Vec<String>> {
-        unimplemented!()
+        Err(CatalogError::Unsupported {
+            operation: "list_tables",
+        })
     }
     fn save_table(&mut self, _table_name: &str) -> Result<()> {
-        unimplemented!()
+        Err(CatalogError::Unsupported {
+            operation: "save_table",
+        })
     }
-    fn seq_scan_pages(&self, _table_name: &str) -> Result<Vec<[u8; PAGE_SIZE]>> {
-        unimplemented!()
+    fn scan_pages(&self, _table_name: &str) -> Result<PageScan<'_>> {
+        Err(CatalogError::Unsupported {
+            operation: "scan_pages",
+        })
     }
     fn append_record(
         &mut self,
@@ -89,7 +178,18 @@ impl Catalog for InMemoryCatalog {
         _row_id: RowId,
         _rec: &storage::Record,
     ) -> Result<TupleLoc> {
-        unimplemented!()
+        Err(CatalogError::Unsupported {
+            operation: "append_record",
+        })
+    }
+    fn append_records(
+        &mut self,
+        _table_name: &str,
+        _rows: &[(RowId, storage::Record)],
+    ) -> Result<Vec<TupleLoc>> {
+        Err(CatalogError::Unsupported {
+            operation: "append_records",
+        })
     }
     fn update_record(
         &mut self,
@@ -98,15 +198,106 @@ impl Catalog for InMemoryCatalog {
         _row_id: RowId,
         _rec: &storage::Record,
     ) -> Result<TupleLoc> {
-        unimplemented!()
+        Err(CatalogError::Unsupported {
+            operation: "update_record",
+        })
     }
     fn tombstone(&mut self, _table_name: &str, _old: TupleLoc) -> Result<()> {
-        unimplemented!()
+        Err(CatalogError::Unsupported {
+            operation: "tombstone",
+        })
+    }
+    fn fetch_record(&self, _table_name: &str, _loc: TupleLoc) -> Result<Option<storage::Record>> {
+        Err(CatalogError::Unsupported {
+            operation: "fetch_record",
+        })
+    }
+    fn create_index(&mut self, _table_name: &str, _index_name: &str, _column: &str) -> Result<()> {
+        Err(CatalogError::Unsupported {
+            operation: "create_index",
+        })
+    }
+    fn drop_index(&mut self, _index_name: &str) -> Result<()> {
+        Err(CatalogError::Unsupported {
+            operation: "drop_index",
+        })
+    }
+    fn add_column(&mut self, _table_name: &str, _column: Column) -> Result<()> {
+        Err(CatalogError::Unsupported {
+            operation: "add_column",
+        })
+    }
+    fn drop_column(&mut self, _table_name: &str, _column: &str) -> Result<()> {
+        Err(CatalogError::Unsupported {
+            operation: "drop_column",
+        })
+    }
+    fn rename_column(&mut self, _table_name: &str, _from: &str, _to: &str) -> Result<()> {
+        Err(CatalogError::Unsupported {
+            operation: "rename_column",
+        })
+    }
+    fn decode_row(
+        &self,
+        _table_name: &str,
+        _flags: u8,
+        _payload: &[u8],
+    ) -> Result<(RowId, storage::Record)> {
+        Err(CatalogError::Unsupported {
+            operation: "decode_row",
+        })
+    }
+    fn index_lookup(
+        &self,
+        _table_name: &str,
+        _column: &str,
+        _lo: Bound<&ASTValue>,
+        _hi: Bound<&ASTValue>,
+    ) -> Result<Option<Vec<RowId>>> {
+        Ok(None)
+    }
+    fn index_name(&self, _table_name: &str, _column: &str) -> Option<String> {
+        None
+    }
+    fn begin_transaction(&mut self) -> Result<()> {
+        Err(CatalogError::Unsupported {
+            operation: "begin_transaction",
+        })
+    }
+    fn commit_transaction(&mut self) -> Result<()> {
+        Err(CatalogError::Unsupported {
+            operation: "commit_transaction",
+        })
+    }
+    fn rollback_transaction(&mut self) -> Result<()> {
+        Err(CatalogError::Unsupported {
+            operation: "rollback_transaction",
+        })
+    }
+    fn abort_transaction(&mut self) -> Result<()> {
+        Err(CatalogError::Unsupported {
+            operation: "abort_transaction",
+        })
+    }
+    fn in_transaction(&self) -> bool {
+        false
+    }
+    fn transaction_aborted(&self) -> bool {
+        false
     }
     fn next_row_id(&mut self, _table_name: &str) -> Result<RowId> {
-        unimplemented!()
+        Err(CatalogError::Unsupported {
+            operation: "next_row_id",
+        })
+    }
+    fn next_auto_value(&mut self, _table_name: &str, _above: i64) -> Result<i64> {
+        Err(CatalogError::Unsupported {
+            operation: "next_auto_value",
+        })
     }
     fn get_tuple_loc(&self, _table_name: &str, _row_id: RowId) -> Result<Option<TupleLoc>> {
-        unimplemented!()
+        Err(CatalogError::Unsupported {
+            operation: "get_tuple_loc",
+        })
     }
 }
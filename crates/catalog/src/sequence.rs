@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+use sql::ast::ASTValue;
+use storage::{Record, page::PAGE_SIZE};
+
+use crate::{
+    error::{CatalogError, Result},
+    file_catalog::FileCatalog,
+};
+
+// AUTOINCREMENT counter: data/<db>/tables/<table>/sequence
+//
+// A single page holding magic "SEQ0" and the next value (i64 LE). It is
+// staged through the write set like heap pages, so a value handed out by a
+// rolled-back statement is reused, and a committed one never is, even after
+// the row holding it is deleted.
+const SEQUENCE_MAGIC: [u8; 4] = *b"SEQ0";
+
+pub(crate) fn sequence_rel_path(table_name: &str) -> PathBuf {
+    Path::new("tables").join(table_name).join("sequence")
+}
+
+impl FileCatalog {
+    /// Next AUTOINCREMENT value recorded for `table_name`; 1 if none yet.
+    pub(crate) fn load_sequence(&self, table_name: &str) -> Result<i64> {
+        let rel = sequence_rel_path(table_name);
+        let db = self
+            .current_db
+            .as_ref()
+            .ok_or(CatalogError::NoCurrentDatabase)?;
+        if !self.root_dir.join(db).join(&rel).exists() {
+            return Ok(1);
+        }
+        let buf = self.read_file_page(&rel, 0)?;
+        if buf[..4] != SEQUENCE_MAGIC {
+            return Err(CatalogError::InvalidMetadata {
+                path: rel,
+                source: Box::new(std::io::Error::other("bad sequence magic")),
+            });
+        }
+        Ok(i64::from_le_bytes(buf[4..12].try_into().unwrap()))
+    }
+
+    /// Hand out the next value of the table's AUTOINCREMENT column.
+    pub(crate) fn stage_next_auto(&mut self, table_name: &str) -> Result<i64> {
+        let state = self.table_states.get_mut(table_name).ok_or_else(|| {
+            CatalogError::TableDoesNotExist {
+                name: table_name.to_string(),
+            }
+        })?;
+        let value = state.next_auto;
+        let next = value
+            .checked_add(1)
+            .ok_or_else(|| CatalogError::SequenceExhausted {
+                table: table_name.to_string(),
+            })?;
+        state.next_auto = next;
+        self.store_sequence(table_name, next)?;
+        Ok(value)
+    }
+
+    /// Move the counter past an explicitly inserted AUTOINCREMENT value.
+    pub(crate) fn bump_sequence(&mut self, table_name: &str, rec: &Record) -> Result<()> {
+        let Some(column) = self
+            .tables
+            .get(table_name)
+            .and_then(|t| t.columns.iter().find(|c| c.auto_increment))
+        else {
+            return Ok(());
+        };
+        let Some(&ASTValue::Int(value)) = rec.get_value(&column.name) else {
+            return Ok(());
+        };
+        self.advance_sequence(table_name, value)
+    }
+
+    /// Move the counter past `value`, in one step however far that is.
+    pub(crate) fn advance_sequence(&mut self, table_name: &str, value: i64) -> Result<()> {
+        let state = self.table_states.get_mut(table_name).ok_or_else(|| {
+            CatalogError::TableDoesNotExist {
+                name: table_name.to_string(),
+            }
+        })?;
+        if value < state.next_auto {
+            return Ok(());
+        }
+        state.next_auto = value.saturating_add(1);
+        self.store_sequence(table_name, value.saturating_add(1))
+    }
+
+    fn store_sequence(&mut self, table_name: &str, next: i64) -> Result<()> {
+        let mut buf = [0u8; PAGE_SIZE];
+        buf[..4].copy_from_slice(&SEQUENCE_MAGIC);
+        buf[4..12].copy_from_slice(&next.to_le_bytes());
+        self.write_file_page(sequence_rel_path(table_name), 0, &buf)
+    }
+}
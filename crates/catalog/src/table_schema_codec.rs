@@ -1,10 +1,19 @@
 use crc32fast::Hasher;
-use storage::types::Column;
+use sql::{
+    ast::{ASTValue, ArithOp, DefaultExpr, ForeignKey, ReferentialAction},
+    decimal::Decimal,
+};
+use storage::{
+    Table,
+    types::{Column, LayoutColumn},
+};
 use types::tokens::DataType;
 
+use crate::error::CatalogError;
+
 // Binary layout (LE):
 // magic[4] = b"TBL0"
-// version u32 = 1
+// version u32 = 5        // versions 1 to 4 still decode
 // table_name_len u16
 // table_name bytes (UTF-8)
 // column_count u16
@@ -12,23 +21,44 @@ use types::tokens::DataType;
 //   name_len u16
 //   name bytes (UTF-8)
 //   data_type_code u16   // stable mapping (see data_type_to_code)
+//   precision u8, scale u8 // version 5+, DECIMAL and NUMERIC only; 0 0 when
+//                        // written without them
 //   nullable u8          // 0/1
-//   reserved u8          // 0 for now
+//   flags u8             // bit 0: PRIMARY KEY, bit 1: UNIQUE, bit 2: AUTOINCREMENT,
+//                        // bit 3: has DEFAULT (version 3+)
+//   default expr         // only if bit 3 is set, see put_default
 // table_flags u32        // reserved 0
+// fk_count u16           // version 2+
+// for each foreign key:
+//   column_count u16, then per column: name_len u16, name bytes
+//   ref_table_len u16, ref_table bytes
+//   ref_column_count u16, then per column: name_len u16, name bytes
+//   on_delete u8         // 0 RESTRICT, 1 CASCADE, 2 SET NULL
+// schema_version u16     // version 4+; older files are at 0
+// layout_count u16       // version 4+; older files use the columns above
+// for each layout column (payload order, see storage::types::LayoutColumn):
+//   name_len u16, name bytes
+//   data_type_code u16, then precision and scale as above
+//   added_in u16
+//   dropped_in u16       // 0 = live (drops always happen after version 0)
+//   missing value        // tag u8 + value, as a default value below
 // checksum u32           // CRC32 of everything before checksum
 const MAGIC: [u8; 4] = *b"TBL0";
-const VERSION: u32 = 1;
+const VERSION: u32 = 5;
+
+const COL_PRIMARY_KEY: u8 = 0x01;
+const COL_UNIQUE: u8 = 0x02;
+const COL_AUTO_INCREMENT: u8 = 0x04;
+const COL_HAS_DEFAULT: u8 = 0x08;
 
-pub fn encode_schema(table_name: &str, columns: &[Column]) -> Vec<u8> {
+pub fn encode_schema(table: &Table) -> Vec<u8> {
+    let columns = &table.columns;
     let mut buf = Vec::with_capacity(64 + columns.len() * 32);
 
     buf.extend_from_slice(&MAGIC);
     buf.extend_from_slice(&VERSION.to_le_bytes());
 
-    let table_name_bytes = table_name.as_bytes();
-    let table_name_len = u16::try_from(table_name_bytes.len()).unwrap_or(u16::MAX);
-    buf.extend_from_slice(&table_name_len.to_le_bytes());
-    buf.extend_from_slice(table_name_bytes);
+    put_str(&mut buf, &table.name);
 
     let col_count: u16 = columns.len().try_into().unwrap_or(u16::MAX);
     buf.extend_from_slice(&col_count.to_le_bytes());
@@ -40,17 +70,56 @@ pub fn encode_schema(table_name: &str, columns: &[Column]) -> Vec<u8> {
         buf.extend_from_slice(&name_len.to_le_bytes());
         buf.extend_from_slice(name_bytes);
 
-        let dt_code = data_type_to_code(&col.data_type);
-        buf.extend_from_slice(&dt_code.to_le_bytes());
+        put_data_type(&mut buf, &col.data_type);
 
         let nullable = if col.nullable { 1u8 } else { 0u8 };
         buf.push(nullable);
 
-        buf.push(0u8); // reserved per-column
+        let mut flags = 0u8;
+        if col.primary_key {
+            flags |= COL_PRIMARY_KEY;
+        }
+        if col.unique {
+            flags |= COL_UNIQUE;
+        }
+        if col.auto_increment {
+            flags |= COL_AUTO_INCREMENT;
+        }
+        if col.default.is_some() {
+            flags |= COL_HAS_DEFAULT;
+        }
+        buf.push(flags);
+        if let Some(default) = &col.default {
+            put_default(&mut buf, default);
+        }
     }
 
     buf.extend_from_slice(&0u32.to_le_bytes());
 
+    let fk_count: u16 = table.foreign_keys.len().try_into().unwrap_or(u16::MAX);
+    buf.extend_from_slice(&fk_count.to_le_bytes());
+    for fk in &table.foreign_keys {
+        put_str_list(&mut buf, &fk.columns);
+        put_str(&mut buf, &fk.ref_table);
+        put_str_list(&mut buf, &fk.ref_columns);
+        buf.push(match fk.on_delete {
+            ReferentialAction::Restrict => 0,
+            ReferentialAction::Cascade => 1,
+            ReferentialAction::SetNull => 2,
+        });
+    }
+
+    buf.extend_from_slice(&table.schema_version.to_le_bytes());
+    let layout_count: u16 = table.layout.len().try_into().unwrap_or(u16::MAX);
+    buf.extend_from_slice(&layout_count.to_le_bytes());
+    for col in &table.layout {
+        put_str(&mut buf, &col.name);
+        put_data_type(&mut buf, &col.data_type);
+        buf.extend_from_slice(&col.added_in.to_le_bytes());
+        buf.extend_from_slice(&col.dropped_in.unwrap_or(0).to_le_bytes());
+        put_value(&mut buf, &col.missing);
+    }
+
     let mut hasher = Hasher::new();
     hasher.update(&buf);
     let checksum = hasher.finalize();
@@ -59,6 +128,226 @@ pub fn encode_schema(table_name: &str, columns: &[Column]) -> Vec<u8> {
     buf
 }
 
+fn put_data_type(buf: &mut Vec<u8>, data_type: &DataType) {
+    buf.extend_from_slice(&data_type_to_code(data_type).to_le_bytes());
+    if let DataType::DECIMAL(spec) | DataType::NUMERIC(spec) = data_type {
+        let (precision, scale) = spec.unwrap_or((0, 0));
+        buf.extend_from_slice(&[precision, scale]);
+    }
+}
+
+fn put_str(buf: &mut Vec<u8>, s: &str) {
+    let len = u16::try_from(s.len()).unwrap_or(u16::MAX);
+    buf.extend_from_slice(&len.to_le_bytes());
+    buf.extend_from_slice(&s.as_bytes()[..len as usize]);
+}
+
+fn put_str_list(buf: &mut Vec<u8>, items: &[String]) {
+    let count: u16 = items.len().try_into().unwrap_or(u16::MAX);
+    buf.extend_from_slice(&count.to_le_bytes());
+    for s in items {
+        put_str(buf, s);
+    }
+}
+
+// Default expressions, prefix order:
+//   0 value: tag u8 (0 NULL, 1 INT i64, 2 FLOAT f64, 3 TEXT str, 4 BOOL u8,
+//            5 DECIMAL scale u8 + mantissa i128, 6 DATE i32, 7 TIME i64,
+//            8 TIMESTAMP i64)
+//   1 negate: operand
+//   2 binary: op u8 (0 +, 1 -, 2 *, 3 /), left, right
+fn put_default(buf: &mut Vec<u8>, expr: &DefaultExpr) {
+    match expr {
+        DefaultExpr::Value(v) => {
+            buf.push(0);
+            put_value(buf, v);
+        }
+        DefaultExpr::Negate(operand) => {
+            buf.push(1);
+            put_default(buf, operand);
+        }
+        DefaultExpr::Binary { op, left, right } => {
+            buf.push(2);
+            buf.push(match op {
+                ArithOp::Add => 0,
+                ArithOp::Sub => 1,
+                ArithOp::Mul => 2,
+                ArithOp::Div => 3,
+            });
+            put_default(buf, left);
+            put_default(buf, right);
+        }
+    }
+}
+
+fn put_value(buf: &mut Vec<u8>, v: &ASTValue) {
+    match v {
+        ASTValue::Null => buf.push(0),
+        ASTValue::Int(i) => {
+            buf.push(1);
+            buf.extend_from_slice(&i.to_le_bytes());
+        }
+        ASTValue::Float(f) => {
+            buf.push(2);
+            buf.extend_from_slice(&f.to_le_bytes());
+        }
+        ASTValue::String(s) => {
+            buf.push(3);
+            put_str(buf, s);
+        }
+        ASTValue::Boolean(b) => {
+            buf.push(4);
+            buf.push(*b as u8);
+        }
+        ASTValue::Decimal(d) => {
+            buf.push(5);
+            buf.push(d.scale());
+            buf.extend_from_slice(&d.mantissa().to_le_bytes());
+        }
+        ASTValue::Date(d) => {
+            buf.push(6);
+            buf.extend_from_slice(&d.to_le_bytes());
+        }
+        ASTValue::Time(t) => {
+            buf.push(7);
+            buf.extend_from_slice(&t.to_le_bytes());
+        }
+        ASTValue::Timestamp(t) => {
+            buf.push(8);
+            buf.extend_from_slice(&t.to_le_bytes());
+        }
+    }
+}
+
+fn get_value(rest: &[u8]) -> Result<(ASTValue, &[u8]), CatalogError> {
+    let (&tag, rest) = rest.split_first().ok_or(CatalogError::Truncated)?;
+    match tag {
+        0 => Ok((ASTValue::Null, rest)),
+        1 | 2 => {
+            if rest.len() < 8 {
+                return Err(CatalogError::Truncated);
+            }
+            let (b, rest) = rest.split_at(8);
+            let b: [u8; 8] = b.try_into().unwrap();
+            if tag == 1 {
+                Ok((ASTValue::Int(i64::from_le_bytes(b)), rest))
+            } else {
+                Ok((ASTValue::Float(f64::from_le_bytes(b)), rest))
+            }
+        }
+        3 => {
+            let (s, rest) = get_str(rest)?;
+            Ok((ASTValue::String(s), rest))
+        }
+        4 => {
+            let (&b, rest) = rest.split_first().ok_or(CatalogError::Truncated)?;
+            Ok((ASTValue::Boolean(b != 0), rest))
+        }
+        5 => {
+            let (&scale, rest) = rest.split_first().ok_or(CatalogError::Truncated)?;
+            let (b, rest) = take(rest, 16)?;
+            let decimal = Decimal::new(i128::from_le_bytes(b.try_into().unwrap()), scale)
+                .ok_or_else(|| bad_schema("default DECIMAL out of range".to_string()))?;
+            Ok((ASTValue::Decimal(decimal), rest))
+        }
+        6 => {
+            let (b, rest) = take(rest, 4)?;
+            Ok((
+                ASTValue::Date(i32::from_le_bytes(b.try_into().unwrap())),
+                rest,
+            ))
+        }
+        7 | 8 => {
+            let (b, rest) = take(rest, 8)?;
+            let t = i64::from_le_bytes(b.try_into().unwrap());
+            if tag == 7 {
+                Ok((ASTValue::Time(t), rest))
+            } else {
+                Ok((ASTValue::Timestamp(t), rest))
+            }
+        }
+        other => Err(bad_schema(format!("unknown default value tag {}", other))),
+    }
+}
+
+fn get_default(rest: &[u8]) -> Result<(DefaultExpr, &[u8]), CatalogError> {
+    let (&kind, rest) = rest.split_first().ok_or(CatalogError::Truncated)?;
+    match kind {
+        0 => {
+            let (value, rest) = get_value(rest)?;
+            Ok((DefaultExpr::Value(value), rest))
+        }
+        1 => {
+            let (operand, rest) = get_default(rest)?;
+            Ok((DefaultExpr::Negate(Box::new(operand)), rest))
+        }
+        2 => {
+            let (&op, rest) = rest.split_first().ok_or(CatalogError::Truncated)?;
+            let op = match op {
+                0 => ArithOp::Add,
+                1 => ArithOp::Sub,
+                2 => ArithOp::Mul,
+                3 => ArithOp::Div,
+                other => return Err(bad_schema(format!("unknown default operator {}", other))),
+            };
+            let (left, rest) = get_default(rest)?;
+            let (right, rest) = get_default(rest)?;
+            Ok((
+                DefaultExpr::Binary {
+                    op,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+                rest,
+            ))
+        }
+        other => Err(bad_schema(format!(
+            "unknown default expression kind {}",
+            other
+        ))),
+    }
+}
+
+fn bad_schema(msg: String) -> CatalogError {
+    CatalogError::InvalidMetadata {
+        path: std::path::PathBuf::from("schema.tbl"),
+        source: Box::new(std::io::Error::other(msg)),
+    }
+}
+
+fn take(rest: &[u8], n: usize) -> Result<(&[u8], &[u8]), CatalogError> {
+    if rest.len() < n {
+        return Err(CatalogError::Truncated);
+    }
+    Ok(rest.split_at(n))
+}
+
+fn get_u16(rest: &[u8]) -> Result<(u16, &[u8]), CatalogError> {
+    let (b, rest) = take(rest, 2)?;
+    Ok((u16::from_le_bytes(b.try_into().unwrap()), rest))
+}
+
+fn get_str(rest: &[u8]) -> Result<(String, &[u8]), CatalogError> {
+    let (len, rest) = get_u16(rest)?;
+    if rest.len() < len as usize {
+        return Err(CatalogError::Truncated);
+    }
+    let (b, rest) = rest.split_at(len as usize);
+    let s = std::str::from_utf8(b).map_err(|_| CatalogError::BadUtf8)?;
+    Ok((s.to_string(), rest))
+}
+
+fn get_str_list(rest: &[u8]) -> Result<(Vec<String>, &[u8]), CatalogError> {
+    let (count, mut rest) = get_u16(rest)?;
+    let mut items = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let (s, r) = get_str(rest)?;
+        items.push(s);
+        rest = r;
+    }
+    Ok((items, rest))
+}
+
 fn data_type_to_code(dt: &DataType) -> u16 {
     match dt {
         DataType::INTEGER => 1,
@@ -72,10 +361,10 @@ fn data_type_to_code(dt: &DataType) -> u16 {
         DataType::CHAR => 9,
         DataType::BLOB => 10,
         DataType::JSON => 11,
-        DataType::DECIMAL => 12,
+        DataType::DECIMAL(_) => 12,
         DataType::DOUBLE => 13,
         DataType::REAL => 14,
-        DataType::NUMERIC => 15,
+        DataType::NUMERIC(_) => 15,
         DataType::TINYINT => 16,
         DataType::SMALLINT => 17,
         DataType::MEDIUMINT => 18,
@@ -96,10 +385,10 @@ fn data_type_from_code(code: u16) -> Result<DataType, String> {
         9 => Ok(DataType::CHAR),
         10 => Ok(DataType::BLOB),
         11 => Ok(DataType::JSON),
-        12 => Ok(DataType::DECIMAL),
+        12 => Ok(DataType::DECIMAL(None)),
         13 => Ok(DataType::DOUBLE),
         14 => Ok(DataType::REAL),
-        15 => Ok(DataType::NUMERIC),
+        15 => Ok(DataType::NUMERIC(None)),
         16 => Ok(DataType::TINYINT),
         17 => Ok(DataType::SMALLINT),
         18 => Ok(DataType::MEDIUMINT),
@@ -108,9 +397,24 @@ fn data_type_from_code(code: u16) -> Result<DataType, String> {
     }
 }
 
-pub fn decode_schema(bytes: &[u8]) -> Result<(String, Vec<Column>), crate::error::CatalogError> {
-    use crate::error::CatalogError;
+fn get_data_type(rest: &[u8], version: u32) -> Result<(DataType, &[u8]), CatalogError> {
+    let (code, rest) = get_u16(rest)?;
+    let data_type = data_type_from_code(code).map_err(bad_schema)?;
+    if version < 5 {
+        return Ok((data_type, rest));
+    }
+    if !matches!(data_type, DataType::DECIMAL(_) | DataType::NUMERIC(_)) {
+        return Ok((data_type, rest));
+    }
+    let (b, rest) = take(rest, 2)?;
+    let spec = (b[0] != 0).then_some((b[0], b[1]));
+    Ok(match data_type {
+        DataType::DECIMAL(_) => (DataType::DECIMAL(spec), rest),
+        _ => (DataType::NUMERIC(spec), rest),
+    })
+}
 
+pub fn decode_schema(bytes: &[u8]) -> Result<Table, CatalogError> {
     if bytes.len() < 4 + 4 + 2 + 2 + 4 + 4 {
         return Err(CatalogError::MetaTooShort {
             min: 20,
@@ -125,7 +429,7 @@ pub fn decode_schema(bytes: &[u8]) -> Result<(String, Vec<Column>), crate::error
 
     let (ver_b, rest) = rest.split_at(4);
     let version = u32::from_le_bytes(ver_b.try_into().unwrap());
-    if version != VERSION {
+    if version == 0 || version > VERSION {
         return Err(CatalogError::BadVersion { version });
     }
 
@@ -151,34 +455,102 @@ pub fn decode_schema(bytes: &[u8]) -> Result<(String, Vec<Column>), crate::error
         }
         let (name_len_b, r1) = rest.split_at(2);
         let name_len = u16::from_le_bytes(name_len_b.try_into().unwrap()) as usize;
-        if r1.len() < name_len + 2 + 1 + 1 {
+        if r1.len() < name_len + 2 {
             return Err(CatalogError::Truncated);
         }
         let (name_b, r2) = r1.split_at(name_len);
-        let (code_b, r3) = r2.split_at(2);
+        let (dt, r3) = get_data_type(r2, version)?;
+        if r3.len() < 1 + 1 {
+            return Err(CatalogError::Truncated);
+        }
         let (nullable_b, r4) = r3.split_at(1);
-        let (_reserved_b, r5) = r4.split_at(1);
+        let (flags_b, r5) = r4.split_at(1);
 
         let name = std::str::from_utf8(name_b)
             .map_err(|_| CatalogError::BadUtf8)?
             .to_string();
-        let code = u16::from_le_bytes(code_b.try_into().unwrap());
-        let dt = data_type_from_code(code).map_err(|e| CatalogError::WriteFile {
-            path: std::path::PathBuf::from("schema.tbl"),
-            source: std::io::Error::other(e),
-        })?;
         let nullable = nullable_b[0] != 0;
 
-        columns.push(Column::new(name, dt, nullable));
+        let mut column = Column::new(name, dt, nullable);
+        column.primary_key = flags_b[0] & COL_PRIMARY_KEY != 0;
+        column.unique = flags_b[0] & COL_UNIQUE != 0;
+        column.auto_increment = flags_b[0] & COL_AUTO_INCREMENT != 0;
         rest = r5;
+        if version >= 3 && flags_b[0] & COL_HAS_DEFAULT != 0 {
+            let (default, r) = get_default(rest)?;
+            column.default = Some(default);
+            rest = r;
+        }
+        columns.push(column);
     }
 
     if rest.len() < 4 + 4 {
         return Err(CatalogError::Truncated);
     }
-    let (flags_b, rest) = rest.split_at(4);
+    let (flags_b, mut rest) = rest.split_at(4);
     let _flags = u32::from_le_bytes(flags_b.try_into().unwrap());
 
+    let mut foreign_keys = Vec::new();
+    if version >= 2 {
+        let (fk_count, r) = get_u16(rest)?;
+        rest = r;
+        for _ in 0..fk_count {
+            let (columns, r) = get_str_list(rest)?;
+            let (ref_table, r) = get_str(r)?;
+            let (ref_columns, r) = get_str_list(r)?;
+            let Some((&action, r)) = r.split_first() else {
+                return Err(CatalogError::Truncated);
+            };
+            let on_delete = match action {
+                0 => ReferentialAction::Restrict,
+                1 => ReferentialAction::Cascade,
+                2 => ReferentialAction::SetNull,
+                other => return Err(bad_schema(format!("unknown ON DELETE action {}", other))),
+            };
+            foreign_keys.push(ForeignKey {
+                columns,
+                ref_table,
+                ref_columns,
+                on_delete,
+            });
+            rest = r;
+        }
+    }
+
+    let mut layout = None;
+    let mut schema_version = 0;
+    if version >= 4 {
+        let (v, r) = get_u16(rest)?;
+        let (count, r) = get_u16(r)?;
+        schema_version = v;
+        rest = r;
+        let mut cols = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let (name, r) = get_str(rest)?;
+            let (data_type, r) = get_data_type(r, version)?;
+            let (added_in, r) = get_u16(r)?;
+            let (dropped_in, r) = get_u16(r)?;
+            let (missing, r) = get_value(r)?;
+            let mut col = LayoutColumn::new(name, data_type, added_in, missing);
+            col.dropped_in = (dropped_in != 0).then_some(dropped_in);
+            cols.push(col);
+            rest = r;
+        }
+        let live = cols.iter().filter(|c| c.dropped_in.is_none());
+        if !live
+            .map(|c| (&c.name, &c.data_type))
+            .eq(columns.iter().map(|c| (&c.name, &c.data_type)))
+        {
+            return Err(bad_schema(
+                "row layout does not match the table's columns".to_string(),
+            ));
+        }
+        layout = Some(cols);
+    }
+    if rest.len() < 4 {
+        return Err(CatalogError::Truncated);
+    }
+
     let (checksum_b, _tail) = rest.split_at(4);
     let checksum = u32::from_le_bytes(checksum_b.try_into().unwrap());
     let mut hasher = Hasher::new();
@@ -191,5 +563,112 @@ pub fn decode_schema(bytes: &[u8]) -> Result<(String, Vec<Column>), crate::error
         });
     }
 
-    Ok((table_name, columns))
+    let mut table = Table::new(table_name, columns);
+    table.foreign_keys = foreign_keys;
+    table.schema_version = schema_version;
+    if let Some(layout) = layout {
+        table.layout = layout;
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constraint_flags_roundtrip() {
+        let mut id = Column::new("id".to_string(), DataType::INTEGER, false);
+        id.primary_key = true;
+        id.auto_increment = true;
+        let mut email = Column::new("email".to_string(), DataType::TEXT, true);
+        email.unique = true;
+        let mut note = Column::new("note".to_string(), DataType::TEXT, true);
+        note.default = Some(DefaultExpr::Value(ASTValue::String("none".to_string())));
+
+        let table = Table::new(
+            "users".to_string(),
+            vec![id.clone(), email.clone(), note.clone()],
+        );
+        let decoded = decode_schema(&encode_schema(&table)).unwrap();
+        assert_eq!(decoded.name, "users");
+        assert_eq!(decoded.columns, vec![id, email, note]);
+    }
+
+    #[test]
+    fn test_default_expr_roundtrip() {
+        let mut n = Column::new("n".to_string(), DataType::INTEGER, false);
+        n.default = Some(DefaultExpr::Binary {
+            op: ArithOp::Mul,
+            left: Box::new(DefaultExpr::Negate(Box::new(DefaultExpr::Value(
+                ASTValue::Int(2),
+            )))),
+            right: Box::new(DefaultExpr::Value(ASTValue::Int(21))),
+        });
+        let table = Table::new("t".to_string(), vec![n.clone()]);
+        let decoded = decode_schema(&encode_schema(&table)).unwrap();
+        assert_eq!(decoded.columns, vec![n]);
+    }
+
+    #[test]
+    fn test_foreign_keys_roundtrip() {
+        let mut table = Table::new(
+            "orders".to_string(),
+            vec![Column::new("user_id".to_string(), DataType::INTEGER, true)],
+        );
+        table.foreign_keys.push(ForeignKey {
+            columns: vec!["user_id".to_string()],
+            ref_table: "users".to_string(),
+            ref_columns: vec!["id".to_string()],
+            on_delete: ReferentialAction::SetNull,
+        });
+        let decoded = decode_schema(&encode_schema(&table)).unwrap();
+        assert_eq!(decoded.foreign_keys, table.foreign_keys);
+    }
+
+    #[test]
+    fn test_row_layout_roundtrip() {
+        let mut table = Table::new(
+            "t".to_string(),
+            vec![
+                Column::new("a".to_string(), DataType::INTEGER, false),
+                Column::new("b".to_string(), DataType::TEXT, true),
+            ],
+        );
+        table.drop_column("b").unwrap();
+        table
+            .add_column(
+                Column::new("c".to_string(), DataType::TEXT, false),
+                ASTValue::String("x".to_string()),
+            )
+            .unwrap();
+        let decoded = decode_schema(&encode_schema(&table)).unwrap();
+        assert_eq!(decoded.schema_version, 2);
+        assert_eq!(decoded.layout, table.layout);
+        assert_eq!(decoded.columns, table.columns);
+    }
+
+    #[test]
+    fn test_decimal_precision_roundtrip() {
+        let mut price = Column::new("price".to_string(), DataType::DECIMAL(Some((10, 2))), false);
+        price.default = Some(DefaultExpr::Value(ASTValue::Decimal(
+            "9.99".parse().unwrap(),
+        )));
+        let mut table = Table::new(
+            "items".to_string(),
+            vec![
+                price,
+                Column::new("ratio".to_string(), DataType::NUMERIC(None), true),
+            ],
+        );
+        table
+            .add_column(
+                Column::new("tax".to_string(), DataType::NUMERIC(Some((4, 4))), true),
+                ASTValue::Decimal("0.0700".parse().unwrap()),
+            )
+            .unwrap();
+        let decoded = decode_schema(&encode_schema(&table)).unwrap();
+        assert_eq!(decoded.columns, table.columns);
+        assert_eq!(decoded.layout, table.layout);
+    }
 }
@@ -0,0 +1,434 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crc32fast::Hasher;
+use storage::page::PAGE_SIZE;
+
+use crate::error::{CatalogError, Result};
+
+// Write-ahead log, one file per database: data/<db>/wal.log
+//
+// File header (LE):
+// magic[4] = b"WAL0"
+// version u32 = 1
+//
+// Followed by a sequence of frames:
+// frame_len u32          // bytes after this field, including checksum
+// lsn u64                // monotonically increasing log sequence number
+// txn_id u64             // frames of one atomic unit share a txn_id
+// kind u8                // 1 = page image, 2 = commit
+// [kind == 1 only]
+//   path_len u16
+//   path bytes (UTF-8)   // heap file path relative to the database directory
+//   page_id u32
+//   image [PAGE_SIZE]    // full after-image of the page (redo only)
+// checksum u32           // CRC32 of lsn..end of payload
+//
+// A unit of work is durable once its commit frame has been fsynced. Replay
+// redoes the page images of committed units in log order and stops at the
+// first torn or corrupt frame; frames without a commit are discarded.
+const MAGIC: [u8; 4] = *b"WAL0";
+const VERSION: u32 = 1;
+const FILE_HEADER_LEN: u64 = 8;
+
+const KIND_PAGE: u8 = 1;
+const KIND_COMMIT: u8 = 2;
+
+pub const WAL_FILE_NAME: &str = "wal.log";
+
+/// Truncate the log once it grows past this many bytes.
+pub const WAL_CHECKPOINT_BYTES: u64 = 16 * 1024 * 1024;
+
+/// A full page after-image destined for `db_dir/file` at `page_id`.
+#[derive(Clone)]
+pub struct PageWrite {
+    pub file: PathBuf,
+    pub page_id: u32,
+    pub image: Box<[u8; PAGE_SIZE]>,
+}
+
+pub struct Wal {
+    path: PathBuf,
+    file: File,
+    next_lsn: u64,
+    next_txn_id: u64,
+    len: u64,
+}
+
+impl Wal {
+    /// Open (or create) the log in `db_dir`. Existing frames are left in place;
+    /// call [`replay`] first and [`Wal::truncate`] once they have been applied.
+    pub fn open(db_dir: &Path) -> Result<Self> {
+        let path = db_dir.join(WAL_FILE_NAME);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|source| CatalogError::OpenFile {
+                path: path.clone(),
+                source,
+            })?;
+
+        let len = file
+            .metadata()
+            .map_err(|source| CatalogError::ReadFile {
+                path: path.clone(),
+                source,
+            })?
+            .len();
+        if len < FILE_HEADER_LEN {
+            write_file_header(&mut file, &path)?;
+        }
+        let len = file
+            .seek(SeekFrom::End(0))
+            .map_err(|source| CatalogError::SeekFile {
+                path: path.clone(),
+                source,
+            })?;
+
+        Ok(Self {
+            path,
+            file,
+            next_lsn: 1,
+            next_txn_id: 1,
+            len,
+        })
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len <= FILE_HEADER_LEN
+    }
+
+    /// Append all page images of one unit of work followed by its commit
+    /// frame, then fsync. Once this returns the unit survives a crash.
+    pub fn log_commit(&mut self, writes: &[PageWrite]) -> Result<()> {
+        let txn_id = self.next_txn_id;
+        self.next_txn_id += 1;
+
+        let mut buf = Vec::with_capacity(writes.len() * (PAGE_SIZE + 64) + 32);
+        for w in writes {
+            let path = w.file.to_string_lossy();
+            let path_bytes = path.as_bytes();
+            let path_len = u16::try_from(path_bytes.len()).unwrap_or(u16::MAX);
+
+            let mut body = Vec::with_capacity(PAGE_SIZE + 32 + path_bytes.len());
+            body.push(KIND_PAGE);
+            body.extend_from_slice(&path_len.to_le_bytes());
+            body.extend_from_slice(&path_bytes[..path_len as usize]);
+            body.extend_from_slice(&w.page_id.to_le_bytes());
+            body.extend_from_slice(&w.image[..]);
+            self.encode_frame(&mut buf, txn_id, &body);
+        }
+        self.encode_frame(&mut buf, txn_id, &[KIND_COMMIT]);
+
+        let res = self
+            .file
+            .write_all(&buf)
+            .map_err(|source| CatalogError::WriteFile {
+                path: self.path.clone(),
+                source,
+            })
+            .and_then(|()| {
+                self.file
+                    .sync_data()
+                    .map_err(|source| CatalogError::SyncFile {
+                        path: self.path.clone(),
+                        source,
+                    })
+            });
+        if let Err(e) = res {
+            // Replay stops at the first torn frame, so a partial unit left
+            // here would hide every commit logged after it.
+            let _ = self.cut_to_len();
+            return Err(e);
+        }
+        self.len += buf.len() as u64;
+        Ok(())
+    }
+
+    // Drop whatever follows the last complete unit.
+    fn cut_to_len(&mut self) -> std::io::Result<()> {
+        self.file.set_len(self.len)?;
+        self.file.seek(SeekFrom::Start(self.len))?;
+        Ok(())
+    }
+
+    /// Drop every frame. Callers must have made all logged pages durable
+    /// in their heap files first.
+    pub fn truncate(&mut self) -> Result<()> {
+        self.file
+            .set_len(FILE_HEADER_LEN)
+            .map_err(|source| CatalogError::WriteFile {
+                path: self.path.clone(),
+                source,
+            })?;
+        self.file
+            .seek(SeekFrom::Start(FILE_HEADER_LEN))
+            .map_err(|source| CatalogError::SeekFile {
+                path: self.path.clone(),
+                source,
+            })?;
+        self.file
+            .sync_all()
+            .map_err(|source| CatalogError::SyncFile {
+                path: self.path.clone(),
+                source,
+            })?;
+        self.len = FILE_HEADER_LEN;
+        Ok(())
+    }
+
+    fn encode_frame(&mut self, buf: &mut Vec<u8>, txn_id: u64, body: &[u8]) {
+        let lsn = self.next_lsn;
+        self.next_lsn += 1;
+
+        let frame_len = (8 + 8 + body.len() + 4) as u32;
+        buf.extend_from_slice(&frame_len.to_le_bytes());
+        let start = buf.len();
+        buf.extend_from_slice(&lsn.to_le_bytes());
+        buf.extend_from_slice(&txn_id.to_le_bytes());
+        buf.extend_from_slice(body);
+
+        let mut hasher = Hasher::new();
+        hasher.update(&buf[start..]);
+        let checksum = hasher.finalize();
+        buf.extend_from_slice(&checksum.to_le_bytes());
+    }
+}
+
+fn write_file_header(file: &mut File, path: &Path) -> Result<()> {
+    let mut hdr = Vec::with_capacity(FILE_HEADER_LEN as usize);
+    hdr.extend_from_slice(&MAGIC);
+    hdr.extend_from_slice(&VERSION.to_le_bytes());
+    file.set_len(0).map_err(|source| CatalogError::WriteFile {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    file.write_all(&hdr)
+        .map_err(|source| CatalogError::WriteFile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    file.sync_all().map_err(|source| CatalogError::SyncFile {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Read the log in `db_dir` and return the page images of every committed
+/// unit, in log order. A missing log yields nothing.
+pub fn replay(db_dir: &Path) -> Result<Vec<PageWrite>> {
+    let path = db_dir.join(WAL_FILE_NAME);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut bytes = Vec::new();
+    File::open(&path)
+        .and_then(|mut f| f.read_to_end(&mut bytes))
+        .map_err(|source| CatalogError::ReadFile {
+            path: path.clone(),
+            source,
+        })?;
+
+    if bytes.len() < FILE_HEADER_LEN as usize {
+        // Crashed while creating the log; nothing was ever committed.
+        return Ok(Vec::new());
+    }
+    let (magic, rest) = bytes.split_at(4);
+    if magic != MAGIC {
+        return Err(CatalogError::BadMagic);
+    }
+    let (ver_b, mut rest) = rest.split_at(4);
+    let version = u32::from_le_bytes(ver_b.try_into().unwrap());
+    if version != VERSION {
+        return Err(CatalogError::BadVersion { version });
+    }
+
+    let mut committed = Vec::new();
+    let mut pending: Vec<PageWrite> = Vec::new();
+    let mut pending_txn = None;
+
+    while rest.len() >= 4 {
+        let frame_len = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+        if frame_len < 8 + 8 + 1 + 4 || rest.len() < 4 + frame_len {
+            break; // torn tail
+        }
+        let frame = &rest[4..4 + frame_len];
+        rest = &rest[4 + frame_len..];
+
+        let (content, checksum_b) = frame.split_at(frame_len - 4);
+        let checksum = u32::from_le_bytes(checksum_b.try_into().unwrap());
+        let mut hasher = Hasher::new();
+        hasher.update(content);
+        if hasher.finalize() != checksum {
+            break; // torn or corrupt frame; nothing after it can be trusted
+        }
+
+        let txn_id = u64::from_le_bytes(content[8..16].try_into().unwrap());
+        let body = &content[16..];
+        if pending_txn != Some(txn_id) {
+            // A new unit started before the previous one committed.
+            pending.clear();
+            pending_txn = Some(txn_id);
+        }
+
+        match body[0] {
+            KIND_PAGE => match decode_page_body(&body[1..]) {
+                Some(w) => pending.push(w),
+                None => break,
+            },
+            KIND_COMMIT => {
+                committed.append(&mut pending);
+                pending_txn = None;
+            }
+            _ => break,
+        }
+    }
+
+    Ok(committed)
+}
+
+fn decode_page_body(body: &[u8]) -> Option<PageWrite> {
+    if body.len() < 2 {
+        return None;
+    }
+    let (path_len_b, rest) = body.split_at(2);
+    let path_len = u16::from_le_bytes(path_len_b.try_into().unwrap()) as usize;
+    if rest.len() != path_len + 4 + PAGE_SIZE {
+        return None;
+    }
+    let (path_b, rest) = rest.split_at(path_len);
+    let (pid_b, image_b) = rest.split_at(4);
+    let file = PathBuf::from(std::str::from_utf8(path_b).ok()?);
+    let page_id = u32::from_le_bytes(pid_b.try_into().unwrap());
+    let mut image = Box::new([0u8; PAGE_SIZE]);
+    image.copy_from_slice(image_b);
+    Some(PageWrite {
+        file,
+        page_id,
+        image,
+    })
+}
+
+/// Write a page image into `path` at `page_id` without truncating the file.
+pub fn write_page_at(path: &Path, page_id: u32, buf: &[u8; PAGE_SIZE]) -> Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|source| CatalogError::CreateDir {
+            path: parent.to_path_buf(),
+            source,
+        })?;
+    }
+    let mut f = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|source| CatalogError::OpenFile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    let off = (page_id as u64) * (PAGE_SIZE as u64);
+    f.seek(SeekFrom::Start(off))
+        .map_err(|source| CatalogError::SeekFile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    f.write_all(buf).map_err(|source| CatalogError::WriteFile {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db_dir(tag: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("meridb-wal-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn page(fill: u8) -> PageWrite {
+        PageWrite {
+            file: PathBuf::from("tables/t/data/heap.0001"),
+            page_id: fill as u32,
+            image: Box::new([fill; PAGE_SIZE]),
+        }
+    }
+
+    #[test]
+    fn test_replay_returns_committed_pages_in_order() {
+        let dir = temp_db_dir("committed");
+        let mut wal = Wal::open(&dir).unwrap();
+        wal.log_commit(&[page(1), page(2)]).unwrap();
+        wal.log_commit(&[page(3)]).unwrap();
+
+        let pages = replay(&dir).unwrap();
+        let ids: Vec<u32> = pages.iter().map(|p| p.page_id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(pages[2].image[0], 3);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_replay_discards_torn_tail() {
+        let dir = temp_db_dir("torn");
+        let mut wal = Wal::open(&dir).unwrap();
+        wal.log_commit(&[page(1)]).unwrap();
+        let committed_len = wal.len();
+        wal.log_commit(&[page(2)]).unwrap();
+        drop(wal);
+
+        // Simulate a crash half-way through the second unit.
+        let f = OpenOptions::new()
+            .write(true)
+            .open(dir.join(WAL_FILE_NAME))
+            .unwrap();
+        f.set_len(committed_len + 100).unwrap();
+
+        let pages = replay(&dir).unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].page_id, 1);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_failed_commit_leaves_no_torn_frame() {
+        let dir = temp_db_dir("cut");
+        let mut wal = Wal::open(&dir).unwrap();
+        wal.log_commit(&[page(1)]).unwrap();
+
+        // A write that failed part-way through its frames.
+        wal.file.write_all(&[0xAB; 100]).unwrap();
+        wal.cut_to_len().unwrap();
+        wal.log_commit(&[page(2)]).unwrap();
+
+        let ids: Vec<u32> = replay(&dir).unwrap().iter().map(|p| p.page_id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_truncate_empties_log() {
+        let dir = temp_db_dir("truncate");
+        let mut wal = Wal::open(&dir).unwrap();
+        wal.log_commit(&[page(1)]).unwrap();
+        assert!(!wal.is_empty());
+        wal.truncate().unwrap();
+        assert!(wal.is_empty());
+        assert!(replay(&dir).unwrap().is_empty());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,10 +1,12 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
 
-use api::Session;
+use api::{CatalogOptions, FileSession, Session};
 use clap::Parser;
 use sql::ast::ASTNode;
-use sql::parse_command;
+use sql::error::SqlError;
+use sql::parse_script;
 use tracing::{debug, info};
 
 use crate::input_handler::InputHandler;
@@ -23,21 +25,101 @@ struct Args {
     #[arg(short = 'd', long = "database", value_name = "DB")]
     database: Option<String>,
 
-    /// Execute a single SQL statement non-interactively and exit
+    /// Execute SQL statements non-interactively and exit
     #[arg(short = 'e', long = "exec", value_name = "SQL")]
     exec: Option<String>,
+
+    /// Execute the statements of a SQL script file and exit
+    #[arg(
+        short = 'f',
+        long = "file",
+        value_name = "PATH",
+        conflicts_with = "exec"
+    )]
+    file: Option<PathBuf>,
+
+    /// Buffer pool capacity in 8 KiB pages
+    #[arg(long = "buffer-pages", value_name = "N")]
+    buffer_pages: Option<usize>,
+
+    /// Heap segment file size in MiB before a table rolls over to the next file
+    #[arg(long = "segment-mib", value_name = "MIB")]
+    segment_mib: Option<u64>,
+}
+
+impl Args {
+    fn catalog_options(&self) -> CatalogOptions {
+        let mut options = CatalogOptions::default();
+        if let Some(pages) = self.buffer_pages {
+            options.pool_pages = pages;
+        }
+        if let Some(mib) = self.segment_mib {
+            options.segment_bytes = mib << 20;
+        }
+        options
+    }
+}
+
+/// Why a script stopped early.
+enum ScriptError {
+    Read(String),
+    Parse(SqlError),
+    /// The statement that failed, counted from 1, and its error.
+    Exec(usize, String),
+}
+
+impl ScriptError {
+    fn report(&self, name: Option<&Path>) {
+        let source = name
+            .map(|p| format!(" in {}", p.display()))
+            .unwrap_or_default();
+        match self {
+            ScriptError::Read(e) => eprintln!("{e}"),
+            ScriptError::Parse(e) => eprintln!("Parse error{source}: {e}"),
+            ScriptError::Exec(_, e) if name.is_none() => eprintln!("Exec error: {e}"),
+            ScriptError::Exec(n, e) => eprintln!("Exec error{source}, statement {n}: {e}"),
+        }
+    }
+
+    fn exit_code(&self) -> i32 {
+        match self {
+            ScriptError::Read(_) | ScriptError::Exec(..) => 1,
+            ScriptError::Parse(_) => 2,
+        }
+    }
+}
+
+// Parse all of `sql`, then execute its statements in order, printing each
+// result. Stops at the first statement that fails.
+fn run_script(session: &mut FileSession, sql: &str) -> Result<(), ScriptError> {
+    let statements = parse_script(sql).map_err(ScriptError::Parse)?;
+    for (i, ast) in statements.into_iter().enumerate() {
+        debug!("Parsed AST: {:#?}", ast);
+        session
+            .execute(ast)
+            .and_then(|qr| qr.print(&mut io::stdout().lock()))
+            .map_err(|e| ScriptError::Exec(i + 1, e))?;
+    }
+    Ok(())
+}
+
+fn run_file(session: &mut FileSession, path: &Path) -> Result<(), ScriptError> {
+    let sql = fs::read_to_string(path)
+        .map_err(|e| ScriptError::Read(format!("Cannot read {}: {}", path.display(), e)))?;
+    run_script(session, &sql)
 }
 
 fn main() {
     let args = Args::parse();
+    let options = args.catalog_options();
 
     let _guard = logging::init_logging(&args.data_dir, args.database.as_deref());
 
     fs::create_dir_all(&args.data_dir).ok();
 
-    // Non-interactive: --exec
-    if let Some(sql) = args.exec {
-        let mut session = Session::file_backed(args.data_dir);
+    // Non-interactive: --exec or --file
+    if args.exec.is_some() || args.file.is_some() {
+        let mut session = Session::file_backed(args.data_dir, options);
 
         if let Some(db) = args.database {
             info!("Using database: {}", db);
@@ -49,20 +131,16 @@ fn main() {
             }
         }
 
-        match parse_command(&sql) {
-            Ok(ast) => match session.execute(ast) {
-                Ok(qr) => {
-                    println!("{}", qr);
-                    std::process::exit(0);
-                }
-                Err(e) => {
-                    eprintln!("Exec error: {e}");
-                    std::process::exit(1);
-                }
-            },
+        let res = match (&args.exec, &args.file) {
+            (Some(sql), _) => run_script(&mut session, sql),
+            (None, Some(path)) => run_file(&mut session, path),
+            (None, None) => unreachable!(),
+        };
+        match res {
+            Ok(()) => std::process::exit(0),
             Err(e) => {
-                eprintln!("Parse error: {}", e);
-                std::process::exit(2);
+                e.report(args.file.as_deref());
+                std::process::exit(e.exit_code());
             }
         }
     }
@@ -71,7 +149,7 @@ fn main() {
     let mut input_handler =
         InputHandler::with_history_file(history_file).expect("Failed to initialize input handler");
 
-    let mut session = Session::file_backed(args.data_dir);
+    let mut session = Session::file_backed(args.data_dir, options);
 
     //preselect database for the REPL if -d/--database is provided
     if let Some(db) = args.database {
@@ -81,19 +159,28 @@ fn main() {
         }
     }
 
-    while let Ok(line) = input_handler.readline("meridb> ") {
+    loop {
+        let prompt = if session.in_transaction() {
+            "meridb*> "
+        } else {
+            "meridb> "
+        };
+        let Ok(line) = input_handler.readline(prompt) else {
+            break;
+        };
         if line.eq_ignore_ascii_case("exit") {
             break;
         }
-        match parse_command(&line) {
-            Ok(ast) => {
-                debug!("Parsed AST: {:#?}", ast);
-                match session.execute(ast) {
-                    Ok(qr) => println!("{}", qr),
-                    Err(e) => eprintln!("Exec error: {e}"),
-                }
+        // \i <file>: run a script file
+        if let Some(path) = line.trim().strip_prefix("\\i ") {
+            let path = Path::new(path.trim());
+            if let Err(e) = run_file(&mut session, path) {
+                e.report(Some(path));
             }
-            Err(e) => eprintln!("Parse error: {}", e),
+            continue;
+        }
+        if let Err(e) = run_script(&mut session, &line) {
+            e.report(None);
         }
     }
 }
@@ -0,0 +1,277 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use sql::{
+    ast::{ASTValue, AggregateCall, AggregateFunc},
+    decimal::Decimal,
+};
+use storage::{Record, btree::encode_value};
+
+use crate::operator::Operator;
+
+// Hash aggregation.
+//
+// The whole input is read on open. Input rows are bucketed by their GROUP BY values; each bucket keeps one
+// accumulator per aggregate call. Groups come out in the order they were
+// first seen, so a scan in RowId order gives a deterministic result.
+//
+// `group_by` and the calls' columns are row keys (see scope.rs). Each output
+// record holds the GROUP BY keys plus one column per call, under the name
+// paired with it (the call as written, e.g. `SUM(amount)`).
+pub(crate) struct HashAggregate<'a> {
+    input: Box<dyn Operator + 'a>,
+    group_by: Vec<String>,
+    calls: Vec<(String, AggregateCall)>,
+    groups: std::vec::IntoIter<Record>,
+}
+
+impl<'a> HashAggregate<'a> {
+    pub fn new(
+        input: Box<dyn Operator + 'a>,
+        group_by: Vec<String>,
+        calls: Vec<(String, AggregateCall)>,
+    ) -> Self {
+        Self {
+            input,
+            group_by,
+            calls,
+            groups: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl Operator for HashAggregate<'_> {
+    fn open(&mut self) -> Result<(), String> {
+        self.input.open()?;
+        let groups = hash_aggregate(self.input.as_mut(), &self.group_by, &self.calls)?;
+        self.input.close();
+        self.groups = groups.into_iter();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Record>, String> {
+        Ok(self.groups.next())
+    }
+
+    fn close(&mut self) {
+        self.groups = Vec::new().into_iter();
+    }
+}
+
+fn hash_aggregate(
+    input: &mut dyn Operator,
+    group_by: &[String],
+    calls: &[(String, AggregateCall)],
+) -> Result<Vec<Record>, String> {
+    // NULL keys encode to None, so all NULLs land in one group.
+    let mut slots: HashMap<Vec<Option<Vec<u8>>>, usize> = HashMap::new();
+    let mut groups: Vec<(Vec<ASTValue>, Vec<Accumulator>)> = Vec::new();
+
+    while let Some(rec) = input.next()? {
+        let values: Vec<ASTValue> = group_by
+            .iter()
+            .map(|c| rec.get_value(c).cloned().unwrap_or(ASTValue::Null))
+            .collect();
+        let key = values.iter().map(encode_value).collect();
+        let slot = *slots.entry(key).or_insert_with(|| {
+            let accs = calls
+                .iter()
+                .map(|(_, c)| Accumulator::new(c.func))
+                .collect();
+            groups.push((values, accs));
+            groups.len() - 1
+        });
+        for (acc, (_, call)) in groups[slot].1.iter_mut().zip(calls) {
+            let value = match &call.column {
+                Some(c) => rec.get_value(&c.column).unwrap_or(&ASTValue::Null),
+                None => &ASTValue::Boolean(true), // COUNT(*) counts every row
+            };
+            acc.update(value, call)?;
+        }
+    }
+
+    // Without GROUP BY there is exactly one group, even over no rows.
+    if group_by.is_empty() && groups.is_empty() {
+        let accs = calls
+            .iter()
+            .map(|(_, c)| Accumulator::new(c.func))
+            .collect();
+        groups.push((Vec::new(), accs));
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|(values, accs)| {
+            let mut out = Record::new(0);
+            for (column, value) in group_by.iter().zip(values) {
+                out.set_value(column, value);
+            }
+            for ((name, _), acc) in calls.iter().zip(accs) {
+                out.set_value(name, acc.finish());
+            }
+            out
+        })
+        .collect())
+}
+
+enum Accumulator {
+    Count(i64),
+    /// Running total; NULL until the first non-NULL input.
+    Sum(ASTValue),
+    /// Like Sum, but integers are added as floats.
+    Avg {
+        sum: ASTValue,
+        count: i64,
+    },
+    Min(ASTValue),
+    Max(ASTValue),
+}
+
+impl Accumulator {
+    fn new(func: AggregateFunc) -> Self {
+        match func {
+            AggregateFunc::Count => Accumulator::Count(0),
+            AggregateFunc::Sum => Accumulator::Sum(ASTValue::Null),
+            AggregateFunc::Avg => Accumulator::Avg {
+                sum: ASTValue::Null,
+                count: 0,
+            },
+            AggregateFunc::Min => Accumulator::Min(ASTValue::Null),
+            AggregateFunc::Max => Accumulator::Max(ASTValue::Null),
+        }
+    }
+
+    // Fold one input value in. NULL inputs are skipped by every aggregate.
+    fn update(&mut self, value: &ASTValue, call: &AggregateCall) -> Result<(), String> {
+        if *value == ASTValue::Null {
+            return Ok(());
+        }
+        match self {
+            Accumulator::Count(n) => *n += 1,
+            Accumulator::Sum(total) => {
+                *total = match (&*total, value) {
+                    (ASTValue::Null, v) => v.clone(),
+                    (ASTValue::Int(a), ASTValue::Int(b)) => ASTValue::Int(
+                        a.checked_add(*b)
+                            .ok_or_else(|| format!("Integer overflow in {}", call))?,
+                    ),
+                    (a, b) => add(a, b, call)?,
+                };
+            }
+            Accumulator::Avg { sum, count } => {
+                *sum = match (&*sum, value) {
+                    (ASTValue::Null, ASTValue::Decimal(d)) => ASTValue::Decimal(*d),
+                    (ASTValue::Null, v) => ASTValue::Float(as_f64(v, call)?),
+                    (a, b) => add(a, b, call)?,
+                };
+                *count += 1;
+            }
+            Accumulator::Min(best) => {
+                if *best == ASTValue::Null || value.partial_cmp(best) == Some(Ordering::Less) {
+                    *best = value.clone();
+                }
+            }
+            Accumulator::Max(best) => {
+                if *best == ASTValue::Null || value.partial_cmp(best) == Some(Ordering::Greater) {
+                    *best = value.clone();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> ASTValue {
+        match self {
+            Accumulator::Count(n) => ASTValue::Int(n),
+            Accumulator::Avg { sum, count } => match sum {
+                ASTValue::Decimal(d) => d.checked_div(&Decimal::from(count)).map_or(
+                    ASTValue::Float(d.to_f64() / count as f64),
+                    ASTValue::Decimal,
+                ),
+                ASTValue::Float(f) => ASTValue::Float(f / count as f64),
+                _ => ASTValue::Null,
+            },
+            Accumulator::Sum(v) | Accumulator::Min(v) | Accumulator::Max(v) => v,
+        }
+    }
+}
+
+// a + b for SUM and AVG: exact while both are integers or decimals, a float
+// once a float is involved.
+fn add(a: &ASTValue, b: &ASTValue, call: &AggregateCall) -> Result<ASTValue, String> {
+    let decimal = |v: &ASTValue| match v {
+        ASTValue::Int(i) => Some(Decimal::from(*i)),
+        ASTValue::Decimal(d) => Some(*d),
+        _ => None,
+    };
+    match (decimal(a), decimal(b)) {
+        (Some(x), Some(y)) => x
+            .checked_add(&y)
+            .map(ASTValue::Decimal)
+            .ok_or_else(|| format!("Decimal overflow in {}", call)),
+        _ => Ok(ASTValue::Float(as_f64(a, call)? + as_f64(b, call)?)),
+    }
+}
+
+fn as_f64(v: &ASTValue, call: &AggregateCall) -> Result<f64, String> {
+    match v {
+        ASTValue::Int(i) => Ok(*i as f64),
+        ASTValue::Float(f) => Ok(*f),
+        ASTValue::Decimal(d) => Ok(d.to_f64()),
+        _ => Err(format!("{} needs a numeric column", call)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::TestDb;
+
+    fn db() -> TestDb {
+        let mut db = TestDb::new("aggregate");
+        db.run("CREATE TABLE t (id INTEGER PRIMARY KEY, g TEXT NULL, n INTEGER NULL);")
+            .unwrap();
+        db
+    }
+
+    const ALL: &str = "SELECT COUNT(*), COUNT(n), SUM(n), AVG(n), MIN(n), MAX(n) FROM t";
+
+    #[test]
+    fn test_aggregates_over_no_rows() {
+        let mut db = db();
+        // Without GROUP BY there is always one row; with it, one per group.
+        assert_eq!(
+            db.query(&format!("{};", ALL)).unwrap(),
+            [["0", "0", "NULL", "NULL", "NULL", "NULL"]]
+        );
+        assert!(
+            db.query("SELECT g, COUNT(*) FROM t GROUP BY g;")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_aggregates_skip_nulls() {
+        let mut db = db();
+        db.run(
+            "INSERT INTO t VALUES (1, 'a', NULL), (2, 'a', NULL), (3, 'b', 4), (4, 'b', NULL),
+             (5, 'b', 8), (6, NULL, 1), (7, NULL, NULL);",
+        )
+        .unwrap();
+        assert_eq!(
+            db.query(&format!("{} WHERE g = 'a';", ALL)).unwrap(),
+            [["2", "0", "NULL", "NULL", "NULL", "NULL"]]
+        );
+        assert_eq!(
+            db.query(&format!("{};", ALL)).unwrap(),
+            [["7", "3", "13", "4.333333333333333", "1", "8"]]
+        );
+        // NULL keys form one group of their own.
+        assert_eq!(
+            db.query(
+                "SELECT g, COUNT(*), SUM(n) FROM t GROUP BY g HAVING COUNT(*) > 1 ORDER BY g;"
+            )
+            .unwrap(),
+            [["NULL", "2", "1"], ["a", "2", "NULL"], ["b", "3", "12"]]
+        );
+    }
+}
@@ -1,16 +1,22 @@
-use catalog::Catalog;
-use sql::ast::{ASTNode, ASTValue, Assignment, ColumnDefinition, Condition, ShowType};
-use std::collections::HashMap;
+use catalog::{Catalog, error::CatalogError};
+use sql::ast::{
+    ASTNode, ASTValue, AlterAction, Assignment, ColumnConstraint, ColumnDefinition, DefaultExpr,
+    Expr, ForeignKey, InsertValue, Select, ShowType,
+};
 use storage::{
     Record, Table,
-    page::iter_slots,
-    record::deserialize_record_for_page,
-    types::{Column, TupleLoc},
+    record::{EvalContext, coerce_value},
+    types::Column,
 };
 use tracing::info;
 
 use super::result::{ExecutionResult, QueryResult};
-use crate::{Executor, result::ResultSet};
+use crate::{
+    Executor,
+    physical::PhysicalPlan,
+    plan::{plan_assignments, plan_rows, plan_select},
+    result::ResultSet,
+};
 
 pub struct QueryExecutor;
 
@@ -21,45 +27,151 @@ impl Default for QueryExecutor {
 }
 
 impl Executor for QueryExecutor {
-    fn execute(&mut self, cat: &mut dyn Catalog, ast: ASTNode) -> ExecutionResult {
+    fn execute<'a>(&mut self, cat: &'a mut dyn Catalog, ast: ASTNode) -> ExecutionResult<'a> {
+        if cat.transaction_aborted() && !matches!(ast, ASTNode::Commit | ASTNode::Rollback) {
+            return Err(CatalogError::TransactionAborted.to_string());
+        }
         match ast {
-            ASTNode::Select {
-                columns,
+            ASTNode::Select(select) => QueryExecutor::execute_select(cat, select),
+            ASTNode::Insert {
                 table_name,
-                where_clause,
-            } => QueryExecutor::execute_select(cat, columns, table_name, where_clause),
-            ASTNode::Insert { table_name, values } => {
-                QueryExecutor::execute_insert(cat, table_name, values)
-            }
+                columns,
+                rows,
+            } => QueryExecutor::atomically(cat, |cat| {
+                QueryExecutor::execute_insert(cat, table_name, columns, rows)
+            }),
             ASTNode::Update {
                 table_name,
                 assignments,
                 where_clause,
-            } => QueryExecutor::execute_update(cat, table_name, assignments, where_clause),
+            } => QueryExecutor::atomically(cat, |cat| {
+                QueryExecutor::execute_update(cat, table_name, assignments, where_clause)
+            }),
             ASTNode::Delete {
                 table_name,
                 where_clause,
-            } => QueryExecutor::execute_delete(cat, table_name, where_clause),
+            } => QueryExecutor::atomically(cat, |cat| {
+                QueryExecutor::execute_delete(cat, table_name, where_clause)
+            }),
             ASTNode::CreateTable {
                 table_name,
                 columns,
-            } => QueryExecutor::execute_create_table(cat, table_name, columns),
-            ASTNode::CreateDatabase { database_name } => {
-                cat.create_database(&database_name)
-                    .map_err(|e| e.to_string())?;
-                Ok(QueryResult::Create)
-            }
+                foreign_keys,
+                if_not_exists,
+            } => QueryExecutor::execute_create_table(
+                cat,
+                table_name,
+                columns,
+                foreign_keys,
+                if_not_exists,
+            ),
+            ASTNode::CreateDatabase {
+                database_name,
+                if_not_exists,
+            } => match cat.create_database(&database_name) {
+                Err(CatalogError::AlreadyExists { .. }) if if_not_exists => Ok(
+                    QueryResult::Skipped(format!("Database {} already exists", database_name)),
+                ),
+                res => {
+                    res.map_err(|e| e.to_string())?;
+                    Ok(QueryResult::Create)
+                }
+            },
             ASTNode::USE { database_name } => {
-                let _ = cat.use_database(&database_name);
+                cat.use_database(&database_name)
+                    .map_err(|e| e.to_string())?;
                 Ok(QueryResult::Use(database_name))
             }
             ASTNode::Show { show_type } => QueryExecutor::execute_show(cat, show_type),
+            ASTNode::Begin => {
+                cat.begin_transaction().map_err(|e| e.to_string())?;
+                Ok(QueryResult::Transaction("BEGIN"))
+            }
+            ASTNode::Commit => {
+                cat.commit_transaction().map_err(|e| e.to_string())?;
+                Ok(QueryResult::Transaction("COMMIT"))
+            }
+            ASTNode::Rollback => {
+                cat.rollback_transaction().map_err(|e| e.to_string())?;
+                Ok(QueryResult::Transaction("ROLLBACK"))
+            }
+            ASTNode::CreateIndex {
+                index_name,
+                table_name,
+                column_name,
+            } => QueryExecutor::execute_create_index(cat, index_name, table_name, column_name),
+            ASTNode::DropIndex { index_name } => {
+                cat.drop_index(&index_name).map_err(|e| e.to_string())?;
+                Ok(QueryResult::Drop)
+            }
+            ASTNode::DropTable {
+                table_name,
+                if_exists,
+            } => match cat.drop_table(&table_name) {
+                Err(CatalogError::TableDoesNotExist { .. }) if if_exists => Ok(
+                    QueryResult::Skipped(format!("Table {} does not exist", table_name)),
+                ),
+                res => {
+                    res.map_err(|e| e.to_string())?;
+                    Ok(QueryResult::Drop)
+                }
+            },
+            ASTNode::DropDatabase {
+                database_name,
+                if_exists,
+            } => match cat.drop_database(&database_name) {
+                Err(CatalogError::DatabaseDirMissing { .. }) if if_exists => Ok(
+                    QueryResult::Skipped(format!("Database {} does not exist", database_name)),
+                ),
+                res => {
+                    res.map_err(|e| e.to_string())?;
+                    Ok(QueryResult::Drop)
+                }
+            },
+            ASTNode::Truncate { table_name } => {
+                cat.truncate_table(&table_name).map_err(|e| e.to_string())?;
+                Ok(QueryResult::Truncate)
+            }
+            ASTNode::AlterTable { table_name, action } => {
+                QueryExecutor::execute_alter_table(cat, table_name, action)
+            }
+            ASTNode::Explain { statement } => QueryExecutor::execute_explain(cat, *statement),
         }
     }
 }
 
 impl QueryExecutor {
-    fn execute_show(cat: &mut dyn Catalog, show_type: ShowType) -> ExecutionResult {
+    // Run a data-modifying statement as one unit. Outside BEGIN/COMMIT the
+    // statement gets its own transaction; inside one, a failing statement
+    // aborts the transaction so no partial batch survives, and later
+    // statements are refused until ROLLBACK.
+    fn atomically<F>(cat: &mut dyn Catalog, f: F) -> ExecutionResult<'static>
+    where
+        F: FnOnce(&mut dyn Catalog) -> ExecutionResult<'static>,
+    {
+        let autocommit = !cat.in_transaction();
+        if autocommit {
+            cat.begin_transaction().map_err(|e| e.to_string())?;
+        }
+        match f(cat) {
+            Ok(res) => {
+                if autocommit {
+                    cat.commit_transaction().map_err(|e| e.to_string())?;
+                }
+                Ok(res)
+            }
+            Err(e) if autocommit => {
+                cat.rollback_transaction().map_err(|e| e.to_string())?;
+                Err(e)
+            }
+            Err(e) => {
+                cat.abort_transaction().map_err(|e| e.to_string())?;
+                Err(format!("{}; transaction aborted, ROLLBACK to end it", e))
+            }
+        }
+    }
+
+    fn execute_show(cat: &mut dyn Catalog, show_type: ShowType) -> ExecutionResult<'static> {
         match show_type {
             ShowType::DATABASES => {
                 let list = cat.list_databases().map_err(|e| e.to_string())?;
@@ -72,283 +184,201 @@ impl QueryExecutor {
         }
     }
 
-    fn execute_select(
-        cat: &mut dyn Catalog,
-        columns: Vec<String>,
-        table_name: String,
-        where_clause: Option<Condition>,
-    ) -> ExecutionResult {
-        let (columns, table_columns, is_empty) = {
-            let table = match cat.get_table(&table_name) {
-                Some(t) => t,
-                None => return Err(format!("Table '{}' not found", table_name)),
-            };
+    fn execute_select(cat: &mut dyn Catalog, select: Select) -> ExecutionResult<'_> {
+        let logical = plan_select(cat, select)?;
+        let plan = PhysicalPlan::from_logical(cat, logical);
+        let columns = plan.output_columns();
+        let rows = ResultSet::new(columns, plan.into_operator(cat, EvalContext::now()))?;
+        Ok(QueryResult::Select(rows))
+    }
 
-            let resolved_columns = if columns.len() == 1 && columns[0] == "*" {
-                table
-                    .columns
-                    .iter()
-                    .map(|c| c.name.clone())
-                    .collect::<Vec<_>>()
-            } else {
-                columns
-            };
+    // Plan the rows an UPDATE or DELETE on `table_name` touches.
+    fn plan_dml(
+        cat: &mut dyn Catalog,
+        table_name: &str,
+        where_clause: Option<&Expr>,
+    ) -> Result<PhysicalPlan, String> {
+        let columns = match cat.get_table(table_name) {
+            Some(t) => t.columns.clone(),
+            None => return Err(format!("Table '{}' not found", table_name)),
+        };
+        let logical = plan_rows(table_name, columns, where_clause)?;
+        Ok(PhysicalPlan::from_logical(cat, logical))
+    }
 
-            // Validate column names early
-            for c in &resolved_columns {
-                if !table.columns.iter().any(|col| &col.name == c) {
-                    return Err(format!("Unknown column '{}' in table '{}'", c, table_name));
-                }
+    fn execute_explain(cat: &mut dyn Catalog, statement: ASTNode) -> ExecutionResult<'static> {
+        let lines = match statement {
+            ASTNode::Select(select) => {
+                let logical = plan_select(cat, select)?;
+                PhysicalPlan::from_logical(cat, logical).explain()
             }
-
-            let is_empty = table.scan().next().is_none();
-
-            (resolved_columns, table.columns.clone(), is_empty)
+            ASTNode::Update {
+                table_name,
+                assignments,
+                where_clause,
+            } => {
+                let plan = QueryExecutor::plan_dml(cat, &table_name, where_clause.as_ref())?;
+                let set: Vec<&str> = assignments.iter().map(|a| a.column.as_str()).collect();
+                let mut lines = vec![format!("Update {} set [{}]", table_name, set.join(", "))];
+                lines.extend(plan.explain().into_iter().map(|l| format!("  {}", l)));
+                lines
+            }
+            ASTNode::Delete {
+                table_name,
+                where_clause,
+            } => {
+                let plan = QueryExecutor::plan_dml(cat, &table_name, where_clause.as_ref())?;
+                let mut lines = vec![format!("Delete {}", table_name)];
+                lines.extend(plan.explain().into_iter().map(|l| format!("  {}", l)));
+                lines
+            }
+            _ => return Err("EXPLAIN supports SELECT, UPDATE and DELETE".to_string()),
         };
+        Ok(QueryResult::Info(lines))
+    }
 
-        if is_empty {
-            let mut rs = ResultSet::new(columns.clone());
-
-            let pages = cat.seq_scan_pages(&table_name).map_err(|e| e.to_string())?;
-
-            // Collect only the latest version per RowId
-            let mut latest: HashMap<u64, (u32, u16, Record)> = HashMap::new();
-
-            for (pid, page) in pages.iter().enumerate() {
-                let slots = iter_slots(page).map_err(|e| e.clone())?;
-                for (sid, (off, len, flags)) in slots.enumerate() {
-                    if flags != 0 {
-                        continue;
-                    }
-                    let start = off as usize;
-                    let end = start + len as usize;
-                    if end > page.len() {
-                        continue;
-                    }
-
-                    let payload = &page[start..end];
-                    let (row_id, rec) = deserialize_record_for_page(payload, &table_columns)?;
+    fn execute_insert(
+        cat: &mut dyn Catalog,
+        table_name: String,
+        target: Option<Vec<String>>,
+        rows: Vec<Vec<InsertValue>>,
+    ) -> ExecutionResult<'static> {
+        info!(table = %table_name, rows = rows.len(), "insert.start");
+        let columns = match cat.get_table(&table_name) {
+            Some(t) => t.columns.clone(),
+            None => return Err(format!("Table '{}' not found", table_name)),
+        };
 
-                    // WHERE filtering
-                    if let Some(cond) = &where_clause {
-                        if !rec.evaluate_condition(cond) {
-                            continue;
-                        }
-                    }
-                    let prefer_this = match cat
-                        .get_tuple_loc(&table_name, row_id)
-                        .map_err(|e| e.to_string())?
-                    {
-                        Some(loc) if loc.page_id == pid as u32 && loc.slot_id == sid as u16 => true,
-                        _ => match latest.get(&row_id) {
-                            Some((prev_pid, prev_sid, _)) => {
-                                (pid as u32, sid as u16) > (*prev_pid, *prev_sid)
-                            }
-                            None => true,
-                        },
-                    };
-                    if prefer_this {
-                        latest.insert(row_id, (pid as u32, sid as u16, rec));
+        // Position in each VALUES row of every table column, if listed.
+        let positions: Vec<Option<usize>> = match &target {
+            Some(names) => {
+                for (i, name) in names.iter().enumerate() {
+                    if !columns.iter().any(|c| &c.name == name) {
+                        return Err(format!("Column '{}' not found in '{}'", name, table_name));
                     }
-                }
-            }
-
-            for (_, (_, _, rec)) in latest.into_iter() {
-                let mut out = Record::new(0);
-                for c in &columns {
-                    match rec.get_value(c) {
-                        Some(v) => out.set_value(c, v.clone()),
-                        None => return Err(format!("Column '{}' missing in record", c)),
+                    if names[..i].contains(name) {
+                        return Err(format!("Column '{}' listed more than once", name));
                     }
                 }
-                rs.add_record(out);
+                columns
+                    .iter()
+                    .map(|c| names.iter().position(|n| *n == c.name))
+                    .collect()
             }
-
-            return Ok(QueryResult::Select(rs));
-        }
-
-        for c in &columns {
-            if !table_columns.iter().any(|col| &col.name == c) {
-                return Err(format!("Unknown column '{}' in table '{}'", c, table_name));
+            None => (0..columns.len()).map(Some).collect(),
+        };
+        let width = target.as_ref().map_or(columns.len(), |names| names.len());
+
+        let mut records = Vec::with_capacity(rows.len());
+        // Highest AUTOINCREMENT value given explicitly so far; generated
+        // values for later rows of the batch skip past it.
+        let mut max_explicit = i64::MIN;
+        for values in rows {
+            if values.len() != width {
+                return Err(format!(
+                    "Column count mismatch. Expected {}, got {}",
+                    width,
+                    values.len()
+                ));
             }
-        }
-
-        let mut rs = ResultSet::new(columns.clone());
 
-        let table = cat
-            .get_table(&table_name)
-            .ok_or(format!("Table '{}' not found", table_name))?;
-        for rec in table.scan() {
-            if let Some(cond) = &where_clause {
-                if !rec.evaluate_condition(cond) {
-                    continue;
+            // Columns not listed or given as DEFAULT take their AUTOINCREMENT
+            // value, their DEFAULT, or NULL.
+            let mut record = Record::new(0);
+            for (col, pos) in columns.iter().zip(&positions) {
+                let val = match pos.map(|p| &values[p]) {
+                    Some(InsertValue::Value(v)) => v.clone(),
+                    Some(InsertValue::Default) | None => {
+                        if col.auto_increment {
+                            let next = cat
+                                .next_auto_value(&table_name, max_explicit)
+                                .map_err(|e| e.to_string())?;
+                            ASTValue::Int(next)
+                        } else if let Some(default) = &col.default {
+                            eval_default(default)?
+                        } else if col.nullable {
+                            ASTValue::Null
+                        } else {
+                            return Err(format!("No value for NOT NULL column '{}'", col.name));
+                        }
+                    }
+                };
+                if col.auto_increment
+                    && let ASTValue::Int(v) = val
+                {
+                    max_explicit = max_explicit.max(v);
                 }
-            }
 
-            let mut out = Record::new(0);
-            for c in &columns {
-                match rec.get_value(c) {
-                    Some(v) => out.set_value(c, v.clone()),
-                    None => return Err(format!("Column '{}' missing in record", c)),
+                if !col.nullable && matches!(val, ASTValue::Null) {
+                    return Err(format!("NOT NULL violation for column '{}'", col.name));
                 }
-            }
-            rs.add_record(out);
-        }
-
-        Ok(QueryResult::Select(rs))
-    }
-
-    fn execute_insert(
-        cat: &mut dyn Catalog,
-        table_name: String,
-        values: Vec<ASTValue>,
-    ) -> ExecutionResult {
-        info!(table = %table_name, "insert.start");
-        let table = match cat.get_table_mut(&table_name) {
-            Some(t) => t,
-            None => return Err(format!("Table '{}' not found", table_name)),
-        };
-
-        if values.len() != table.columns.len() {
-            return Err(format!(
-                "Column count mismatch. Expected {}, got {}",
-                table.columns.len(),
-                values.len()
-            ));
-        }
+                let val = coerce_value(val, &col.data_type)
+                    .map_err(|e| format!("{} in column '{}'", e, col.name))?;
 
-        let mut record = Record::new(0);
-        for (col, val) in table.columns.iter().zip(values.into_iter()) {
-            if !col.nullable && matches!(val, ASTValue::Null) {
-                return Err(format!("NOT NULL violation for column '{}'", col.name));
+                record.set_value(&col.name, val);
             }
 
-            let ok = matches!(
-                (&val, &col.data_type),
-                (ASTValue::Null, _)
-                    | (ASTValue::Int(_), types::tokens::DataType::INTEGER)
-                    | (ASTValue::Float(_), types::tokens::DataType::FLOAT)
-                    | (ASTValue::Boolean(_), types::tokens::DataType::BOOLEAN)
-                    | (ASTValue::String(_), types::tokens::DataType::TEXT)
-                    | (ASTValue::String(_), types::tokens::DataType::CHAR)
-                    | (ASTValue::String(_), types::tokens::DataType::BLOB)
-                    | (ASTValue::String(_), types::tokens::DataType::JSON)
-            );
-            if !ok {
-                return Err(format!("Type mismatch for column '{}'", col.name));
+            if let Err(e) = record.validate(&columns) {
+                return Err(format!("Record validation failed: {}", e));
             }
 
-            record.set_value(&col.name, val);
-        }
-
-        if let Err(e) = record.validate(&table.columns) {
-            return Err(format!("Record validation failed: {}", e));
+            let row_id = cat.next_row_id(&table_name).map_err(|e| e.to_string())?;
+            record.id = row_id;
+            records.push((row_id, record));
         }
 
-        let row_id = cat.next_row_id(&table_name).map_err(|e| e.to_string())?;
-        record.id = row_id;
-
-        let _loc = cat
-            .append_record(&table_name, row_id, &record)
+        let locs = cat
+            .append_records(&table_name, &records)
             .map_err(|e| e.to_string())?;
-        Ok(QueryResult::Insert(1))
+        Ok(QueryResult::Insert(locs.len() as u64))
     }
 
     fn execute_update(
         cat: &mut dyn Catalog,
         table_name: String,
         assignments: Vec<Assignment>,
-        where_clause: Option<Condition>,
-    ) -> ExecutionResult {
+        where_clause: Option<Expr>,
+    ) -> ExecutionResult<'static> {
         let columns = match cat.get_table(&table_name) {
             Some(t) => t.columns.clone(),
             None => return Err(format!("Table '{}' not found", table_name)),
         };
 
-        for a in &assignments {
-            if !columns.iter().any(|c| c.name == a.column) {
-                return Err(format!(
-                    "Unknown column '{}' in table '{}'",
-                    a.column, table_name
-                ));
-            }
-        }
-
-        let pages = cat.seq_scan_pages(&table_name).map_err(|e| e.to_string())?;
-
-        let mut latest: HashMap<u64, (u32, u16, storage::Record)> = HashMap::new();
-
-        for (pid, page) in pages.iter().enumerate() {
-            let slots = iter_slots(page).map_err(|e| e.to_string())?;
-            for (sid, (off, len, flags)) in slots.enumerate() {
-                if flags != 0 {
-                    continue;
-                }
-                let start = off as usize;
-                let end = start + len as usize;
-                if end > page.len() {
-                    continue;
-                }
-
-                let payload = &page[start..end];
-                let (row_id, rec) =
-                    storage::record::deserialize_record_for_page(payload, &columns)?;
-
-                if let Some(cond) = &where_clause {
-                    if !rec.evaluate_condition(cond) {
-                        continue;
-                    }
-                }
-
-                match latest.get(&row_id) {
-                    Some((prev_pid, prev_sid, _)) => {
-                        if (pid as u32, sid as u16) > (*prev_pid, *prev_sid) {
-                            latest.insert(row_id, (pid as u32, sid as u16, rec));
-                        }
-                    }
-                    None => {
-                        latest.insert(row_id, (pid as u32, sid as u16, rec));
-                    }
-                }
-            }
-        }
+        let assignments = plan_assignments(&table_name, &columns, assignments)?;
+        let plan = QueryExecutor::plan_dml(cat, &table_name, where_clause.as_ref())?;
+        // One clock reading for the WHERE and every SET expression.
+        let ctx = EvalContext::now();
+        let rows = plan.execute(cat, ctx)?;
 
         let mut updated = 0u64;
-        for (row_id, (pid, sid, mut rec)) in latest.into_iter() {
-            for a in &assignments {
-                let col = columns.iter().find(|c| c.name == a.column).unwrap();
-                if !col.nullable && matches!(a.value, ASTValue::Null) {
+        for mut rec in rows {
+            let Some(old_loc) = cat
+                .get_tuple_loc(&table_name, rec.id)
+                .map_err(|e| e.to_string())?
+            else {
+                continue;
+            };
+            // Every SET expression sees the row as it was before the update.
+            let values = assignments
+                .iter()
+                .map(|(_, expr)| rec.evaluate(expr, &ctx))
+                .collect::<Result<Vec<_>, _>>()?;
+            for ((column, _), value) in assignments.iter().zip(values) {
+                let col = columns.iter().find(|c| c.name == *column).unwrap();
+                if !col.nullable && value == ASTValue::Null {
                     return Err(format!("NOT NULL violation for column '{}'", col.name));
                 }
-                let ok = matches!(
-                    (&a.value, &col.data_type),
-                    (ASTValue::Null, _)
-                        | (ASTValue::Int(_), types::tokens::DataType::INTEGER)
-                        | (ASTValue::Float(_), types::tokens::DataType::FLOAT)
-                        | (ASTValue::Boolean(_), types::tokens::DataType::BOOLEAN)
-                        | (ASTValue::String(_), types::tokens::DataType::TEXT)
-                        | (ASTValue::String(_), types::tokens::DataType::CHAR)
-                        | (ASTValue::String(_), types::tokens::DataType::BLOB)
-                        | (ASTValue::String(_), types::tokens::DataType::JSON)
-                );
-                if !ok {
-                    return Err(format!("Type mismatch for column '{}'", col.name));
-                }
-                rec.set_value(&a.column, a.value.clone());
+                let value = coerce_value(value, &col.data_type)
+                    .map_err(|e| format!("{} in column '{}'", e, col.name))?;
+                rec.set_value(column, value);
             }
 
             if let Err(e) = rec.validate(&columns) {
                 return Err(format!("Record validation failed: {}", e));
             }
 
-            let old_loc = TupleLoc {
-                seg: 1,
-                page_id: pid,
-                slot_id: sid,
-                flags: 0,
-            };
             let _ = cat
-                .update_record(&table_name, old_loc, row_id, &rec)
+                .update_record(&table_name, old_loc, rec.id, &rec)
                 .map_err(|e| e.to_string())?;
             updated += 1;
         }
@@ -359,58 +389,19 @@ impl QueryExecutor {
     fn execute_delete(
         cat: &mut dyn Catalog,
         table_name: String,
-        where_clause: Option<Condition>,
-    ) -> ExecutionResult {
-        let columns = match cat.get_table(&table_name) {
-            Some(t) => t.columns.clone(),
-            None => return Err(format!("Table '{}' not found", table_name)),
-        };
-
-        let pages = cat.seq_scan_pages(&table_name).map_err(|e| e.to_string())?;
-
-        let mut latest: HashMap<u64, (u32, u16)> = HashMap::new();
-
-        for (pid, page) in pages.iter().enumerate() {
-            let slots = iter_slots(page).map_err(|e| e.to_string())?;
-            for (sid, (off, len, flags)) in slots.enumerate() {
-                if flags != 0 {
-                    continue;
-                }
-                let start = off as usize;
-                let end = start + len as usize;
-                if end > page.len() {
-                    continue;
-                }
-
-                let payload = &page[start..end];
-                let (row_id, rec) = deserialize_record_for_page(payload, &columns)?;
-
-                if let Some(cond) = &where_clause {
-                    if !rec.evaluate_condition(cond) {
-                        continue;
-                    }
-                }
-
-                match latest.get(&row_id) {
-                    Some((prev_pid, prev_sid)) => {
-                        if (pid as u32, sid as u16) > (*prev_pid, *prev_sid) {
-                            latest.insert(row_id, (pid as u32, sid as u16));
-                        }
-                    }
-                    None => {
-                        latest.insert(row_id, (pid as u32, sid as u16));
-                    }
-                }
-            }
-        }
+        where_clause: Option<Expr>,
+    ) -> ExecutionResult<'static> {
+        let plan = QueryExecutor::plan_dml(cat, &table_name, where_clause.as_ref())?;
+        let rows = plan.execute(cat, EvalContext::now())?;
 
         let mut deleted = 0u64;
-        for (_row_id, (pid, sid)) in latest.into_iter() {
-            let old_loc = TupleLoc {
-                seg: 1,
-                page_id: pid,
-                slot_id: sid,
-                flags: 0,
+        for rec in rows {
+            // ON DELETE CASCADE may already have removed a later row.
+            let Some(old_loc) = cat
+                .get_tuple_loc(&table_name, rec.id)
+                .map_err(|e| e.to_string())?
+            else {
+                continue;
             };
             cat.tombstone(&table_name, old_loc)
                 .map_err(|e| e.to_string())?;
@@ -420,27 +411,74 @@ impl QueryExecutor {
         Ok(QueryResult::Delete(deleted))
     }
 
+    fn execute_create_index(
+        cat: &mut dyn Catalog,
+        index_name: String,
+        table_name: String,
+        column_name: String,
+    ) -> ExecutionResult<'static> {
+        cat.create_index(&table_name, &index_name, &column_name)
+            .map_err(|e| e.to_string())?;
+        Ok(QueryResult::Create)
+    }
+
     fn execute_create_table(
         cat: &mut dyn Catalog,
         table_name: String,
         column_defs: Vec<ColumnDefinition>,
-    ) -> ExecutionResult {
+        mut foreign_keys: Vec<ForeignKey>,
+        if_not_exists: bool,
+    ) -> ExecutionResult<'static> {
+        for d in &column_defs {
+            for c in &d.columns_constraints {
+                if let ColumnConstraint::References(fk) = c {
+                    foreign_keys.push(fk.clone());
+                }
+            }
+        }
         let cols: Vec<Column> = column_defs
             .into_iter()
-            .map(|d| {
-                Column::new(
-                    d.column_name,
-                    d.column_type,
-                    d.columns_constraints
-                        .iter()
-                        .any(|c| c == &vec!['N', 'U', 'L', 'L']),
-                )
-            })
+            .map(column_from_definition)
             .collect();
-        let table = Table::new(table_name.clone(), cols);
-        cat.create_table(table_name, table)
-            .map_err(|e| e.to_string())?;
-        Ok(QueryResult::Create)
+        for col in &cols {
+            check_default(col)?;
+        }
+        let mut table = Table::new(table_name.clone(), cols);
+        table.foreign_keys = foreign_keys;
+        match cat.create_table(table_name.clone(), table) {
+            Err(CatalogError::TableExists { .. }) if if_not_exists => Ok(QueryResult::Skipped(
+                format!("Table {} already exists", table_name),
+            )),
+            res => {
+                res.map_err(|e| e.to_string())?;
+                Ok(QueryResult::Create)
+            }
+        }
+    }
+
+    fn execute_alter_table(
+        cat: &mut dyn Catalog,
+        table_name: String,
+        action: AlterAction,
+    ) -> ExecutionResult<'static> {
+        let res = match action {
+            AlterAction::AddColumn(def) => {
+                if def
+                    .columns_constraints
+                    .iter()
+                    .any(|c| matches!(c, ColumnConstraint::References(_)))
+                {
+                    return Err("REFERENCES is not supported in ALTER TABLE ADD COLUMN".to_string());
+                }
+                let col = column_from_definition(def);
+                check_default(&col)?;
+                cat.add_column(&table_name, col)
+            }
+            AlterAction::DropColumn(column) => cat.drop_column(&table_name, &column),
+            AlterAction::RenameColumn { from, to } => cat.rename_column(&table_name, &from, &to),
+        };
+        res.map_err(|e| e.to_string())?;
+        Ok(QueryResult::Alter)
     }
 
     fn _create_record_from_values(
@@ -465,3 +503,360 @@ impl QueryExecutor {
         Ok(record)
     }
 }
+
+// The table column a CREATE TABLE or ADD COLUMN definition declares.
+fn column_from_definition(d: ColumnDefinition) -> Column {
+    let has = |c: ColumnConstraint| d.columns_constraints.contains(&c);
+    let primary_key = has(ColumnConstraint::PrimaryKey);
+    let unique = has(ColumnConstraint::Unique);
+    let nullable = has(ColumnConstraint::Null) && !has(ColumnConstraint::NotNull) && !primary_key;
+    let auto_increment = has(ColumnConstraint::AutoIncrement);
+    let default = d.columns_constraints.iter().find_map(|c| match c {
+        ColumnConstraint::Default(e) => Some(e.clone()),
+        _ => None,
+    });
+    let mut column = Column::new(d.column_name, d.column_type, nullable);
+    column.primary_key = primary_key;
+    column.unique = unique;
+    column.auto_increment = auto_increment;
+    column.default = default;
+    column
+}
+
+// A column's DEFAULT must evaluate to a value of its type.
+fn check_default(col: &Column) -> Result<(), String> {
+    if let Some(default) = &col.default {
+        let val = eval_default(default)?;
+        if coerce_value(val.clone(), &col.data_type).is_err()
+            || (!col.nullable && val == ASTValue::Null)
+        {
+            return Err(format!(
+                "DEFAULT for column '{}' does not fit its type",
+                col.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Evaluate a column DEFAULT. Integer arithmetic stays integer (and fails on
+// overflow or division by zero); mixing in a float makes the result a float.
+fn eval_default(expr: &DefaultExpr) -> Result<ASTValue, String> {
+    Record::new(0)
+        .evaluate(&expr.to_expr(), &EvalContext::now())
+        .map_err(|e| format!("{} in DEFAULT", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use catalog::Catalog;
+
+    use crate::{result::QueryResult, testing::TestDb};
+
+    #[test]
+    fn test_insert_without_column_list_needs_every_column() {
+        let mut db = TestDb::new("insert-width");
+        db.run("CREATE TABLE t (id INTEGER AUTOINCREMENT PRIMARY KEY, v TEXT DEFAULT 'x', n INTEGER NULL);")
+            .unwrap();
+        assert_eq!(
+            db.run("INSERT INTO t VALUES (1);"),
+            Err("Column count mismatch. Expected 3, got 1".to_string())
+        );
+        db.run("INSERT INTO t VALUES (DEFAULT, DEFAULT, DEFAULT);")
+            .unwrap();
+        db.run("INSERT INTO t (n) VALUES (7);").unwrap();
+        assert_eq!(
+            db.query("SELECT id, v, n FROM t ORDER BY id;").unwrap(),
+            vec![vec!["1", "x", "NULL"], vec!["2", "x", "7"]]
+        );
+    }
+
+    #[test]
+    fn test_explicit_autoincrement_values_move_the_counter_at_once() {
+        let mut db = TestDb::new("insert-auto");
+        db.run("CREATE TABLE t (id INTEGER AUTOINCREMENT PRIMARY KEY, v TEXT);")
+            .unwrap();
+        db.run("INSERT INTO t (id, v) VALUES (50000000, 'a'), (DEFAULT, 'b');")
+            .unwrap();
+        db.run("INSERT INTO t (v) VALUES ('c');").unwrap();
+        db.run("INSERT INTO t (id, v) VALUES (7, 'd'), (DEFAULT, 'e');")
+            .unwrap();
+        assert_eq!(
+            db.query("SELECT id, v FROM t ORDER BY id;").unwrap(),
+            vec![
+                vec!["7", "d"],
+                vec!["50000000", "a"],
+                vec!["50000001", "b"],
+                vec!["50000002", "c"],
+                vec!["50000003", "e"],
+            ]
+        );
+
+        // Nothing is left above the largest key.
+        db.run("INSERT INTO t (id, v) VALUES (9223372036854775807, 'f');")
+            .unwrap();
+        assert_eq!(
+            db.run("INSERT INTO t (v) VALUES ('g');"),
+            Err("AUTOINCREMENT values of t are exhausted".to_string())
+        );
+    }
+
+    #[test]
+    fn test_create_table_if_not_exists_keeps_the_table() {
+        let mut db = TestDb::new("create-table-ine");
+        db.run("CREATE TABLE t (id INTEGER PRIMARY KEY); INSERT INTO t VALUES (1);")
+            .unwrap();
+        assert!(matches!(
+            db.execute("CREATE TABLE IF NOT EXISTS t (id INTEGER PRIMARY KEY, v TEXT);"),
+            Ok(QueryResult::Skipped(_))
+        ));
+        assert!(db.run("CREATE TABLE t (id INTEGER PRIMARY KEY);").is_err());
+        assert_eq!(db.query("SELECT * FROM t;").unwrap(), vec![vec!["1"]]);
+    }
+
+    #[test]
+    fn test_create_database_if_not_exists_stays_in_the_current_database() {
+        let mut db = TestDb::new("create-db-ine");
+        db.run("CREATE DATABASE other; CREATE TABLE t (id INTEGER PRIMARY KEY); INSERT INTO t VALUES (2);")
+            .unwrap();
+        assert!(matches!(
+            db.execute("CREATE DATABASE IF NOT EXISTS db;"),
+            Ok(QueryResult::Skipped(_))
+        ));
+        assert!(db.run("CREATE DATABASE db;").is_err());
+        assert_eq!(db.cat.current_db.as_deref(), Some("other"));
+        assert_eq!(db.query("SELECT * FROM t;").unwrap(), vec![vec!["2"]]);
+    }
+
+    #[test]
+    fn test_result_columns_with_the_same_name_stay_apart() {
+        let mut db = TestDb::new("same-name");
+        db.run("CREATE TABLE a (id INTEGER PRIMARY KEY, v TEXT); INSERT INTO a VALUES (1, 'x');")
+            .unwrap();
+        assert_eq!(
+            db.query("SELECT id AS v, v FROM a;").unwrap(),
+            vec![vec!["1", "x"]]
+        );
+        assert_eq!(
+            db.query("SELECT v, v, id + 1 AS id, id FROM a;").unwrap(),
+            vec![vec!["x", "x", "2", "1"]]
+        );
+    }
+
+    #[test]
+    fn test_now_is_read_once_per_statement() {
+        let mut db = TestDb::new("now");
+        db.run("CREATE TABLE t (id INTEGER PRIMARY KEY, ts TIMESTAMP NULL);")
+            .unwrap();
+        let values: Vec<String> = (1..=3000).map(|i| format!("({}, NULL)", i)).collect();
+        db.run(&format!("INSERT INTO t VALUES {};", values.join(", ")))
+            .unwrap();
+
+        let mut now = db.column("SELECT NOW() FROM t;").unwrap();
+        assert_eq!(now.len(), 3000);
+        now.dedup();
+        assert_eq!(now.len(), 1);
+        assert_eq!(
+            db.column("SELECT COUNT(*) FROM t WHERE CURRENT_TIMESTAMP = NOW();")
+                .unwrap(),
+            ["3000"]
+        );
+
+        db.run("UPDATE t SET ts = NOW();").unwrap();
+        let mut stamped = db.column("SELECT ts FROM t;").unwrap();
+        stamped.dedup();
+        assert_eq!(stamped.len(), 1);
+    }
+
+    #[test]
+    fn test_use_is_refused_inside_a_transaction() {
+        let mut db = TestDb::new("txn-use");
+        db.run("CREATE DATABASE other; USE db; BEGIN;").unwrap();
+        assert_eq!(
+            db.run("USE other;"),
+            Err("A transaction is already in progress".to_string())
+        );
+        assert_eq!(db.cat.current_db.as_deref(), Some("db"));
+        db.run("ROLLBACK; USE other;").unwrap();
+        assert_eq!(db.cat.current_db.as_deref(), Some("other"));
+        assert!(db.run("USE missing;").is_err());
+        assert_eq!(db.cat.current_db.as_deref(), Some("other"));
+    }
+
+    #[test]
+    fn test_failed_statement_aborts_the_transaction() {
+        let aborted = "The transaction was aborted by a failed statement; ROLLBACK to end it";
+        let mut db = TestDb::new("txn-abort");
+        db.run("CREATE TABLE t (id INTEGER PRIMARY KEY); INSERT INTO t VALUES (1);")
+            .unwrap();
+
+        db.run("BEGIN; INSERT INTO t VALUES (2);").unwrap();
+        assert!(db.run("INSERT INTO t VALUES (1);").is_err());
+        // Nothing runs, and nothing is committed, until the transaction ends.
+        assert_eq!(
+            db.run("INSERT INTO t VALUES (3);"),
+            Err(aborted.to_string())
+        );
+        assert_eq!(db.query("SELECT id FROM t;"), Err(aborted.to_string()));
+        db.run("ROLLBACK;").unwrap();
+        assert_eq!(db.column("SELECT id FROM t;").unwrap(), ["1"]);
+
+        // COMMIT of an aborted transaction rolls it back and says so.
+        db.run("BEGIN; INSERT INTO t VALUES (4);").unwrap();
+        assert!(db.run("UPDATE t SET id = 1 WHERE id = 4;").is_err());
+        assert_eq!(db.run("COMMIT;"), Err(aborted.to_string()));
+        assert!(!db.cat.in_transaction());
+        db.run("INSERT INTO t VALUES (5);").unwrap();
+        assert_eq!(
+            db.column("SELECT id FROM t ORDER BY id;").unwrap(),
+            ["1", "5"]
+        );
+    }
+
+    #[test]
+    fn test_truncate_empties_the_table_and_its_indexes() {
+        let mut db = TestDb::new("truncate");
+        db.run("CREATE TABLE t (id INTEGER AUTOINCREMENT PRIMARY KEY, code TEXT UNIQUE);")
+            .unwrap();
+        let values: Vec<String> = (1..=500).map(|i| format!("('c{}')", i)).collect();
+        db.run(&format!(
+            "INSERT INTO t (code) VALUES {};",
+            values.join(", ")
+        ))
+        .unwrap();
+
+        db.run("TRUNCATE TABLE t;").unwrap();
+        assert_eq!(db.column("SELECT COUNT(*) FROM t;").unwrap(), ["0"]);
+        // Old keys are gone from both indexes; the counter is not reset.
+        db.run("INSERT INTO t (id, code) VALUES (1, 'c1'); INSERT INTO t (code) VALUES ('c2');")
+            .unwrap();
+        assert_eq!(
+            db.lines("EXPLAIN SELECT id FROM t WHERE code = 'c2';")
+                .unwrap(),
+            [
+                "Project [id]",
+                "  Filter code = 'c2'",
+                "    IndexScan t using t_code_key (code = 'c2')"
+            ]
+        );
+        assert_eq!(
+            db.column("SELECT id FROM t WHERE code = 'c2';").unwrap(),
+            ["501"]
+        );
+        assert_eq!(
+            db.column("SELECT code FROM t WHERE id = 1;").unwrap(),
+            ["c1"]
+        );
+        assert!(
+            db.column("SELECT id FROM t WHERE code = 'c3';")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            db.run("INSERT INTO t (id, code) VALUES (2, 'c1');")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_drop_table_then_create_it_again() {
+        let mut db = TestDb::new("drop-table");
+        db.run("CREATE TABLE t (id INTEGER AUTOINCREMENT PRIMARY KEY, v TEXT UNIQUE); INSERT INTO t (v) VALUES ('a'), ('b');")
+            .unwrap();
+        db.run("DROP TABLE t;").unwrap();
+        assert!(db.query("SELECT * FROM t;").is_err());
+        assert!(db.run("DROP TABLE t;").is_err());
+        assert!(matches!(
+            db.execute("DROP TABLE IF EXISTS t;"),
+            Ok(QueryResult::Skipped(_))
+        ));
+
+        // Nothing of the old table survives: rows, indexes or counter.
+        db.run("CREATE TABLE t (id INTEGER AUTOINCREMENT PRIMARY KEY, v TEXT UNIQUE); INSERT INTO t (v) VALUES ('b');")
+            .unwrap();
+        assert_eq!(
+            db.query("SELECT id, v FROM t;").unwrap(),
+            vec![vec!["1", "b"]]
+        );
+    }
+
+    #[test]
+    fn test_drop_database() {
+        let mut db = TestDb::new("drop-db");
+        db.run("CREATE DATABASE other; CREATE TABLE t (id INTEGER PRIMARY KEY); INSERT INTO t VALUES (1); USE db;")
+            .unwrap();
+        db.run("DROP DATABASE other;").unwrap();
+        assert_eq!(db.cat.current_db.as_deref(), Some("db"));
+        assert!(db.run("USE other;").is_err());
+        assert!(matches!(
+            db.execute("DROP DATABASE IF EXISTS other;"),
+            Ok(QueryResult::Skipped(_))
+        ));
+
+        db.run("CREATE DATABASE other;").unwrap();
+        assert!(db.query("SELECT * FROM t;").is_err());
+        db.run("CREATE TABLE t (id INTEGER PRIMARY KEY); USE db;")
+            .unwrap();
+
+        // Dropping the database in use leaves none selected.
+        db.run("DROP DATABASE db;").unwrap();
+        assert_eq!(db.cat.current_db, None);
+        assert!(db.run("USE db;").is_err());
+        db.run("USE other;").unwrap();
+        assert!(db.query("SELECT * FROM t;").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_primary_key_and_unique_refuse_duplicates() {
+        let mut db = TestDb::new("unique");
+        db.run("CREATE TABLE t (id INTEGER PRIMARY KEY, code TEXT UNIQUE NULL); INSERT INTO t VALUES (1, 'a'), (2, 'b');")
+            .unwrap();
+
+        assert_eq!(
+            db.run("INSERT INTO t VALUES (1, 'c');"),
+            Err("PRIMARY KEY constraint violated: duplicate value 1 in t.id".to_string())
+        );
+        assert_eq!(
+            db.run("INSERT INTO t VALUES (3, 'a');"),
+            Err("UNIQUE constraint violated: duplicate value 'a' in t.code".to_string())
+        );
+        // A duplicate within one statement refuses the whole statement.
+        assert!(db.run("INSERT INTO t VALUES (3, 'c'), (3, 'd');").is_err());
+        assert!(db.run("INSERT INTO t VALUES (4, 'e'), (5, 'e');").is_err());
+
+        assert_eq!(
+            db.run("UPDATE t SET id = 1 WHERE id = 2;"),
+            Err("PRIMARY KEY constraint violated: duplicate value 1 in t.id".to_string())
+        );
+        assert_eq!(
+            db.run("UPDATE t SET code = 'b' WHERE id = 1;"),
+            Err("UNIQUE constraint violated: duplicate value 'b' in t.code".to_string())
+        );
+        assert!(db.run("UPDATE t SET code = 'z';").is_err());
+        // Writing a row's own value back is no conflict.
+        db.run("UPDATE t SET code = 'a', id = 1 WHERE id = 1;")
+            .unwrap();
+        assert_eq!(
+            db.query("SELECT id, code FROM t ORDER BY id;").unwrap(),
+            vec![vec!["1", "a"], vec!["2", "b"]]
+        );
+    }
+
+    #[test]
+    fn test_unique_column_holds_any_number_of_nulls() {
+        let mut db = TestDb::new("unique-null");
+        db.run("CREATE TABLE t (id INTEGER PRIMARY KEY, code TEXT UNIQUE NULL);")
+            .unwrap();
+        db.run("INSERT INTO t VALUES (1, NULL), (2, NULL); INSERT INTO t VALUES (3, 'a');")
+            .unwrap();
+        db.run("UPDATE t SET code = NULL WHERE id = 3; INSERT INTO t VALUES (4, 'a');")
+            .unwrap();
+        assert_eq!(
+            db.column("SELECT COUNT(*) FROM t WHERE code IS NULL;")
+                .unwrap(),
+            ["3"]
+        );
+        assert!(db.run("INSERT INTO t VALUES (5, 'a');").is_err());
+        assert!(db.run("INSERT INTO t VALUES (NULL, 'b');").is_err());
+    }
+}
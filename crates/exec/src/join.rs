@@ -0,0 +1,262 @@
+use std::collections::{HashMap, VecDeque};
+
+use sql::ast::{ASTValue, Expr, JoinKind};
+use storage::{Record, btree::encode_value, record::EvalContext};
+
+use crate::operator::{Operator, drain};
+
+// Join operators.
+//
+// Both read the right input in full on open and then stream the left one:
+// each left row's matches are emitted in right-input order, so a join's
+// output order is deterministic. A LEFT join emits an unmatched left row
+// once, with every right column set to NULL.
+
+/// Compare every left row with every right row.
+pub(crate) struct NestedLoopJoin<'a> {
+    left: Box<dyn Operator + 'a>,
+    right: Box<dyn Operator + 'a>,
+    kind: JoinKind,
+    on: Expr,
+    right_keys: Vec<String>,
+    ctx: EvalContext,
+    inner: Vec<Record>,
+    /// Joined rows of the current left row not yet returned.
+    pending: VecDeque<Record>,
+}
+
+impl<'a> NestedLoopJoin<'a> {
+    pub fn new(
+        left: Box<dyn Operator + 'a>,
+        right: Box<dyn Operator + 'a>,
+        kind: JoinKind,
+        on: Expr,
+        right_keys: Vec<String>,
+        ctx: EvalContext,
+    ) -> Self {
+        Self {
+            left,
+            right,
+            kind,
+            on,
+            right_keys,
+            ctx,
+            inner: Vec::new(),
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl Operator for NestedLoopJoin<'_> {
+    fn open(&mut self) -> Result<(), String> {
+        self.right.open()?;
+        self.inner = drain(self.right.as_mut())?;
+        self.right.close();
+        self.pending.clear();
+        self.left.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Record>, String> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Ok(Some(row));
+            }
+            let Some(l) = self.left.next()? else {
+                return Ok(None);
+            };
+            self.pending = matches(
+                l,
+                &self.inner,
+                &self.on,
+                self.kind,
+                &self.right_keys,
+                &self.ctx,
+            )?;
+        }
+    }
+
+    fn close(&mut self) {
+        self.left.close();
+        self.inner.clear();
+        self.pending.clear();
+    }
+}
+
+/// Equi-join on `left_key = right_key`: hash the right input on its key,
+/// then probe it with each left row. The full ON condition is re-checked
+/// on every candidate pair. NULL keys never match.
+pub(crate) struct HashJoin<'a> {
+    left: Box<dyn Operator + 'a>,
+    right: Box<dyn Operator + 'a>,
+    kind: JoinKind,
+    on: Expr,
+    left_key: String,
+    right_key: String,
+    right_keys: Vec<String>,
+    ctx: EvalContext,
+    table: HashMap<Vec<u8>, Vec<Record>>,
+    pending: VecDeque<Record>,
+}
+
+impl<'a> HashJoin<'a> {
+    pub fn new(
+        left: Box<dyn Operator + 'a>,
+        right: Box<dyn Operator + 'a>,
+        kind: JoinKind,
+        on: Expr,
+        (left_key, right_key): (String, String),
+        right_keys: Vec<String>,
+        ctx: EvalContext,
+    ) -> Self {
+        Self {
+            left,
+            right,
+            kind,
+            on,
+            left_key,
+            right_key,
+            right_keys,
+            ctx,
+            table: HashMap::new(),
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl Operator for HashJoin<'_> {
+    fn open(&mut self) -> Result<(), String> {
+        self.table.clear();
+        self.right.open()?;
+        while let Some(r) = self.right.next()? {
+            if let Some(key) = r.get_value(&self.right_key).and_then(encode_value) {
+                self.table.entry(key).or_default().push(r);
+            }
+        }
+        self.right.close();
+        self.pending.clear();
+        self.left.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Record>, String> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Ok(Some(row));
+            }
+            let Some(l) = self.left.next()? else {
+                return Ok(None);
+            };
+            let candidates = l
+                .get_value(&self.left_key)
+                .and_then(encode_value)
+                .and_then(|key| self.table.get(&key))
+                .map_or(&[][..], |rows| rows.as_slice());
+            self.pending = matches(
+                l,
+                candidates,
+                &self.on,
+                self.kind,
+                &self.right_keys,
+                &self.ctx,
+            )?;
+        }
+    }
+
+    fn close(&mut self) {
+        self.left.close();
+        self.table.clear();
+        self.pending.clear();
+    }
+}
+
+// Joined rows of `l` with every candidate that satisfies `on`.
+fn matches(
+    l: Record,
+    candidates: &[Record],
+    on: &Expr,
+    kind: JoinKind,
+    right_keys: &[String],
+    ctx: &EvalContext,
+) -> Result<VecDeque<Record>, String> {
+    let mut out = VecDeque::new();
+    for r in candidates {
+        let row = combine(&l, r);
+        if row.evaluate_condition(on, ctx)? {
+            out.push_back(row);
+        }
+    }
+    if kind == JoinKind::Left && out.is_empty() {
+        out.push_back(pad_nulls(l, right_keys));
+    }
+    Ok(out)
+}
+
+fn combine(l: &Record, r: &Record) -> Record {
+    let mut row = l.clone();
+    row.data
+        .extend(r.data.iter().map(|(k, v)| (k.clone(), v.clone())));
+    row
+}
+
+fn pad_nulls(mut l: Record, right_keys: &[String]) -> Record {
+    for key in right_keys {
+        l.set_value(key, ASTValue::Null);
+    }
+    l
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::TestDb;
+
+    fn db() -> TestDb {
+        let mut db = TestDb::new("join");
+        db.run(
+            "CREATE TABLE c (id INTEGER PRIMARY KEY, name TEXT);
+             CREATE TABLE o (id INTEGER PRIMARY KEY, cid INTEGER, total INTEGER);
+             INSERT INTO c VALUES (1, 'ann'), (2, 'bob'), (3, 'cy');
+             INSERT INTO o VALUES (10, 1, 5), (11, 1, 7), (12, 3, 9), (13, 4, 1);",
+        )
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_inner_join_keeps_matches_only() {
+        let mut db = db();
+        assert_eq!(
+            db.query("SELECT c.name, o.total FROM c JOIN o ON o.cid = c.id ORDER BY o.id;")
+                .unwrap(),
+            [["ann", "5"], ["ann", "7"], ["cy", "9"]]
+        );
+    }
+
+    #[test]
+    fn test_left_join_pads_unmatched_rows_with_nulls() {
+        let mut db = db();
+        assert_eq!(
+            db.query(
+                "SELECT c.name, o.id, o.total FROM c AS c LEFT JOIN o ON o.cid = c.id \
+                 ORDER BY c.id, o.id;"
+            )
+            .unwrap(),
+            [
+                ["ann", "10", "5"],
+                ["ann", "11", "7"],
+                ["bob", "NULL", "NULL"],
+                ["cy", "12", "9"],
+            ]
+        );
+        // The padding is NULL, so IS NULL finds the rows without a match.
+        assert_eq!(
+            db.column("SELECT c.name FROM c LEFT JOIN o ON o.cid = c.id WHERE o.id IS NULL;")
+                .unwrap(),
+            ["bob"]
+        );
+        // An ON term that never holds still keeps every left row.
+        assert_eq!(
+            db.column("SELECT o.total FROM c LEFT JOIN o ON o.cid = c.id AND o.total > 100;")
+                .unwrap(),
+            ["NULL", "NULL", "NULL"]
+        );
+    }
+}
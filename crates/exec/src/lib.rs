@@ -1,10 +1,19 @@
+mod aggregate;
 pub mod executor;
+mod join;
+mod operator;
+mod physical;
+mod plan;
 pub mod result;
+mod scope;
+#[cfg(test)]
+mod testing;
 
 use crate::result::ExecutionResult;
 use catalog::Catalog;
 use sql::ast::ASTNode;
 
 pub trait Executor {
-    fn execute(&mut self, cat: &mut dyn Catalog, ast: ASTNode) -> ExecutionResult;
+    /// A SELECT result reads its rows lazily and borrows `cat` until dropped.
+    fn execute<'a>(&mut self, cat: &'a mut dyn Catalog, ast: ASTNode) -> ExecutionResult<'a>;
 }
@@ -0,0 +1,529 @@
+use std::{cmp::Ordering, collections::VecDeque, ops::Bound};
+
+use catalog::{Catalog, PageScan};
+use sql::ast::{ASTValue, Expr, OrderByKey};
+use storage::{
+    Record,
+    page::{SLOT_TOMBSTONE, iter_slots},
+    record::EvalContext,
+    types::RowId,
+};
+
+// Pull-based (Volcano) operators.
+//
+// A physical plan runs as a tree of operators. `open` prepares an operator
+// and its inputs, each `next` returns one row, or None once the operator is
+// exhausted, and `close` releases what the operator holds. Rows flow up one
+// at a time, so only operators that must see their whole input (Sort,
+// HashAggregate, the build side of a join) keep more than a page of rows,
+// and a Limit that has its rows stops pulling from the scan below it.
+//
+// Operators only read the catalog, so they share one `&dyn Catalog`.
+pub(crate) trait Operator {
+    fn open(&mut self) -> Result<(), String>;
+    fn next(&mut self) -> Result<Option<Record>, String>;
+    fn close(&mut self);
+}
+
+/// Pull every remaining row of an opened operator.
+pub(crate) fn drain(op: &mut dyn Operator) -> Result<Vec<Record>, String> {
+    let mut rows = Vec::new();
+    while let Some(rec) = op.next()? {
+        rows.push(rec);
+    }
+    Ok(rows)
+}
+
+/// Every live row of a table, in heap order, read one page at a time.
+pub(crate) struct SeqScan<'a> {
+    cat: &'a dyn Catalog,
+    table: String,
+    qualifier: Option<String>,
+    pages: Option<PageScan<'a>>,
+    /// Rows of the current page not yet returned.
+    page_rows: VecDeque<Record>,
+}
+
+impl<'a> SeqScan<'a> {
+    pub fn new(cat: &'a dyn Catalog, table: String, qualifier: Option<String>) -> Self {
+        Self {
+            cat,
+            table,
+            qualifier,
+            pages: None,
+            page_rows: VecDeque::new(),
+        }
+    }
+
+    // Decode the live rows of one page. A slot holds the latest version of
+    // its row only if the RowIndex points at it; older versions are skipped.
+    fn load_page(&mut self, seg: u32, pid: u32, page: &[u8]) -> Result<(), String> {
+        let slots = iter_slots(page).map_err(|e| e.clone())?;
+        for (sid, (off, len, flags)) in slots.enumerate() {
+            if flags & SLOT_TOMBSTONE != 0 {
+                continue;
+            }
+            let start = off as usize;
+            let end = start + len as usize;
+            if end > page.len() {
+                continue;
+            }
+            let (row_id, rec) = self
+                .cat
+                .decode_row(&self.table, flags, &page[start..end])
+                .map_err(|e| e.to_string())?;
+            let latest = self
+                .cat
+                .get_tuple_loc(&self.table, row_id)
+                .map_err(|e| e.to_string())?
+                .is_some_and(|loc| (loc.seg, loc.page_id, loc.slot_id) == (seg, pid, sid as u16));
+            if !latest {
+                continue;
+            }
+            self.page_rows.push_back(match &self.qualifier {
+                Some(q) => qualify(rec, q),
+                None => rec,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Operator for SeqScan<'_> {
+    fn open(&mut self) -> Result<(), String> {
+        self.pages = Some(
+            self.cat
+                .scan_pages(&self.table)
+                .map_err(|e| e.to_string())?,
+        );
+        self.page_rows.clear();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Record>, String> {
+        loop {
+            if let Some(rec) = self.page_rows.pop_front() {
+                return Ok(Some(rec));
+            }
+            let Some(item) = self.pages.as_mut().and_then(|pages| pages.next()) else {
+                return Ok(None);
+            };
+            let (seg, pid, page) = item.map_err(|e| e.to_string())?;
+            self.load_page(seg, pid, &page)?;
+        }
+    }
+
+    fn close(&mut self) {
+        self.pages = None;
+        self.page_rows.clear();
+    }
+}
+
+// Re-key a row by `qualifier.column` for a join.
+fn qualify(rec: Record, qualifier: &str) -> Record {
+    let mut out = Record::new(rec.id);
+    for (column, value) in rec.data {
+        out.set_value(&format!("{}.{}", qualifier, column), value);
+    }
+    out
+}
+
+/// Rows whose `column` lies within the bounds, in index order. The RowIds
+/// are looked up on open; each row is fetched when it is asked for.
+pub(crate) struct IndexScan<'a> {
+    cat: &'a dyn Catalog,
+    table: String,
+    qualifier: Option<String>,
+    column: String,
+    lo: Bound<ASTValue>,
+    hi: Bound<ASTValue>,
+    row_ids: std::vec::IntoIter<RowId>,
+}
+
+impl<'a> IndexScan<'a> {
+    pub fn new(
+        cat: &'a dyn Catalog,
+        table: String,
+        qualifier: Option<String>,
+        column: String,
+        lo: Bound<ASTValue>,
+        hi: Bound<ASTValue>,
+    ) -> Self {
+        Self {
+            cat,
+            table,
+            qualifier,
+            column,
+            lo,
+            hi,
+            row_ids: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl Operator for IndexScan<'_> {
+    fn open(&mut self) -> Result<(), String> {
+        self.row_ids = self
+            .cat
+            .index_lookup(
+                &self.table,
+                &self.column,
+                self.lo.as_ref(),
+                self.hi.as_ref(),
+            )
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Index on {}.{} is gone", self.table, self.column))?
+            .into_iter();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Record>, String> {
+        for row_id in self.row_ids.by_ref() {
+            let Some(loc) = self
+                .cat
+                .get_tuple_loc(&self.table, row_id)
+                .map_err(|e| e.to_string())?
+            else {
+                continue;
+            };
+            if let Some(rec) = self
+                .cat
+                .fetch_record(&self.table, loc)
+                .map_err(|e| e.to_string())?
+            {
+                return Ok(Some(match &self.qualifier {
+                    Some(q) => qualify(rec, q),
+                    None => rec,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self) {
+        self.row_ids = Vec::new().into_iter();
+    }
+}
+
+pub(crate) struct Filter<'a> {
+    input: Box<dyn Operator + 'a>,
+    predicate: Expr,
+    ctx: EvalContext,
+}
+
+impl<'a> Filter<'a> {
+    pub fn new(input: Box<dyn Operator + 'a>, predicate: Expr, ctx: EvalContext) -> Self {
+        Self {
+            input,
+            predicate,
+            ctx,
+        }
+    }
+}
+
+impl Operator for Filter<'_> {
+    fn open(&mut self) -> Result<(), String> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Record>, String> {
+        while let Some(rec) = self.input.next()? {
+            if rec.evaluate_condition(&self.predicate, &self.ctx)? {
+                return Ok(Some(rec));
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self) {
+        self.input.close();
+    }
+}
+
+/// Reads its whole input on open, then returns it sorted. The sort is
+/// stable, so ties keep their input order.
+pub(crate) struct Sort<'a> {
+    input: Box<dyn Operator + 'a>,
+    keys: Vec<OrderByKey>,
+    ctx: EvalContext,
+    rows: std::vec::IntoIter<Record>,
+}
+
+impl<'a> Sort<'a> {
+    pub fn new(input: Box<dyn Operator + 'a>, keys: Vec<OrderByKey>, ctx: EvalContext) -> Self {
+        Self {
+            input,
+            keys,
+            ctx,
+            rows: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl Operator for Sort<'_> {
+    fn open(&mut self) -> Result<(), String> {
+        self.input.open()?;
+        let rows = drain(self.input.as_mut())?;
+        self.input.close();
+        // Evaluate each row's keys once, not on every comparison.
+        let mut keyed = Vec::with_capacity(rows.len());
+        for rec in rows {
+            let values = self
+                .keys
+                .iter()
+                .map(|key| rec.evaluate(&key.expr, &self.ctx))
+                .collect::<Result<Vec<_>, _>>()?;
+            keyed.push((values, rec));
+        }
+        keyed.sort_by(|(a, _), (b, _)| {
+            self.keys
+                .iter()
+                .zip(a.iter().zip(b))
+                .map(|(key, (va, vb))| compare_sort_key(va, vb, key))
+                .find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        self.rows = keyed
+            .into_iter()
+            .map(|(_, rec)| rec)
+            .collect::<Vec<_>>()
+            .into_iter();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Record>, String> {
+        Ok(self.rows.next())
+    }
+
+    fn close(&mut self) {
+        self.rows = Vec::new().into_iter();
+    }
+}
+
+// Compare two rows' values of one ORDER BY key. NULLs sort first ascending
+// and last descending unless NULLS FIRST / NULLS LAST says otherwise; values
+// of different types compare equal.
+fn compare_sort_key(va: &ASTValue, vb: &ASTValue, key: &OrderByKey) -> Ordering {
+    let nulls_first = key.nulls_first.unwrap_or(!key.descending);
+    match (va, vb) {
+        (ASTValue::Null, ASTValue::Null) => Ordering::Equal,
+        (ASTValue::Null, _) if nulls_first => Ordering::Less,
+        (ASTValue::Null, _) => Ordering::Greater,
+        (_, ASTValue::Null) if nulls_first => Ordering::Greater,
+        (_, ASTValue::Null) => Ordering::Less,
+        _ => {
+            let o = va.partial_cmp(vb).unwrap_or(Ordering::Equal);
+            if key.descending { o.reverse() } else { o }
+        }
+    }
+}
+
+/// Skips `offset` rows, then returns at most `limit`. Once it has returned
+/// `limit` rows it stops pulling from its input.
+pub(crate) struct Limit<'a> {
+    input: Box<dyn Operator + 'a>,
+    limit: Option<u64>,
+    offset: u64,
+    skipped: u64,
+    returned: u64,
+}
+
+impl<'a> Limit<'a> {
+    pub fn new(input: Box<dyn Operator + 'a>, limit: Option<u64>, offset: u64) -> Self {
+        Self {
+            input,
+            limit,
+            offset,
+            skipped: 0,
+            returned: 0,
+        }
+    }
+}
+
+impl Operator for Limit<'_> {
+    fn open(&mut self) -> Result<(), String> {
+        self.skipped = 0;
+        self.returned = 0;
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Record>, String> {
+        if self.limit.is_some_and(|l| self.returned >= l) {
+            return Ok(None);
+        }
+        while self.skipped < self.offset {
+            if self.input.next()?.is_none() {
+                return Ok(None);
+            }
+            self.skipped += 1;
+        }
+        let rec = self.input.next()?;
+        if rec.is_some() {
+            self.returned += 1;
+        }
+        Ok(rec)
+    }
+
+    fn close(&mut self) {
+        self.input.close();
+    }
+}
+
+/// Computes the result columns, each from its expression over the input row.
+pub(crate) struct Project<'a> {
+    input: Box<dyn Operator + 'a>,
+    columns: Vec<(String, Expr)>,
+    ctx: EvalContext,
+}
+
+impl<'a> Project<'a> {
+    pub fn new(
+        input: Box<dyn Operator + 'a>,
+        columns: Vec<(String, Expr)>,
+        ctx: EvalContext,
+    ) -> Self {
+        Self {
+            input,
+            columns,
+            ctx,
+        }
+    }
+}
+
+impl Operator for Project<'_> {
+    fn open(&mut self) -> Result<(), String> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Record>, String> {
+        let Some(rec) = self.input.next()? else {
+            return Ok(None);
+        };
+        // Keyed by position rather than name: two result columns may share
+        // a name, as in `SELECT id AS v, v FROM t`.
+        let mut out = Record::new(rec.id);
+        for (i, (_, expr)) in self.columns.iter().enumerate() {
+            out.set_value(&i.to_string(), rec.evaluate(expr, &self.ctx)?);
+        }
+        Ok(Some(out))
+    }
+
+    fn close(&mut self) {
+        self.input.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use crate::{result::ResultSet, testing::TestDb};
+
+    // Yields `n` rows, counting how many were pulled and whether it is open.
+    struct Counter {
+        n: u64,
+        pulled: Rc<Cell<u64>>,
+        open: Rc<Cell<bool>>,
+    }
+
+    impl Operator for Counter {
+        fn open(&mut self) -> Result<(), String> {
+            self.open.set(true);
+            Ok(())
+        }
+
+        fn next(&mut self) -> Result<Option<Record>, String> {
+            if self.pulled.get() == self.n {
+                return Ok(None);
+            }
+            self.pulled.set(self.pulled.get() + 1);
+            Ok(Some(Record::new(self.pulled.get())))
+        }
+
+        fn close(&mut self) {
+            self.open.set(false);
+        }
+    }
+
+    #[test]
+    fn test_rows_are_pulled_only_when_needed() {
+        let pulled = Rc::new(Cell::new(0));
+        let open = Rc::new(Cell::new(false));
+        let counter = Counter {
+            n: 1_000_000,
+            pulled: pulled.clone(),
+            open: open.clone(),
+        };
+        let limit = Limit::new(Box::new(counter), Some(3), 2);
+        let mut rows = ResultSet::new(vec![], Box::new(limit)).unwrap();
+        assert!(open.get());
+        assert_eq!(pulled.get(), 0);
+
+        assert!(rows.next().unwrap().is_ok());
+        assert_eq!(pulled.get(), 3);
+        assert_eq!(rows.by_ref().filter(Result::is_ok).count(), 2);
+        // Exhausting the result closes the tree without reading further.
+        assert_eq!(pulled.get(), 5);
+        assert!(!open.get());
+    }
+
+    #[test]
+    fn test_dropped_result_closes_its_operators() {
+        let open = Rc::new(Cell::new(false));
+        let counter = Counter {
+            n: 10,
+            pulled: Rc::new(Cell::new(0)),
+            open: open.clone(),
+        };
+        let mut rows = ResultSet::new(vec![], Box::new(counter)).unwrap();
+        rows.next();
+        assert!(open.get());
+        drop(rows);
+        assert!(!open.get());
+    }
+
+    fn db_with_nulls() -> TestDb {
+        let mut db = TestDb::new("order-by");
+        db.run(
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, n INTEGER NULL);
+             INSERT INTO t VALUES (1, 20), (2, NULL), (3, 10), (4, NULL), (5, 30);",
+        )
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_order_by_places_nulls() {
+        let mut db = db_with_nulls();
+        let mut ids = |order: &str| db.column(&format!("SELECT id FROM t ORDER BY {};", order));
+        // NULLs are the smallest values unless told otherwise.
+        assert_eq!(ids("n, id").unwrap(), ["2", "4", "3", "1", "5"]);
+        assert_eq!(ids("n DESC, id").unwrap(), ["5", "1", "3", "2", "4"]);
+        assert_eq!(ids("n NULLS LAST, id").unwrap(), ["3", "1", "5", "2", "4"]);
+        assert_eq!(
+            ids("n DESC NULLS FIRST, id DESC").unwrap(),
+            ["4", "2", "5", "1", "3"]
+        );
+    }
+
+    #[test]
+    fn test_limit_and_offset() {
+        let mut db = db_with_nulls();
+        assert_eq!(
+            db.column("SELECT id FROM t ORDER BY id LIMIT 2 OFFSET 1;")
+                .unwrap(),
+            ["2", "3"]
+        );
+        assert_eq!(
+            db.column("SELECT id FROM t ORDER BY id LIMIT 10 OFFSET 4;")
+                .unwrap(),
+            ["5"]
+        );
+        assert!(
+            db.column("SELECT id FROM t ORDER BY id LIMIT 0;")
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
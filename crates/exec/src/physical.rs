@@ -0,0 +1,642 @@
+use std::{cmp::Ordering, ops::Bound};
+
+use catalog::Catalog;
+use sql::ast::{ASTValue, AggregateCall, Expr, JoinKind, OrderByKey};
+use storage::{
+    Record,
+    record::{EvalContext, coerce_exact},
+    types::Column,
+};
+use types::tokens::Operator;
+
+use crate::{
+    aggregate::HashAggregate,
+    join::{HashJoin, NestedLoopJoin},
+    operator::{self, Filter, IndexScan, Limit, Project, SeqScan, Sort, drain},
+    plan::{LogicalPlan, collect_conjuncts},
+};
+
+// Physical plans.
+//
+// Each logical operator maps to one physical operator, except that
+//
+// - a Scan under a Filter, directly or through joins, becomes an IndexScan
+//   when the Filter's AND-ed terms compare an indexed column of it with
+//   literals of the column's type. All the terms on that column narrow the
+//   range. The Filter still re-checks every row, and a NULL-padded join row
+//   never passes such a term, so reading fewer rows changes nothing;
+// - a Join becomes a HashJoin when the logical join found equality keys,
+//   else a NestedLoopJoin.
+//
+// A plan runs as a tree of pull-based operators (see operator.rs).
+pub(crate) enum PhysicalPlan {
+    /// Every live row of the table, in heap order.
+    SeqScan {
+        table: String,
+        qualifier: Option<String>,
+    },
+    /// Rows whose `column` lies within the bounds, in index order.
+    IndexScan {
+        table: String,
+        qualifier: Option<String>,
+        index: String,
+        column: String,
+        lo: Bound<ASTValue>,
+        hi: Bound<ASTValue>,
+    },
+    Filter {
+        input: Box<PhysicalPlan>,
+        predicate: Expr,
+    },
+    NestedLoopJoin {
+        left: Box<PhysicalPlan>,
+        right: Box<PhysicalPlan>,
+        kind: JoinKind,
+        on: Expr,
+        right_keys: Vec<String>,
+    },
+    HashJoin {
+        left: Box<PhysicalPlan>,
+        right: Box<PhysicalPlan>,
+        kind: JoinKind,
+        on: Expr,
+        keys: (String, String),
+        right_keys: Vec<String>,
+    },
+    HashAggregate {
+        input: Box<PhysicalPlan>,
+        group_by: Vec<String>,
+        calls: Vec<(String, AggregateCall)>,
+    },
+    Sort {
+        input: Box<PhysicalPlan>,
+        keys: Vec<OrderByKey>,
+    },
+    Limit {
+        input: Box<PhysicalPlan>,
+        limit: Option<u64>,
+        offset: u64,
+    },
+    Project {
+        input: Box<PhysicalPlan>,
+        columns: Vec<(String, Expr)>,
+    },
+}
+
+impl PhysicalPlan {
+    pub fn from_logical(cat: &dyn Catalog, plan: LogicalPlan) -> Self {
+        PhysicalPlan::lower(cat, plan, None)
+    }
+
+    // `filter` is the predicate of a Filter above `plan` that every row of
+    // `plan` reaches, if nothing but joins lies between them.
+    fn lower(cat: &dyn Catalog, plan: LogicalPlan, filter: Option<&Expr>) -> Self {
+        let lower = |p: Box<LogicalPlan>| Box::new(PhysicalPlan::lower(cat, *p, None));
+        let lower_joined = |p: Box<LogicalPlan>| Box::new(PhysicalPlan::lower(cat, *p, filter));
+        match plan {
+            LogicalPlan::Scan {
+                table,
+                columns,
+                qualifier,
+            } => filter
+                .and_then(|predicate| {
+                    index_scan(cat, &table, &columns, qualifier.as_deref(), predicate)
+                })
+                .unwrap_or(PhysicalPlan::SeqScan { table, qualifier }),
+            LogicalPlan::Filter { input, predicate } => PhysicalPlan::Filter {
+                input: Box::new(PhysicalPlan::lower(cat, *input, Some(&predicate))),
+                predicate,
+            },
+            LogicalPlan::Join {
+                left,
+                right,
+                kind,
+                on,
+                equi_keys,
+                right_keys,
+            } => match equi_keys {
+                Some(keys) => PhysicalPlan::HashJoin {
+                    left: lower_joined(left),
+                    right: lower_joined(right),
+                    kind,
+                    on,
+                    keys,
+                    right_keys,
+                },
+                None => PhysicalPlan::NestedLoopJoin {
+                    left: lower_joined(left),
+                    right: lower_joined(right),
+                    kind,
+                    on,
+                    right_keys,
+                },
+            },
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                calls,
+            } => PhysicalPlan::HashAggregate {
+                input: lower(input),
+                group_by,
+                calls,
+            },
+            LogicalPlan::Sort { input, keys } => PhysicalPlan::Sort {
+                input: lower(input),
+                keys,
+            },
+            LogicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => PhysicalPlan::Limit {
+                input: lower(input),
+                limit,
+                offset,
+            },
+            LogicalPlan::Project { input, columns } => PhysicalPlan::Project {
+                input: lower(input),
+                columns,
+            },
+        }
+    }
+
+    /// Result column names, for plans topped by a Project.
+    pub fn output_columns(&self) -> Vec<String> {
+        match self {
+            PhysicalPlan::Project { columns, .. } => {
+                columns.iter().map(|(name, _)| name.clone()).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The operator tree running this plan over `cat`, not yet opened.
+    pub fn into_operator<'a>(
+        self,
+        cat: &'a dyn Catalog,
+        ctx: EvalContext,
+    ) -> Box<dyn operator::Operator + 'a> {
+        let open = |p: Box<PhysicalPlan>| p.into_operator(cat, ctx);
+        match self {
+            PhysicalPlan::SeqScan {
+                table, qualifier, ..
+            } => Box::new(SeqScan::new(cat, table, qualifier)),
+            PhysicalPlan::IndexScan {
+                table,
+                qualifier,
+                column,
+                lo,
+                hi,
+                ..
+            } => Box::new(IndexScan::new(cat, table, qualifier, column, lo, hi)),
+            PhysicalPlan::Filter { input, predicate } => {
+                Box::new(Filter::new(open(input), predicate, ctx))
+            }
+            PhysicalPlan::NestedLoopJoin {
+                left,
+                right,
+                kind,
+                on,
+                right_keys,
+            } => Box::new(NestedLoopJoin::new(
+                open(left),
+                open(right),
+                kind,
+                on,
+                right_keys,
+                ctx,
+            )),
+            PhysicalPlan::HashJoin {
+                left,
+                right,
+                kind,
+                on,
+                keys,
+                right_keys,
+            } => Box::new(HashJoin::new(
+                open(left),
+                open(right),
+                kind,
+                on,
+                keys,
+                right_keys,
+                ctx,
+            )),
+            PhysicalPlan::HashAggregate {
+                input,
+                group_by,
+                calls,
+            } => Box::new(HashAggregate::new(open(input), group_by, calls)),
+            PhysicalPlan::Sort { input, keys } => Box::new(Sort::new(open(input), keys, ctx)),
+            PhysicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => Box::new(Limit::new(open(input), limit, offset)),
+            PhysicalPlan::Project { input, columns } => {
+                Box::new(Project::new(open(input), columns, ctx))
+            }
+        }
+    }
+
+    /// Run the plan to completion and collect its rows.
+    pub fn execute(self, cat: &dyn Catalog, ctx: EvalContext) -> Result<Vec<Record>, String> {
+        let mut op = self.into_operator(cat, ctx);
+        op.open()?;
+        let rows = drain(op.as_mut());
+        op.close();
+        rows
+    }
+
+    /// One line per operator, children indented under their parent.
+    pub fn explain(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        self.explain_into(0, &mut lines);
+        lines
+    }
+
+    fn explain_into(&self, depth: usize, lines: &mut Vec<String>) {
+        let line = match self {
+            PhysicalPlan::SeqScan {
+                table, qualifier, ..
+            } => match qualifier {
+                Some(q) if q != table => format!("SeqScan {} AS {}", table, q),
+                _ => format!("SeqScan {}", table),
+            },
+            PhysicalPlan::IndexScan {
+                table,
+                qualifier,
+                index,
+                column,
+                lo,
+                hi,
+            } => format!(
+                "IndexScan {}{} using {} ({})",
+                table,
+                match qualifier {
+                    Some(q) if q != table => format!(" AS {}", q),
+                    _ => String::new(),
+                },
+                index,
+                describe_range(column, lo, hi)
+            ),
+            PhysicalPlan::Filter { predicate, .. } => format!("Filter {}", predicate),
+            PhysicalPlan::NestedLoopJoin { kind, on, .. } => {
+                format!("NestedLoopJoin {} on {}", describe_kind(*kind), on)
+            }
+            PhysicalPlan::HashJoin { kind, on, keys, .. } => format!(
+                "HashJoin {} on {} (hash {} = {})",
+                describe_kind(*kind),
+                on,
+                keys.0,
+                keys.1
+            ),
+            PhysicalPlan::HashAggregate {
+                group_by, calls, ..
+            } => format!(
+                "HashAggregate group by [{}] compute [{}]",
+                group_by.join(", "),
+                calls
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            PhysicalPlan::Sort { keys, .. } => format!(
+                "Sort [{}]",
+                keys.iter()
+                    .map(|k| {
+                        let mut s = k.expr.to_string();
+                        s.push_str(if k.descending { " DESC" } else { " ASC" });
+                        if let Some(first) = k.nulls_first {
+                            s.push_str(if first { " NULLS FIRST" } else { " NULLS LAST" });
+                        }
+                        s
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            PhysicalPlan::Limit { limit, offset, .. } => match limit {
+                Some(l) => format!("Limit {} offset {}", l, offset),
+                None => format!("Limit all offset {}", offset),
+            },
+            PhysicalPlan::Project { columns, .. } => format!(
+                "Project [{}]",
+                columns
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        lines.push(format!("{}{}", "  ".repeat(depth), line));
+        for child in self.children() {
+            child.explain_into(depth + 1, lines);
+        }
+    }
+
+    fn children(&self) -> Vec<&PhysicalPlan> {
+        match self {
+            PhysicalPlan::SeqScan { .. } | PhysicalPlan::IndexScan { .. } => Vec::new(),
+            PhysicalPlan::NestedLoopJoin { left, right, .. }
+            | PhysicalPlan::HashJoin { left, right, .. } => vec![left, right],
+            PhysicalPlan::Filter { input, .. }
+            | PhysicalPlan::HashAggregate { input, .. }
+            | PhysicalPlan::Sort { input, .. }
+            | PhysicalPlan::Limit { input, .. }
+            | PhysicalPlan::Project { input, .. } => vec![input],
+        }
+    }
+}
+
+// An IndexScan answering part of `predicate`: the first AND-ed term that
+// compares an indexed column with a literal the column's type represents
+// exactly, converted to that type.
+fn index_scan(
+    cat: &dyn Catalog,
+    table_name: &str,
+    columns: &[Column],
+    qualifier: Option<&str>,
+    predicate: &Expr,
+) -> Option<PhysicalPlan> {
+    let mut terms = Vec::new();
+    collect_conjuncts(predicate, &mut terms);
+
+    // The range each column is held to by all its terms together, columns
+    // in the order they first appear.
+    let mut ranges: Vec<(&Column, Bound<ASTValue>, Bound<ASTValue>)> = Vec::new();
+    for term in terms {
+        let Expr::Binary { op, left, right } = term else {
+            continue;
+        };
+        let (column, value, operator) = match (left.as_ref(), right.as_ref()) {
+            (Expr::Column(c), Expr::Value(v)) => (c, v, op.clone()),
+            (Expr::Value(v), Expr::Column(c)) => match flip(op) {
+                Some(op) => (c, v, op),
+                None => continue,
+            },
+            _ => continue,
+        };
+        // Inside a join, rows of this scan are keyed `qualifier.column`.
+        let name = match qualifier {
+            Some(q) => match column
+                .column
+                .strip_prefix(q)
+                .and_then(|n| n.strip_prefix('.'))
+            {
+                Some(name) => name,
+                None => continue,
+            },
+            None => column.column.as_str(),
+        };
+        let Some(col) = columns.iter().find(|col| col.name == name) else {
+            continue;
+        };
+        let Some(value) = coerce_exact(value, &col.data_type) else {
+            continue;
+        };
+        let (lo, hi) = match operator {
+            Operator::EQUALS => (Bound::Included(value.clone()), Bound::Included(value)),
+            Operator::GT => (Bound::Excluded(value), Bound::Unbounded),
+            Operator::GTorE => (Bound::Included(value), Bound::Unbounded),
+            Operator::LT => (Bound::Unbounded, Bound::Excluded(value)),
+            Operator::LTorE => (Bound::Unbounded, Bound::Included(value)),
+            _ => continue,
+        };
+        match ranges.iter_mut().find(|(c, ..)| c.name == col.name) {
+            Some((_, range_lo, range_hi)) => {
+                *range_lo = narrower(range_lo.clone(), lo, Ordering::Greater);
+                *range_hi = narrower(range_hi.clone(), hi, Ordering::Less);
+            }
+            None => ranges.push((col, lo, hi)),
+        }
+    }
+
+    ranges.into_iter().find_map(|(col, lo, hi)| {
+        Some(PhysicalPlan::IndexScan {
+            table: table_name.to_string(),
+            qualifier: qualifier.map(str::to_string),
+            index: cat.index_name(table_name, &col.name)?,
+            column: col.name.clone(),
+            lo,
+            hi,
+        })
+    })
+}
+
+// The narrower of two bounds on the same end of a range; `inward` is how a
+// value compares with another it lies inside of (Greater for lower bounds).
+fn narrower(a: Bound<ASTValue>, b: Bound<ASTValue>, inward: Ordering) -> Bound<ASTValue> {
+    let (x, y) = match (&a, &b) {
+        (_, Bound::Unbounded) => return a,
+        (Bound::Unbounded, _) => return b,
+        (Bound::Included(x) | Bound::Excluded(x), Bound::Included(y) | Bound::Excluded(y)) => {
+            (x, y)
+        }
+    };
+    match y.partial_cmp(x) {
+        Some(ord) if ord == inward => b,
+        Some(Ordering::Equal) if matches!(b, Bound::Excluded(_)) => b,
+        _ => a,
+    }
+}
+
+// `v op col` rewritten as `col op' v`.
+fn flip(op: &Operator) -> Option<Operator> {
+    match op {
+        Operator::EQUALS => Some(Operator::EQUALS),
+        Operator::LT => Some(Operator::GT),
+        Operator::GT => Some(Operator::LT),
+        Operator::LTorE => Some(Operator::GTorE),
+        Operator::GTorE => Some(Operator::LTorE),
+        _ => None,
+    }
+}
+
+fn describe_kind(kind: JoinKind) -> &'static str {
+    match kind {
+        JoinKind::Inner => "INNER",
+        JoinKind::Left => "LEFT",
+    }
+}
+
+fn describe_range(column: &str, lo: &Bound<ASTValue>, hi: &Bound<ASTValue>) -> String {
+    match (lo, hi) {
+        (Bound::Included(a), Bound::Included(b)) if a == b => {
+            format!("{} = {}", column, a)
+        }
+        _ => {
+            let mut parts = Vec::new();
+            match lo {
+                Bound::Included(v) => parts.push(format!("{} >= {}", column, v)),
+                Bound::Excluded(v) => parts.push(format!("{} > {}", column, v)),
+                Bound::Unbounded => {}
+            }
+            match hi {
+                Bound::Included(v) => parts.push(format!("{} <= {}", column, v)),
+                Bound::Excluded(v) => parts.push(format!("{} < {}", column, v)),
+                Bound::Unbounded => {}
+            }
+            parts.join(" AND ")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::TestDb;
+
+    fn db() -> TestDb {
+        let mut db = TestDb::new("physical");
+        db.run(
+            "CREATE TABLE c (id INTEGER PRIMARY KEY, code TEXT, name TEXT);
+             CREATE TABLE o (id INTEGER PRIMARY KEY, cid INTEGER, ccode INTEGER);
+             CREATE INDEX c_name ON c (name);
+             INSERT INTO c VALUES (1, '1', 'ann'), (2, '2', 'bob');
+             INSERT INTO o VALUES (10, 1, 1), (11, 2, 2), (12, 2, 2);",
+        )
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_indexed_equality_uses_index_scan() {
+        let mut db = db();
+        assert_eq!(
+            db.lines("EXPLAIN SELECT name FROM c WHERE id = 2;")
+                .unwrap(),
+            [
+                "Project [name]",
+                "  Filter id = 2",
+                "    IndexScan c using c_pkey (id = 2)",
+            ]
+        );
+        assert_eq!(
+            db.lines("EXPLAIN DELETE FROM c WHERE name = 'bob' AND id > 0;")
+                .unwrap(),
+            [
+                "Delete c",
+                "  Filter name = 'bob' AND id > 0",
+                "    IndexScan c using c_name (name = 'bob')",
+            ]
+        );
+        assert_eq!(
+            db.lines("EXPLAIN SELECT name FROM c WHERE code = '2';")
+                .unwrap()[2],
+            "    SeqScan c"
+        );
+        assert_eq!(
+            db.column("SELECT name FROM c WHERE id = 2;").unwrap(),
+            ["bob"]
+        );
+    }
+
+    #[test]
+    fn test_join_falls_back_to_nested_loop_when_key_types_differ() {
+        let mut db = db();
+        assert_eq!(
+            db.lines("EXPLAIN SELECT c.name FROM c JOIN o ON o.cid = c.id;")
+                .unwrap(),
+            [
+                "Project [c.name]",
+                "  HashJoin INNER on o.cid = c.id (hash c.id = o.cid)",
+                "    SeqScan c",
+                "    SeqScan o",
+            ]
+        );
+        assert_eq!(
+            db.lines("EXPLAIN SELECT c.name FROM c JOIN o ON o.ccode = c.code;")
+                .unwrap(),
+            [
+                "Project [c.name]",
+                "  NestedLoopJoin INNER on o.ccode = c.code",
+                "    SeqScan c",
+                "    SeqScan o",
+            ]
+        );
+        // Both plans give the rows ON selects.
+        assert_eq!(
+            db.column("SELECT o.id FROM c JOIN o ON o.cid = c.id WHERE c.name = 'bob';")
+                .unwrap(),
+            ["11", "12"]
+        );
+        assert!(
+            db.query("SELECT o.id FROM c JOIN o ON o.ccode = c.code;")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_index_scan_takes_every_bound_on_its_column() {
+        let mut db = db();
+        db.run("INSERT INTO c VALUES (3, '3', 'cy'), (4, '4', 'dee'), (5, '5', 'ed');")
+            .unwrap();
+        let scan = |db: &mut TestDb, select: &str| {
+            let lines = db.lines(&format!("EXPLAIN {}", select)).unwrap();
+            lines.last().unwrap().trim().to_string()
+        };
+
+        let select = "SELECT id FROM c WHERE id > 1 AND id < 4;";
+        assert_eq!(
+            scan(&mut db, select),
+            "IndexScan c using c_pkey (id > 1 AND id < 4)"
+        );
+        assert_eq!(db.column(select).unwrap(), ["2", "3"]);
+
+        let select = "SELECT id FROM c WHERE 4 >= id AND id >= 2 AND id > 2 AND id < 5;";
+        assert_eq!(
+            scan(&mut db, select),
+            "IndexScan c using c_pkey (id > 2 AND id <= 4)"
+        );
+        assert_eq!(db.column(select).unwrap(), ["3", "4"]);
+
+        let select = "SELECT id FROM c WHERE code >= '2' AND id <= 3 AND id = 3;";
+        assert_eq!(scan(&mut db, select), "IndexScan c using c_pkey (id = 3)");
+        assert_eq!(db.column(select).unwrap(), ["3"]);
+
+        let select = "SELECT id FROM c WHERE id > 3 AND id < 2;";
+        assert_eq!(
+            scan(&mut db, select),
+            "IndexScan c using c_pkey (id > 3 AND id < 2)"
+        );
+        assert!(db.column(select).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_scans_under_a_join_use_the_where_terms() {
+        let mut db = db();
+        assert_eq!(
+            db.lines("EXPLAIN SELECT o.id FROM c AS x JOIN o ON o.cid = x.id WHERE x.name = 'bob' AND o.id < 12;")
+                .unwrap(),
+            [
+                "Project [o.id]",
+                "  Filter x.name = 'bob' AND o.id < 12",
+                "    HashJoin INNER on o.cid = x.id (hash x.id = o.cid)",
+                "      IndexScan c AS x using c_name (name = 'bob')",
+                "      IndexScan o using o_pkey (id < 12)",
+            ]
+        );
+        assert_eq!(
+            db.column(
+                "SELECT o.id FROM c AS x JOIN o ON o.cid = x.id WHERE x.name = 'bob' AND o.id < 12;"
+            )
+            .unwrap(),
+            ["11"]
+        );
+
+        // Left rows whose matches the index leaves out come back NULL-padded,
+        // and the Filter drops them.
+        let select = "SELECT o.id, c.name FROM o LEFT JOIN c ON c.id = o.cid WHERE c.name = 'ann';";
+        assert_eq!(
+            db.lines(&format!("EXPLAIN {}", select)).unwrap()[4],
+            "      IndexScan c using c_name (name = 'ann')"
+        );
+        assert_eq!(db.query(select).unwrap(), vec![vec!["10", "ann"]]);
+        assert_eq!(
+            db.query("SELECT o.id, c.name FROM o LEFT JOIN c ON c.id = o.cid WHERE o.id = 12;")
+                .unwrap(),
+            vec![vec!["12", "bob"]]
+        );
+    }
+}
@@ -0,0 +1,376 @@
+use catalog::Catalog;
+use sql::ast::{
+    AggregateCall, AggregateFunc, Assignment, ColumnRef, Expr, JoinKind, OrderByKey, Select,
+    SelectItem,
+};
+use storage::types::Column;
+use types::tokens::Operator;
+
+use crate::scope::{Scope, Source};
+
+// Logical plans.
+//
+// The planner turns a statement into a tree of logical operators, resolving
+// every column to its row key (see scope.rs) on the way, so the tree says
+// what to compute without naming algorithms. physical.rs then picks how.
+//
+// A SELECT plans bottom-up as
+//
+//   Scan (Join Scan)* -> Filter(WHERE) -> Aggregate -> Filter(HAVING)
+//        -> Sort -> Limit -> Project
+//
+// with each stage present only when the query needs it.
+pub(crate) enum LogicalPlan {
+    /// Every live row of a table. Inside a join the rows are keyed by
+    /// `qualifier.column`.
+    Scan {
+        table: String,
+        columns: Vec<Column>,
+        qualifier: Option<String>,
+    },
+    Filter {
+        input: Box<LogicalPlan>,
+        predicate: Expr,
+    },
+    Join {
+        left: Box<LogicalPlan>,
+        right: Box<LogicalPlan>,
+        kind: JoinKind,
+        on: Expr,
+        /// Row keys (left, right) of an AND-ed equality in `on` between
+        /// columns of the same type, if there is one.
+        equi_keys: Option<(String, String)>,
+        /// Row keys of the right input, NULL-padded by LEFT joins.
+        right_keys: Vec<String>,
+    },
+    Aggregate {
+        input: Box<LogicalPlan>,
+        group_by: Vec<String>,
+        /// Each call paired with its result column name.
+        calls: Vec<(String, AggregateCall)>,
+    },
+    Sort {
+        input: Box<LogicalPlan>,
+        keys: Vec<OrderByKey>,
+    },
+    Limit {
+        input: Box<LogicalPlan>,
+        limit: Option<u64>,
+        offset: u64,
+    },
+    /// Result columns, each with the expression computing it.
+    Project {
+        input: Box<LogicalPlan>,
+        columns: Vec<(String, Expr)>,
+    },
+}
+
+/// Plan a SELECT.
+pub(crate) fn plan_select(cat: &mut dyn Catalog, select: Select) -> Result<LogicalPlan, String> {
+    let Select {
+        columns: items,
+        from,
+        joins,
+        where_clause,
+        group_by,
+        having,
+        order_by,
+        limit,
+        offset,
+    } = select;
+
+    let mut sources = Vec::with_capacity(joins.len() + 1);
+    for t in std::iter::once(&from).chain(joins.iter().map(|j| &j.table)) {
+        let table = cat
+            .get_table(&t.name)
+            .ok_or_else(|| format!("Table '{}' not found", t.name))?;
+        sources.push(Source {
+            qualifier: t.qualifier().to_string(),
+            table: t.name.clone(),
+            columns: table.columns.clone(),
+        });
+    }
+    let scope = Scope::new(sources)?;
+
+    let aggregated = !group_by.is_empty()
+        || having.is_some()
+        || items.iter().any(|i| match i {
+            SelectItem::Expr { expr, .. } => expr.has_aggregate(),
+            SelectItem::Wildcard => false,
+        })
+        || order_by.iter().any(|k| k.expr.has_aggregate());
+
+    let where_clause = where_clause
+        .as_ref()
+        .map(|expr| resolve_scalar(&scope, expr, scope.sources().len(), "WHERE"))
+        .transpose()?;
+    let group_keys = group_by
+        .iter()
+        .map(|c| scope.resolve(c).map(|r| r.key))
+        .collect::<Result<Vec<_>, _>>()?;
+    let grouped = aggregated.then_some(group_keys.as_slice());
+
+    let mut calls: Vec<(String, AggregateCall)> = Vec::new();
+    let mut projection: Vec<(String, Expr)> = Vec::new();
+    for item in &items {
+        match item {
+            SelectItem::Wildcard => {
+                if aggregated {
+                    return Err("SELECT * cannot be used with GROUP BY or aggregates".to_string());
+                }
+                for i in 0..scope.sources().len() {
+                    projection.extend(
+                        scope
+                            .keys(i)
+                            .into_iter()
+                            .map(|k| (k.clone(), Expr::Column(ColumnRef::new(k)))),
+                    );
+                }
+            }
+            SelectItem::Expr { expr, .. } => {
+                let resolved = resolve_output(&scope, expr, grouped, &mut calls, "Column")?;
+                projection.push((item.name(), resolved));
+            }
+        }
+    }
+    let having = having
+        .as_ref()
+        .map(|expr| resolve_output(&scope, expr, grouped, &mut calls, "HAVING column"))
+        .transpose()?;
+    // An ORDER BY key naming a SELECT alias sorts by that item.
+    let mut sort_keys = Vec::with_capacity(order_by.len());
+    for key in order_by {
+        let aliased = match &key.expr {
+            Expr::Column(ColumnRef {
+                table: None,
+                column,
+            }) => items
+                .iter()
+                .position(|i| matches!(i, SelectItem::Expr { alias: Some(a), .. } if a == column)),
+            _ => None,
+        };
+        let expr = match aliased {
+            Some(i) => {
+                let name = items[i].name();
+                projection
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, e)| e.clone())
+                    .expect("every SELECT item is projected")
+            }
+            None => resolve_output(&scope, &key.expr, grouped, &mut calls, "ORDER BY column")?,
+        };
+        sort_keys.push(OrderByKey { expr, ..key });
+    }
+
+    let mut plan = scan(&scope, 0);
+    for (i, join) in joins.iter().enumerate() {
+        let right = i + 1;
+        plan = LogicalPlan::Join {
+            left: Box::new(plan),
+            right: Box::new(scan(&scope, right)),
+            kind: join.kind,
+            on: resolve_scalar(&scope, &join.on, right + 1, "ON")?,
+            equi_keys: equi_join_keys(&scope, &join.on, right),
+            right_keys: scope.keys(right),
+        };
+    }
+    if let Some(predicate) = where_clause {
+        plan = LogicalPlan::Filter {
+            input: Box::new(plan),
+            predicate,
+        };
+    }
+    if aggregated {
+        plan = LogicalPlan::Aggregate {
+            input: Box::new(plan),
+            group_by: group_keys,
+            calls,
+        };
+        if let Some(predicate) = having {
+            plan = LogicalPlan::Filter {
+                input: Box::new(plan),
+                predicate,
+            };
+        }
+    }
+    if !sort_keys.is_empty() {
+        plan = LogicalPlan::Sort {
+            input: Box::new(plan),
+            keys: sort_keys,
+        };
+    }
+    if limit.is_some() || offset.is_some() {
+        plan = LogicalPlan::Limit {
+            input: Box::new(plan),
+            limit,
+            offset: offset.unwrap_or(0),
+        };
+    }
+    Ok(LogicalPlan::Project {
+        input: Box::new(plan),
+        columns: projection,
+    })
+}
+
+/// Plan the rows an UPDATE or DELETE touches: a scan of `table_name`,
+/// filtered by the WHERE clause if there is one.
+pub(crate) fn plan_rows(
+    table_name: &str,
+    columns: Vec<Column>,
+    where_clause: Option<&Expr>,
+) -> Result<LogicalPlan, String> {
+    let scope = Scope::single(table_name, columns);
+    let plan = scan(&scope, 0);
+    Ok(match where_clause {
+        Some(expr) => LogicalPlan::Filter {
+            input: Box::new(plan),
+            predicate: resolve_scalar(&scope, expr, 1, "WHERE")?,
+        },
+        None => plan,
+    })
+}
+
+/// Resolve an UPDATE's SET list: each target column paired with the
+/// expression computing its new value from the old row.
+pub(crate) fn plan_assignments(
+    table_name: &str,
+    columns: &[Column],
+    assignments: Vec<Assignment>,
+) -> Result<Vec<(String, Expr)>, String> {
+    let scope = Scope::single(table_name, columns.to_vec());
+    assignments
+        .into_iter()
+        .map(|a| {
+            if !columns.iter().any(|c| c.name == a.column) {
+                return Err(format!(
+                    "Unknown column '{}' in table '{}'",
+                    a.column, table_name
+                ));
+            }
+            let value = resolve_scalar(&scope, &a.value, 1, "SET")?;
+            Ok((a.column, value))
+        })
+        .collect()
+}
+
+fn scan(scope: &Scope, source: usize) -> LogicalPlan {
+    let s = &scope.sources()[source];
+    LogicalPlan::Scan {
+        table: s.table.clone(),
+        columns: s.columns.clone(),
+        qualifier: (scope.sources().len() > 1).then(|| s.qualifier.clone()),
+    }
+}
+
+// Register `call` for computation under its written form, checking its
+// column. Returns the result column name.
+fn add_call(
+    scope: &Scope,
+    call: &AggregateCall,
+    calls: &mut Vec<(String, AggregateCall)>,
+) -> Result<String, String> {
+    let name = call.to_string();
+    if calls.iter().any(|(n, _)| *n == name) {
+        return Ok(name);
+    }
+    let mut resolved = call.clone();
+    if let Some(c) = &call.column {
+        let r = scope.resolve(c)?;
+        if matches!(call.func, AggregateFunc::Sum | AggregateFunc::Avg) && !r.data_type.is_numeric()
+        {
+            return Err(format!("{} needs a numeric column", call));
+        }
+        resolved.column = Some(ColumnRef::new(r.key));
+    }
+    calls.push((name.clone(), resolved));
+    Ok(name)
+}
+
+// Resolve an expression evaluated on each input row (WHERE, ON, SET) over
+// the first `visible` sources. Aggregates are not allowed there.
+fn resolve_scalar(
+    scope: &Scope,
+    expr: &Expr,
+    visible: usize,
+    clause: &str,
+) -> Result<Expr, String> {
+    if expr.has_aggregate() {
+        return Err(format!("Aggregates are not allowed in {}", clause));
+    }
+    scope.resolve_expr(expr, visible)
+}
+
+// Resolve a SELECT item, HAVING or ORDER BY expression. With `grouped` (the
+// GROUP BY keys of an aggregated query), columns outside aggregates must be
+// grouped, and each aggregate is added to `calls`.
+fn resolve_output(
+    scope: &Scope,
+    expr: &Expr,
+    grouped: Option<&[String]>,
+    calls: &mut Vec<(String, AggregateCall)>,
+    what: &str,
+) -> Result<Expr, String> {
+    expr.map_leaves(&mut |leaf| match leaf {
+        Expr::Column(c) => {
+            let key = scope.resolve(c)?.key;
+            if grouped.is_some_and(|keys| !keys.contains(&key)) {
+                return Err(format!(
+                    "{} '{}' must appear in GROUP BY or be used in an aggregate",
+                    what, c
+                ));
+            }
+            Ok(Expr::Column(ColumnRef::new(key)))
+        }
+        Expr::Aggregate(call) => {
+            add_call(scope, call, calls)?;
+            Ok(leaf.clone())
+        }
+        other => Ok(other.clone()),
+    })
+}
+
+// Row keys (left, right) of an AND-ed `left = right` term of a join's ON
+// condition that compares an earlier table with the joined one, if there
+// is such a term on columns of the same type.
+fn equi_join_keys(scope: &Scope, on: &Expr, right: usize) -> Option<(String, String)> {
+    let mut terms = Vec::new();
+    collect_conjuncts(on, &mut terms);
+    terms.into_iter().find_map(|term| {
+        let Expr::Binary {
+            op: Operator::EQUALS,
+            left,
+            right: rhs,
+        } = term
+        else {
+            return None;
+        };
+        let (Expr::Column(a), Expr::Column(b)) = (left.as_ref(), rhs.as_ref()) else {
+            return None;
+        };
+        let a = scope.resolve_in(a, right + 1).ok()?;
+        let b = scope.resolve_in(b, right + 1).ok()?;
+        if a.data_type != b.data_type {
+            return None;
+        }
+        match (a.source == right, b.source == right) {
+            (false, true) => Some((a.key, b.key)),
+            (true, false) => Some((b.key, a.key)),
+            _ => None,
+        }
+    })
+}
+
+pub(crate) fn collect_conjuncts<'a>(expr: &'a Expr, out: &mut Vec<&'a Expr>) {
+    if let Expr::Binary {
+        op: Operator::AND,
+        left,
+        right,
+    } = expr
+    {
+        collect_conjuncts(left, out);
+        collect_conjuncts(right, out);
+    } else {
+        out.push(expr);
+    }
+}
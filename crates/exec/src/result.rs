@@ -1,40 +1,96 @@
-use std::fmt;
+use std::{fmt, io::Write};
 
-use sql::ast::ASTValue;
-use storage::Record;
+use crate::operator::Operator;
+use sql::{
+    ast::ASTValue,
+    temporal::{format_date, format_time, format_timestamp},
+};
 
-#[derive(Debug)]
-pub struct ResultSet {
+/// Rows of a SELECT, produced one at a time as the caller pulls them. Each
+/// row holds one value per entry of `columns`, in the same order. The result
+/// borrows the catalog until it is dropped.
+pub struct ResultSet<'a> {
     pub columns: Vec<String>,
-    pub records: Vec<Record>,
+    root: Box<dyn Operator + 'a>,
+    done: bool,
 }
 
-impl ResultSet {
-    pub fn new(columns: Vec<String>) -> Self {
-        Self {
+impl<'a> ResultSet<'a> {
+    /// Open `root` and wrap it.
+    pub(crate) fn new(
+        columns: Vec<String>,
+        mut root: Box<dyn Operator + 'a>,
+    ) -> Result<Self, String> {
+        root.open()?;
+        Ok(Self {
             columns,
-            records: Vec::new(),
+            root,
+            done: false,
+        })
+    }
+
+    fn finish(&mut self) {
+        if !self.done {
+            self.done = true;
+            self.root.close();
         }
     }
+}
+
+impl Iterator for ResultSet<'_> {
+    type Item = Result<Vec<ASTValue>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.root.next() {
+            Ok(Some(mut rec)) => Some(Ok((0..self.columns.len())
+                .map(|i| rec.data.remove(&i.to_string()).unwrap_or(ASTValue::Null))
+                .collect())),
+            Ok(None) => {
+                self.finish();
+                None
+            }
+            Err(e) => {
+                self.finish();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl Drop for ResultSet<'_> {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
 
-    pub fn add_record(&mut self, record: Record) {
-        self.records.push(record);
+impl fmt::Debug for ResultSet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ResultSet")
+            .field("columns", &self.columns)
+            .finish_non_exhaustive()
     }
 }
 
 #[derive(Debug)]
-pub enum QueryResult {
-    Select(ResultSet),
-    Insert(u64), // Number of rows inserted
-    Update(u64), // Number of rows updated
-    Delete(u64), // Number of rows deleted
-    Create,      // Table created successfully
-    Drop,        // Table dropped successfully
+pub enum QueryResult<'a> {
+    Select(ResultSet<'a>),
+    Insert(u64),     // Number of rows inserted
+    Update(u64),     // Number of rows updated
+    Delete(u64),     // Number of rows deleted
+    Create,          // Table created successfully
+    Drop,            // Table dropped successfully
+    Alter,           // Table altered successfully
+    Truncate,        // Table emptied successfully
+    Skipped(String), // IF [NOT] EXISTS made the statement a no-op; says why
     Use(String),
     Info(Vec<String>),
+    Transaction(&'static str), // BEGIN / COMMIT / ROLLBACK
 }
 
-fn cell_to_string(v: &ASTValue) -> String {
+pub(crate) fn cell_to_string(v: &ASTValue) -> String {
     match v {
         ASTValue::Int(i) => i.to_string(),
         ASTValue::Float(f) => {
@@ -54,74 +110,86 @@ fn cell_to_string(v: &ASTValue) -> String {
             }
         }
         ASTValue::Null => "NULL".into(),
+        ASTValue::Decimal(d) => d.to_string(),
+        ASTValue::Date(d) => format_date(*d),
+        ASTValue::Time(t) => format_time(*t),
+        ASTValue::Timestamp(t) => format_timestamp(*t),
     }
 }
 
-impl fmt::Display for QueryResult {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+/// Rows printed per batch. Column widths are fitted to the first batch and
+/// only grow after it, so a result never has to fit in memory to print.
+const PRINT_BATCH: usize = 1000;
+
+impl QueryResult<'_> {
+    /// Write the result for a terminal, pulling SELECT rows as it goes. An
+    /// error while reading rows stops the output where it occurred.
+    pub fn print(self, out: &mut dyn Write) -> Result<(), String> {
+        let io = |e: std::io::Error| e.to_string();
         match self {
-            QueryResult::Select(result_set) => {
-                let ncols = result_set.columns.len();
-                let mut widths: Vec<usize> = result_set.columns.iter().map(|c| c.len()).collect();
-
-                for rec in &result_set.records {
-                    for (i, col) in result_set.columns.iter().enumerate() {
-                        let t = rec
-                            .data
-                            .get(col)
-                            .map(cell_to_string)
-                            .unwrap_or_else(|| "NULL".to_string());
-                        if t.len() > widths[i] {
-                            widths[i] = t.len();
-                        }
-                    }
-                }
-
-                let print_sep = |f: &mut fmt::Formatter<'_>| -> fmt::Result {
-                    write!(f, "+")?;
-                    for w in &widths {
-                        write!(f, "-{:-<width$}-+", "", width = *w)?;
-                    }
-                    writeln!(f)
-                };
-
-                let print_row = |f: &mut fmt::Formatter<'_>, cells: &[String]| -> fmt::Result {
-                    write!(f, "|")?;
-                    for (i, cell) in cells.iter().enumerate() {
-                        write!(f, " {:<width$} |", cell, width = widths[i])?;
-                    }
-                    writeln!(f)
-                };
-
-                print_sep(f)?;
-                let header: Vec<String> = result_set.columns.clone();
-                print_row(f, &header)?;
-                print_sep(f)?;
-
-                for rec in &result_set.records {
-                    let mut cells = Vec::with_capacity(ncols);
-                    for col in &result_set.columns {
-                        let s = rec
-                            .data
-                            .get(col)
-                            .map(cell_to_string)
-                            .unwrap_or_else(|| "NULL".to_string());
-                        cells.push(s);
-                    }
-                    print_row(f, &cells)?;
-                }
-                print_sep(f)?;
-                writeln!(f, "{} row(s)", result_set.records.len())
+            QueryResult::Select(rows) => print_rows(rows, out),
+            QueryResult::Insert(count) => writeln!(out, "{} row(s) inserted", count).map_err(io),
+            QueryResult::Update(count) => writeln!(out, "{} row(s) updated", count).map_err(io),
+            QueryResult::Delete(count) => writeln!(out, "{} row(s) deleted", count).map_err(io),
+            QueryResult::Create => writeln!(out, "Created successfully").map_err(io),
+            QueryResult::Drop => writeln!(out, "Dropped successfully").map_err(io),
+            QueryResult::Alter => writeln!(out, "Altered successfully").map_err(io),
+            QueryResult::Truncate => writeln!(out, "Truncated successfully").map_err(io),
+            QueryResult::Skipped(reason) => writeln!(out, "{}, skipped", reason).map_err(io),
+            QueryResult::Use(database_name) => writeln!(out, "Using {}", database_name).map_err(io),
+            QueryResult::Info(list) => writeln!(out, "{}", list.join("\n")).map_err(io),
+            QueryResult::Transaction(tag) => writeln!(out, "{}", tag).map_err(io),
+        }
+    }
+}
+
+fn print_rows(mut rows: ResultSet, out: &mut dyn Write) -> Result<(), String> {
+    let io = |e: std::io::Error| e.to_string();
+    let columns = rows.columns.clone();
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.chars().count()).collect();
+
+    let print_sep = |out: &mut dyn Write, widths: &[usize]| -> std::io::Result<()> {
+        write!(out, "+")?;
+        for w in widths {
+            write!(out, "-{:-<width$}-+", "", width = *w)?;
+        }
+        writeln!(out)
+    };
+    let print_row = |out: &mut dyn Write, widths: &[usize], cells: &[String]| {
+        write!(out, "|")?;
+        for (cell, w) in cells.iter().zip(widths) {
+            write!(out, " {:<width$} |", cell, width = *w)?;
+        }
+        writeln!(out)
+    };
+
+    let mut count = 0usize;
+    let mut batch: Vec<Vec<String>> = Vec::with_capacity(PRINT_BATCH);
+    loop {
+        batch.clear();
+        for row in rows.by_ref().take(PRINT_BATCH) {
+            batch.push(row?.iter().map(cell_to_string).collect());
+        }
+        for cells in &batch {
+            for (w, cell) in widths.iter_mut().zip(cells) {
+                *w = (*w).max(cell.chars().count());
             }
-            QueryResult::Insert(count) => write!(f, "{} row(s) inserted", count),
-            QueryResult::Update(count) => write!(f, "{} row(s) updated", count),
-            QueryResult::Delete(count) => write!(f, "{} row(s) deleted", count),
-            QueryResult::Create => write!(f, "Created successfully"),
-            QueryResult::Drop => write!(f, "Dropped successfully"),
-            QueryResult::Use(database_name) => write!(f, "Using {}", database_name),
-            QueryResult::Info(list) => write!(f, "{}", list.join("\n")),
+        }
+        if count == 0 {
+            print_sep(out, &widths).map_err(io)?;
+            print_row(out, &widths, &columns).map_err(io)?;
+            print_sep(out, &widths).map_err(io)?;
+        }
+        for cells in &batch {
+            print_row(out, &widths, cells).map_err(io)?;
+        }
+        count += batch.len();
+        if batch.len() < PRINT_BATCH {
+            break;
         }
     }
+    print_sep(out, &widths).map_err(io)?;
+    writeln!(out, "{} row(s)\n", count).map_err(io)
 }
 
-pub type ExecutionResult = Result<QueryResult, String>;
+pub type ExecutionResult<'a> = Result<QueryResult<'a>, String>;
@@ -0,0 +1,123 @@
+use sql::ast::{ColumnRef, Expr};
+use storage::types::Column;
+use types::tokens::DataType;
+
+// Column name resolution.
+//
+// Every table in FROM / JOIN is a source named by its qualifier: the alias
+// if it has one, else the table name. Rows of a single-table query are
+// keyed by bare column names, rows of a join by `qualifier.column`, so that
+// two tables may share column names. Resolving a ColumnRef gives the key
+// under which its value sits in a row.
+pub(crate) struct Source {
+    pub qualifier: String,
+    pub table: String,
+    pub columns: Vec<Column>,
+}
+
+pub(crate) struct Resolved {
+    pub key: String,
+    pub data_type: DataType,
+    /// Index of the source the column belongs to.
+    pub source: usize,
+}
+
+pub(crate) struct Scope {
+    sources: Vec<Source>,
+}
+
+impl Scope {
+    pub fn new(sources: Vec<Source>) -> Result<Self, String> {
+        for (i, s) in sources.iter().enumerate() {
+            if sources[..i].iter().any(|p| p.qualifier == s.qualifier) {
+                return Err(format!(
+                    "Table name '{}' is used more than once; give it an alias",
+                    s.qualifier
+                ));
+            }
+        }
+        Ok(Self { sources })
+    }
+
+    pub fn single(table: &str, columns: Vec<Column>) -> Self {
+        Self {
+            sources: vec![Source {
+                qualifier: table.to_string(),
+                table: table.to_string(),
+                columns,
+            }],
+        }
+    }
+
+    pub fn sources(&self) -> &[Source] {
+        &self.sources
+    }
+
+    /// Row key of `column` in source `source`.
+    pub fn key(&self, source: usize, column: &str) -> String {
+        if self.sources.len() == 1 {
+            column.to_string()
+        } else {
+            format!("{}.{}", self.sources[source].qualifier, column)
+        }
+    }
+
+    /// Row keys of every column of source `source`, in table order.
+    pub fn keys(&self, source: usize) -> Vec<String> {
+        self.sources[source]
+            .columns
+            .iter()
+            .map(|c| self.key(source, &c.name))
+            .collect()
+    }
+
+    pub fn resolve(&self, c: &ColumnRef) -> Result<Resolved, String> {
+        self.resolve_in(c, self.sources.len())
+    }
+
+    /// Resolve `c` against the first `visible` sources only; a join's ON
+    /// condition cannot see tables joined after it.
+    pub fn resolve_in(&self, c: &ColumnRef, visible: usize) -> Result<Resolved, String> {
+        let sources = &self.sources[..visible];
+        let mut found = None;
+        for (i, s) in sources.iter().enumerate() {
+            if c.table.as_ref().is_some_and(|t| *t != s.qualifier) {
+                continue;
+            }
+            if let Some(col) = s.columns.iter().find(|col| col.name == c.column) {
+                if found.is_some() {
+                    return Err(format!("Column '{}' is ambiguous", c));
+                }
+                found = Some((i, col.data_type.clone()));
+            }
+        }
+        match found {
+            Some((source, data_type)) => Ok(Resolved {
+                key: self.key(source, &c.column),
+                data_type,
+                source,
+            }),
+            None => match &c.table {
+                Some(t) if !sources.iter().any(|s| s.qualifier == *t) => {
+                    Err(format!("Unknown table '{}'", t))
+                }
+                _ if sources.len() == 1 => Err(format!(
+                    "Unknown column '{}' in table '{}'",
+                    c.column, sources[0].table
+                )),
+                _ => Err(format!("Unknown column '{}'", c)),
+            },
+        }
+    }
+
+    /// Copy of `expr` with every column replaced by its row key.
+    /// Aggregates are left as they are.
+    pub fn resolve_expr(&self, expr: &Expr, visible: usize) -> Result<Expr, String> {
+        expr.map_leaves(&mut |leaf| match leaf {
+            Expr::Column(c) => Ok(Expr::Column(ColumnRef::new(
+                self.resolve_in(c, visible)?.key,
+            ))),
+            other => Ok(other.clone()),
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use std::{fs, path::PathBuf};
+
+use catalog::{Catalog, file_catalog::FileCatalog};
+
+use crate::{
+    Executor,
+    executor::QueryExecutor,
+    result::{QueryResult, cell_to_string},
+};
+
+// A database in a temporary directory, removed again on drop, to run SQL
+// against in tests.
+pub(crate) struct TestDb {
+    root: PathBuf,
+    pub(crate) cat: FileCatalog,
+}
+
+impl TestDb {
+    pub(crate) fn new(tag: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("meridb-exec-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let mut cat = FileCatalog::new(root.clone());
+        cat.create_database("db").unwrap();
+        Self { root, cat }
+    }
+
+    /// Run every statement of `script`, stopping at the first error.
+    pub(crate) fn run(&mut self, script: &str) -> Result<(), String> {
+        for ast in sql::parse_script(script).map_err(|e| e.to_string())? {
+            if let QueryResult::Select(rows) = QueryExecutor.execute(&mut self.cat, ast)? {
+                for row in rows {
+                    row?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a single statement.
+    pub(crate) fn execute(&mut self, statement: &str) -> Result<QueryResult<'_>, String> {
+        let ast = sql::parse_command(statement).map_err(|e| e.to_string())?;
+        QueryExecutor.execute(&mut self.cat, ast)
+    }
+
+    /// Rows of one SELECT, each cell as the CLI prints it.
+    pub(crate) fn query(&mut self, select: &str) -> Result<Vec<Vec<String>>, String> {
+        let QueryResult::Select(rows) = self.execute(select)? else {
+            return Err(format!("not a SELECT: {}", select));
+        };
+        rows.map(|row| Ok(row?.iter().map(cell_to_string).collect()))
+            .collect()
+    }
+
+    /// Lines printed by a statement that reports text, such as EXPLAIN.
+    pub(crate) fn lines(&mut self, statement: &str) -> Result<Vec<String>, String> {
+        match self.execute(statement)? {
+            QueryResult::Info(lines) => Ok(lines),
+            _ => Err(format!("no lines from {}", statement)),
+        }
+    }
+
+    /// The first column of every row of `select`.
+    pub(crate) fn column(&mut self, select: &str) -> Result<Vec<String>, String> {
+        Ok(self
+            .query(select)?
+            .into_iter()
+            .map(|mut row| row.remove(0))
+            .collect())
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
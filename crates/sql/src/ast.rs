@@ -1,49 +1,526 @@
-use std::cmp::Ordering;
+use std::{cmp::Ordering, fmt};
 
 use serde::{Deserialize, Serialize};
 
 use types::tokens::{DataType, Operator};
 
+use crate::{
+    decimal::Decimal,
+    temporal::{DateField, format_date, format_time, format_timestamp},
+};
+
 /// Abstract Syntax Tree Value
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ASTValue {
     Int(i64),
     Float(f64),
+    Decimal(Decimal),
     String(String),
     Boolean(bool),
+    /// Days since 1970-01-01.
+    Date(i32),
+    /// Microseconds since midnight.
+    Time(i64),
+    /// Microseconds since 1970-01-01 00:00:00, without time zone.
+    Timestamp(i64),
     Null,
 }
 
+/// Prints the value as a SQL literal.
+impl fmt::Display for ASTValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ASTValue::Int(i) => write!(f, "{}", i),
+            ASTValue::Float(x) => write!(f, "{:?}", x),
+            ASTValue::Decimal(d) => write!(f, "{}", d),
+            ASTValue::String(s) => write!(f, "'{}'", s.replace('\'', "''")),
+            ASTValue::Boolean(true) => write!(f, "TRUE"),
+            ASTValue::Boolean(false) => write!(f, "FALSE"),
+            ASTValue::Date(d) => write!(f, "DATE '{}'", format_date(*d)),
+            ASTValue::Time(t) => write!(f, "TIME '{}'", format_time(*t)),
+            ASTValue::Timestamp(ts) => write!(f, "TIMESTAMP '{}'", format_timestamp(*ts)),
+            ASTValue::Null => write!(f, "NULL"),
+        }
+    }
+}
+
+/// Orders two values of the same type. NULL is unordered, as in SQL: it
+/// compares with nothing, not even another NULL.
 impl PartialOrd for ASTValue {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         match (self, other) {
             (ASTValue::Int(a), ASTValue::Int(b)) => a.partial_cmp(b),
             (ASTValue::Float(a), ASTValue::Float(b)) => a.partial_cmp(b),
+            (ASTValue::Decimal(a), ASTValue::Decimal(b)) => a.partial_cmp(b),
             (ASTValue::String(a), ASTValue::String(b)) => a.partial_cmp(b),
             (ASTValue::Boolean(a), ASTValue::Boolean(b)) => a.partial_cmp(b),
-            (ASTValue::Null, ASTValue::Null) => Some(Ordering::Equal),
-            (ASTValue::Null, _) => Some(Ordering::Less),
-            (_, ASTValue::Null) => Some(Ordering::Greater),
+            (ASTValue::Date(a), ASTValue::Date(b)) => a.partial_cmp(b),
+            (ASTValue::Time(a), ASTValue::Time(b)) => a.partial_cmp(b),
+            (ASTValue::Timestamp(a), ASTValue::Timestamp(b)) => a.partial_cmp(b),
             _ => None,
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnConstraint {
+    Null,
+    NotNull,
+    PrimaryKey,
+    Unique,
+    References(ForeignKey),
+    Default(DefaultExpr),
+    AutoIncrement,
+}
+
+/// A column's `DEFAULT`: a literal, or arithmetic over literals evaluated on
+/// every INSERT that leaves the column out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DefaultExpr {
+    Value(ASTValue),
+    Negate(Box<DefaultExpr>),
+    Binary {
+        op: ArithOp,
+        left: Box<DefaultExpr>,
+        right: Box<DefaultExpr>,
+    },
+}
+
+impl DefaultExpr {
+    /// The same arithmetic as a general expression.
+    pub fn to_expr(&self) -> Expr {
+        match self {
+            DefaultExpr::Value(v) => Expr::Value(v.clone()),
+            DefaultExpr::Negate(operand) => Expr::Negate(Box::new(operand.to_expr())),
+            DefaultExpr::Binary { op, left, right } => {
+                Expr::binary(op.operator(), left.to_expr(), right.to_expr())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl ArithOp {
+    pub fn operator(self) -> Operator {
+        match self {
+            ArithOp::Add => Operator::PLUS,
+            ArithOp::Sub => Operator::MINUS,
+            ArithOp::Mul => Operator::ASTERISK,
+            ArithOp::Div => Operator::DIVIDE,
+        }
+    }
+}
+
+/// One item of an INSERT's VALUES list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertValue {
+    Value(ASTValue),
+    Default,
+}
+
+/// What happens to referencing rows when their parent row is deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReferentialAction {
+    #[default]
+    Restrict,
+    Cascade,
+    SetNull,
+}
+
+/// `FOREIGN KEY (columns) REFERENCES ref_table (ref_columns) ON DELETE ...`.
+/// An empty `ref_columns` means the referenced table's primary key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForeignKey {
+    pub columns: Vec<String>,
+    pub ref_table: String,
+    pub ref_columns: Vec<String>,
+    pub on_delete: ReferentialAction,
+}
+
 #[derive(Debug)]
 pub struct ColumnDefinition {
     pub column_name: String,
     pub column_type: DataType,
-    pub columns_constraints: Vec<Vec<char>>,
+    pub columns_constraints: Vec<ColumnConstraint>,
 }
-#[derive(Debug, PartialEq)]
-pub enum Condition {
-    Comparison {
-        operator: Operator, // e.g., "=", "<>", ">"
-        left: Box<Condition>,
-        right: Box<Condition>,
-    },
-    Column(String), // e.g., "age"
+/// A scalar expression, e.g. `price * qty`, `-n` or `NOT (a = 1 OR b < 2)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Column(ColumnRef), // e.g., "age" or "u.age"
     Value(ASTValue),
+    Aggregate(AggregateCall), // e.g., "COUNT(*)"; not allowed in WHERE or ON
+    Negate(Box<Expr>),
+    Not(Box<Expr>),
+    /// `expr IS NULL`, or `expr IS NOT NULL` when `negated`.
+    IsNull {
+        expr: Box<Expr>,
+        negated: bool,
+    },
+    /// A scalar function such as `COALESCE(a, b, ...)` or `CURRENT_DATE`.
+    Function {
+        func: ScalarFunc,
+        args: Vec<Expr>,
+    },
+    /// Arithmetic (`+ - * /`), comparison (`= != < <= > >=`), `AND` or `OR`.
+    Binary {
+        op: Operator,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+}
+
+/// Binding strength of NOT, between AND and the comparisons.
+const NOT_PRECEDENCE: u8 = 3;
+const COMPARISON_PRECEDENCE: u8 = 4;
+
+impl Expr {
+    pub fn binary(op: Operator, left: Expr, right: Expr) -> Self {
+        Expr::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    /// Binding strength of a binary operator; higher binds tighter.
+    pub fn precedence(op: &Operator) -> u8 {
+        match op {
+            Operator::OR => 1,
+            Operator::AND => 2,
+            Operator::PLUS | Operator::MINUS => 5,
+            Operator::ASTERISK | Operator::DIVIDE => 6,
+            _ => COMPARISON_PRECEDENCE,
+        }
+    }
+
+    /// Whether `pred` holds for this expression or any expression in it.
+    pub fn any(&self, pred: &dyn Fn(&Expr) -> bool) -> bool {
+        pred(self)
+            || match self {
+                Expr::Negate(e) | Expr::Not(e) | Expr::IsNull { expr: e, .. } => e.any(pred),
+                Expr::Function { args, .. } => args.iter().any(|a| a.any(pred)),
+                Expr::Binary { left, right, .. } => left.any(pred) || right.any(pred),
+                Expr::Column(_) | Expr::Value(_) | Expr::Aggregate(_) => false,
+            }
+    }
+
+    pub fn has_aggregate(&self) -> bool {
+        self.any(&|e| matches!(e, Expr::Aggregate(_)))
+    }
+
+    /// Copy of the expression with every column, value and aggregate in it
+    /// replaced by `f` of it.
+    pub fn map_leaves<E>(&self, f: &mut impl FnMut(&Expr) -> Result<Expr, E>) -> Result<Expr, E> {
+        Ok(match self {
+            Expr::Negate(e) => Expr::Negate(Box::new(e.map_leaves(f)?)),
+            Expr::Not(e) => Expr::Not(Box::new(e.map_leaves(f)?)),
+            Expr::IsNull { expr, negated } => Expr::IsNull {
+                expr: Box::new(expr.map_leaves(f)?),
+                negated: *negated,
+            },
+            Expr::Function { func, args } => Expr::Function {
+                func: *func,
+                args: args
+                    .iter()
+                    .map(|a| a.map_leaves(f))
+                    .collect::<Result<_, _>>()?,
+            },
+            Expr::Binary { op, left, right } => {
+                Expr::binary(op.clone(), left.map_leaves(f)?, right.map_leaves(f)?)
+            }
+            leaf => f(leaf)?,
+        })
+    }
+}
+
+/// Prints the expression as SQL, with only the parentheses it needs. This
+/// is also the name of a SELECT item without an alias.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let strength = |e: &Expr| match e {
+            Expr::Binary { op, .. } => Expr::precedence(op),
+            Expr::Not(_) => NOT_PRECEDENCE,
+            Expr::IsNull { .. } => COMPARISON_PRECEDENCE,
+            _ => u8::MAX,
+        };
+        match self {
+            Expr::Column(c) => write!(f, "{}", c),
+            Expr::Value(v) => write!(f, "{}", v),
+            Expr::Aggregate(call) => write!(f, "{}", call),
+            Expr::Negate(e) => match e.as_ref() {
+                Expr::Column(_) | Expr::Aggregate(_) => write!(f, "-{}", e),
+                Expr::Value(ASTValue::Int(i)) if *i >= 0 => write!(f, "-{}", e),
+                Expr::Value(ASTValue::Float(x)) if *x >= 0.0 => write!(f, "-{}", e),
+                Expr::Value(ASTValue::Decimal(d)) if d.mantissa() >= 0 => write!(f, "-{}", e),
+                _ => write!(f, "-({})", e),
+            },
+            Expr::Not(e) if strength(e) < COMPARISON_PRECEDENCE => write!(f, "NOT ({})", e),
+            Expr::Not(e) => write!(f, "NOT {}", e),
+            Expr::IsNull { expr, negated } => {
+                if strength(expr) <= COMPARISON_PRECEDENCE {
+                    write!(f, "({})", expr)?;
+                } else {
+                    write!(f, "{}", expr)?;
+                }
+                write!(f, " IS {}NULL", if *negated { "NOT " } else { "" })
+            }
+            Expr::Function {
+                func:
+                    func @ (ScalarFunc::CurrentTimestamp
+                    | ScalarFunc::CurrentDate
+                    | ScalarFunc::CurrentTime),
+                ..
+            } => write!(f, "{}", func),
+            Expr::Function {
+                func: ScalarFunc::Extract(field),
+                args,
+            } if args.len() == 1 => write!(f, "EXTRACT({} FROM {})", field, args[0]),
+            Expr::Function {
+                func: ScalarFunc::DateTrunc(field),
+                args,
+            } if args.len() == 1 => write!(
+                f,
+                "DATE_TRUNC('{}', {})",
+                field.to_string().to_lowercase(),
+                args[0]
+            ),
+            Expr::Function { func, args } => {
+                write!(f, "{}(", func)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Binary { op, left, right } => {
+                // Operators are left-associative; comparisons do not chain.
+                let p = Expr::precedence(op);
+                let side = |e: &Expr, is_right: bool| {
+                    let s = strength(e);
+                    if s < p || (s == p && (is_right || p == COMPARISON_PRECEDENCE)) {
+                        format!("({})", e)
+                    } else {
+                        e.to_string()
+                    }
+                };
+                write!(f, "{} {} {}", side(left, false), op, side(right, true))
+            }
+        }
+    }
+}
+
+/// A column, optionally qualified by a table name or alias: `age`, `u.age`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ColumnRef {
+    pub table: Option<String>,
+    pub column: String,
+}
+
+impl ColumnRef {
+    pub fn new(column: impl Into<String>) -> Self {
+        Self {
+            table: None,
+            column: column.into(),
+        }
+    }
+}
+
+impl fmt::Display for ColumnRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.table {
+            Some(table) => write!(f, "{}.{}", table, self.column),
+            None => write!(f, "{}", self.column),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateFunc {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunc {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "count" => Some(AggregateFunc::Count),
+            "sum" => Some(AggregateFunc::Sum),
+            "avg" => Some(AggregateFunc::Avg),
+            "min" => Some(AggregateFunc::Min),
+            "max" => Some(AggregateFunc::Max),
+            _ => None,
+        }
+    }
+}
+
+/// A scalar function over a row's values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarFunc {
+    /// The first argument that is not NULL, or NULL if all are.
+    Coalesce,
+    /// NULL if both arguments are equal, else the first.
+    NullIf,
+    /// `NOW()`: the current local date and time.
+    Now,
+    /// `CURRENT_TIMESTAMP`, the same as `NOW()`.
+    CurrentTimestamp,
+    /// `CURRENT_DATE`: today's local date.
+    CurrentDate,
+    /// `CURRENT_TIME`: the current local time of day.
+    CurrentTime,
+    /// `EXTRACT(field FROM x)` of a DATE, TIME or TIMESTAMP, as an integer.
+    Extract(DateField),
+    /// `DATE_TRUNC('field', x)`: x cut back to the start of its field.
+    DateTrunc(DateField),
+}
+
+impl ScalarFunc {
+    /// The function called as `name(...)` with plain arguments.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "coalesce" => Some(ScalarFunc::Coalesce),
+            "nullif" => Some(ScalarFunc::NullIf),
+            "now" => Some(ScalarFunc::Now),
+            _ => None,
+        }
+    }
+
+    /// The function written as a bare keyword, without parentheses.
+    pub fn from_keyword(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "current_timestamp" => Some(ScalarFunc::CurrentTimestamp),
+            "current_date" => Some(ScalarFunc::CurrentDate),
+            "current_time" => Some(ScalarFunc::CurrentTime),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ScalarFunc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScalarFunc::Coalesce => write!(f, "COALESCE"),
+            ScalarFunc::NullIf => write!(f, "NULLIF"),
+            ScalarFunc::Now => write!(f, "NOW"),
+            ScalarFunc::CurrentTimestamp => write!(f, "CURRENT_TIMESTAMP"),
+            ScalarFunc::CurrentDate => write!(f, "CURRENT_DATE"),
+            ScalarFunc::CurrentTime => write!(f, "CURRENT_TIME"),
+            ScalarFunc::Extract(_) => write!(f, "EXTRACT"),
+            ScalarFunc::DateTrunc(_) => write!(f, "DATE_TRUNC"),
+        }
+    }
+}
+
+/// `COUNT(*)` or `func(column)`; `column` is None only for `COUNT(*)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateCall {
+    pub func: AggregateFunc,
+    pub column: Option<ColumnRef>,
+}
+
+/// Prints the call as written, e.g. `SUM(amount)`. This is also the name of
+/// the result column holding its value.
+impl fmt::Display for AggregateCall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.func {
+            AggregateFunc::Count => "COUNT",
+            AggregateFunc::Sum => "SUM",
+            AggregateFunc::Avg => "AVG",
+            AggregateFunc::Min => "MIN",
+            AggregateFunc::Max => "MAX",
+        };
+        match &self.column {
+            Some(column) => write!(f, "{}({})", name, column),
+            None => write!(f, "{}(*)", name),
+        }
+    }
+}
+
+/// One item of a SELECT list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectItem {
+    /// `*`: every column of every table in FROM.
+    Wildcard,
+    /// `expr [AS alias]`.
+    Expr { expr: Expr, alias: Option<String> },
+}
+
+impl SelectItem {
+    /// Name of the result column this item produces: the alias, else the
+    /// expression as written.
+    pub fn name(&self) -> String {
+        match self {
+            SelectItem::Wildcard => "*".to_string(),
+            SelectItem::Expr {
+                alias: Some(alias), ..
+            } => alias.clone(),
+            SelectItem::Expr { expr, .. } => expr.to_string(),
+        }
+    }
+}
+
+/// A table in FROM or JOIN. Its columns are qualified by the alias if there
+/// is one, otherwise by the table name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRef {
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+impl TableRef {
+    pub fn qualifier(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    /// LEFT [OUTER] JOIN: unmatched left rows are kept, padded with NULLs.
+    Left,
+}
+
+/// `[INNER | LEFT [OUTER]] JOIN table [AS alias] ON condition`.
+#[derive(Debug, PartialEq)]
+pub struct Join {
+    pub kind: JoinKind,
+    pub table: TableRef,
+    pub on: Expr,
+}
+
+/// A SELECT statement. Joins apply left to right after `from`.
+#[derive(Debug)]
+pub struct Select {
+    pub columns: Vec<SelectItem>,
+    pub from: TableRef,
+    pub joins: Vec<Join>,
+    pub where_clause: Option<Expr>,
+    pub group_by: Vec<ColumnRef>,
+    pub having: Option<Expr>,
+    pub order_by: Vec<OrderByKey>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// One `ORDER BY` key: an expression, which may name a SELECT item's alias.
+/// `nulls_first` is None unless NULLS FIRST / NULLS LAST was given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderByKey {
+    pub expr: Expr,
+    pub descending: bool,
+    pub nulls_first: Option<bool>,
 }
 
 #[derive(Debug)]
@@ -55,36 +532,48 @@ pub enum ShowType {
 #[derive(Debug)]
 pub struct Assignment {
     pub column: String,
-    pub value: ASTValue,
+    pub value: Expr,
+}
+
+/// What an `ALTER TABLE` changes.
+#[derive(Debug)]
+pub enum AlterAction {
+    AddColumn(ColumnDefinition),
+    DropColumn(String),
+    RenameColumn { from: String, to: String },
 }
 
 #[derive(Debug)]
 pub enum ASTNode {
     Insert {
         table_name: String,
-        values: Vec<ASTValue>,
+        /// Target columns; None means every column in table order.
+        columns: Option<Vec<String>>,
+        rows: Vec<Vec<InsertValue>>,
     },
     CreateTable {
         table_name: String,
         columns: Vec<ColumnDefinition>,
+        foreign_keys: Vec<ForeignKey>,
+        /// `CREATE TABLE IF NOT EXISTS`: an existing table is not an error.
+        if_not_exists: bool,
     },
 
     Update {
         table_name: String,
         assignments: Vec<Assignment>,
-        where_clause: Option<Condition>,
-    },
-    Select {
-        columns: Vec<String>,
-        table_name: String,
-        where_clause: Option<Condition>,
+        where_clause: Option<Expr>,
     },
+    Select(Select),
     Delete {
         table_name: String,
-        where_clause: Option<Condition>,
+        where_clause: Option<Expr>,
     },
     CreateDatabase {
         database_name: String,
+        /// `CREATE DATABASE IF NOT EXISTS`: an existing database is not an
+        /// error.
+        if_not_exists: bool,
     },
     USE {
         database_name: String,
@@ -92,4 +581,37 @@ pub enum ASTNode {
     Show {
         show_type: ShowType,
     },
+    Begin,
+    Commit,
+    Rollback,
+    CreateIndex {
+        index_name: String,
+        table_name: String,
+        column_name: String,
+    },
+    DropIndex {
+        index_name: String,
+    },
+    /// `DROP TABLE [IF EXISTS] <table>`
+    DropTable {
+        table_name: String,
+        if_exists: bool,
+    },
+    /// `DROP DATABASE [IF EXISTS] <database>`
+    DropDatabase {
+        database_name: String,
+        if_exists: bool,
+    },
+    /// `TRUNCATE [TABLE] <table>`: delete every row, keeping the table.
+    Truncate {
+        table_name: String,
+    },
+    AlterTable {
+        table_name: String,
+        action: AlterAction,
+    },
+    /// `EXPLAIN <statement>`: show the plan instead of running it.
+    Explain {
+        statement: Box<ASTNode>,
+    },
 }
@@ -0,0 +1,218 @@
+use std::{cmp::Ordering, fmt, ops::Neg, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// Most significant digits a DECIMAL holds.
+pub const MAX_PRECISION: u32 = 38;
+
+/// Fewest fractional digits a quotient keeps.
+pub const MIN_DIV_SCALE: u8 = 6;
+
+/// Exact fixed-point number, `mantissa * 10^-scale`. Values compare and are
+/// equal by what they denote, so `1.5` equals `1.50`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// None if the value needs more than MAX_PRECISION digits or the scale
+    /// is above it.
+    pub fn new(mantissa: i128, scale: u8) -> Option<Self> {
+        (u32::from(scale) <= MAX_PRECISION && digits(mantissa) <= MAX_PRECISION)
+            .then_some(Self { mantissa, scale })
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// The decimal a float prints as, e.g. 0.1 gives 0.1 rather than the
+    /// binary value closest to it. None for NaN, infinities and floats with
+    /// too many digits.
+    pub fn from_f64(f: f64) -> Option<Self> {
+        if !f.is_finite() {
+            return None;
+        }
+        f.to_string().parse().ok()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// Number of significant digits, counting those after the point.
+    pub fn precision(&self) -> u32 {
+        digits(self.mantissa)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    /// The value with `scale` fractional digits, rounded half away from
+    /// zero when digits are dropped. None if it no longer fits.
+    pub fn rescale(&self, scale: u8) -> Option<Self> {
+        if scale >= self.scale {
+            return Self::new(self.mantissa_at(scale)?, scale);
+        }
+        let divisor = 10i128.pow(u32::from(self.scale - scale));
+        Self::new(div_round(self.mantissa, divisor), scale)
+    }
+
+    /// Exact sum, at the larger of the two scales.
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let sum = self
+            .mantissa_at(scale)?
+            .checked_add(other.mantissa_at(scale)?)?;
+        Self::new(sum, scale)
+    }
+
+    /// Exact difference, at the larger of the two scales.
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.checked_add(&-*other)
+    }
+
+    /// Exact product, at the sum of the two scales. Fractional digits past
+    /// MAX_PRECISION are rounded off.
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let product = self.mantissa.checked_mul(other.mantissa)?;
+        let scale = u32::from(self.scale) + u32::from(other.scale);
+        let excess = scale.saturating_sub(MAX_PRECISION);
+        let product = div_round(product, 10i128.pow(excess));
+        Self::new(product, (scale - excess) as u8)
+    }
+
+    /// Quotient at the largest of both scales and MIN_DIV_SCALE, rounded half
+    /// away from zero. None on division by zero or overflow.
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        let scale = self.scale.max(other.scale).max(MIN_DIV_SCALE);
+        // self / other * 10^scale, as integers.
+        let shift = u32::from(scale - self.scale) + u32::from(other.scale);
+        let numerator = self.mantissa.checked_mul(10i128.checked_pow(shift)?)?;
+        Self::new(div_round(numerator, other.mantissa), scale)
+    }
+
+    // The mantissa at a larger `scale`, if it fits.
+    fn mantissa_at(&self, scale: u8) -> Option<i128> {
+        10i128
+            .checked_pow(u32::from(scale - self.scale))
+            .and_then(|f| self.mantissa.checked_mul(f))
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            mantissa: -self.mantissa,
+            scale: self.scale,
+        }
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(i: i64) -> Self {
+        Self {
+            mantissa: i128::from(i),
+            scale: 0,
+        }
+    }
+}
+
+/// Parses `[+-]digits[.digits]`.
+impl FromStr for Decimal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid decimal '{}'", s);
+        let (negative, body) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int, frac) = body.split_once('.').unwrap_or((body, ""));
+        if (int.is_empty() && frac.is_empty())
+            || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let scale = u8::try_from(frac.len()).map_err(|_| invalid())?;
+        let mut mantissa: i128 = 0;
+        for c in int.chars().chain(frac.chars()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(i128::from(c as u8 - b'0')))
+                .ok_or_else(invalid)?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+        Self::new(mantissa, scale).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = usize::from(self.scale);
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, int, frac)
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        match (self.mantissa_at(scale), other.mantissa_at(scale)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            // Whichever side overflows outweighs the other.
+            (None, _) => self.mantissa.cmp(&0),
+            (_, None) => 0.cmp(&other.mantissa),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Decimal {}
+
+// `n / d` rounded half away from zero.
+fn div_round(n: i128, d: i128) -> i128 {
+    let (q, r) = (n / d, n % d);
+    // |r| < |d| <= 2^127, so doubling it cannot overflow a u128.
+    if r.unsigned_abs() * 2 >= d.unsigned_abs() {
+        q + if (n < 0) == (d < 0) { 1 } else { -1 }
+    } else {
+        q
+    }
+}
+
+// Number of decimal digits in `m`, 1 for zero.
+fn digits(m: i128) -> u32 {
+    m.unsigned_abs().checked_ilog10().map_or(1, |d| d + 1)
+}
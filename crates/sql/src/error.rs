@@ -1,29 +1,68 @@
+use std::fmt;
+
 use snafu::Snafu;
 use types::tokens::Token;
 
 pub type Result<T, E = SqlError> = std::result::Result<T, E>;
 
+/// Where a token starts in the SQL text; both counts start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Pos {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
 #[derive(Debug, Snafu)]
 pub enum SqlError {
-    #[snafu(display("Lexing failed at position {pos}: {reason}"))]
-    LexError { pos: usize, reason: String },
+    #[snafu(display("Lexing failed at {pos}: {reason}"))]
+    LexError { pos: Pos, reason: String },
 
-    #[snafu(display("Unexpected token: expected {expected:?}, found {found:?} at position {pos}"))]
+    #[snafu(display("Unexpected token: expected {expected:?}, found {found:?} at {pos}"))]
     UnexpectedToken {
         expected: Token,
         found: Token,
-        pos: usize,
+        pos: Pos,
+    },
+
+    #[snafu(display("Unterminated string literal at {pos}"))]
+    UnterminatedString { pos: Pos },
+
+    #[snafu(display("Invalid number literal '{literal}' at {pos}"))]
+    InvalidNumber { literal: String, pos: Pos },
+
+    #[snafu(display("Invalid type {data_type} at {pos}: {reason}"))]
+    InvalidType {
+        data_type: String,
+        reason: String,
+        pos: Pos,
     },
 
-    #[snafu(display("Unterminated string literal at position {pos}"))]
-    UnterminatedString { pos: usize },
+    #[snafu(display("Invalid {data_type} literal '{literal}' at {pos}"))]
+    InvalidLiteral {
+        data_type: String,
+        literal: String,
+        pos: Pos,
+    },
+
+    #[snafu(display("Invalid date field '{field}' for {func} at {pos}"))]
+    InvalidDateField {
+        field: String,
+        func: String,
+        pos: Pos,
+    },
 
-    #[snafu(display("Invalid number literal '{literal}' at position {pos}"))]
-    InvalidNumber { literal: String, pos: usize },
+    #[snafu(display("Unsupported column constraint '{constraint}' at {pos}"))]
+    UnsupportedConstraint { constraint: String, pos: Pos },
 
-    #[snafu(display("Unsupported or invalid command"))]
-    UnsupportedCommand,
+    #[snafu(display("Unsupported or invalid command at {pos}"))]
+    UnsupportedCommand { pos: Pos },
 
-    #[snafu(display("SHOW command variant not supported in this flow"))]
-    ShowNotSupported,
+    #[snafu(display("SHOW command variant not supported in this flow at {pos}"))]
+    ShowNotSupported { pos: Pos },
 }
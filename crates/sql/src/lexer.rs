@@ -1,12 +1,18 @@
 use types::tokens::{Operator, Token};
 
-use crate::token;
+use crate::{
+    error::{Pos, Result, SqlError},
+    token,
+};
 
 pub struct Lexer {
     input: Vec<char>,
     pub position: usize,
     pub read_position: usize,
     pub ch: char,
+    /// Position of `ch`.
+    line: usize,
+    column: usize,
 }
 
 fn is_letter(ch: char) -> bool {
@@ -24,12 +30,22 @@ impl Lexer {
             position: 0,
             read_position: 0,
             ch: '\0',
+            line: 1,
+            column: 1,
         };
         lexer.read_char();
         lexer
     }
 
     pub fn read_char(&mut self) {
+        if self.read_position > 0 {
+            if self.ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
         if self.read_position >= self.input.len() {
             self.ch = '\0';
         } else {
@@ -45,6 +61,42 @@ impl Lexer {
         }
     }
 
+    // Whitespace, `-- line comments` and `/* block comments */`.
+    fn skip_whitespace_and_comments(&mut self) -> Result<()> {
+        loop {
+            self.skip_whitespace();
+            if self.ch == '-' && self.peek_char() == '-' {
+                while self.position < self.input.len() && self.ch != '\n' {
+                    self.read_char();
+                }
+            } else if self.ch == '/' && self.peek_char() == '*' {
+                let start = self.pos();
+                self.read_char();
+                self.read_char();
+                while !(self.ch == '*' && self.peek_char() == '/') {
+                    if self.position >= self.input.len() {
+                        return Err(SqlError::LexError {
+                            pos: start,
+                            reason: "unterminated comment".to_string(),
+                        });
+                    }
+                    self.read_char();
+                }
+                self.read_char();
+                self.read_char();
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn pos(&self) -> Pos {
+        Pos {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
     pub fn peek_char(&self) -> char {
         if self.read_position >= self.input.len() {
             '\0'
@@ -53,7 +105,36 @@ impl Lexer {
         }
     }
 
-    pub fn next_token(&mut self) -> Token {
+    // Characters up to the closing `quote`, the opening one being current.
+    // A doubled quote stands for one quote character.
+    fn read_quoted(&mut self, quote: char) -> Result<Vec<char>> {
+        let start = self.pos();
+        let mut chars = Vec::new();
+        loop {
+            self.read_char();
+            if self.position >= self.input.len() {
+                return Err(SqlError::UnterminatedString { pos: start });
+            }
+            if self.ch == quote {
+                if self.peek_char() != quote {
+                    self.read_char(); // consume closing quote
+                    return Ok(chars);
+                }
+                self.read_char(); // consume the first of the pair
+            }
+            chars.push(self.ch);
+        }
+    }
+
+    /// The next token and where it starts. At the end of the input this is
+    /// EOF, positioned just past the last character.
+    pub fn next_token(&mut self) -> Result<(Token, Pos)> {
+        self.skip_whitespace_and_comments()?;
+        let pos = self.pos();
+        Ok((self.read_token()?, pos))
+    }
+
+    fn read_token(&mut self) -> Result<Token> {
         let read_identifier = |l: &mut Lexer| -> Vec<char> {
             let position = l.position;
             while l.position < l.input.len() && is_letter(l.ch) {
@@ -62,16 +143,41 @@ impl Lexer {
             l.input[position..l.position].to_vec()
         };
 
-        let read_number = |l: &mut Lexer| -> Vec<char> {
+        // digits [. digits] [(e | E) [+ | -] digits], or one starting at the
+        // point: `.5`. A number with a point or an exponent is a FLOAT.
+        let read_number = |l: &mut Lexer| -> Token {
             let position = l.position;
-            while l.position < l.input.len() && is_digit(l.ch) {
+            let skip_digits = |l: &mut Lexer| {
+                while l.position < l.input.len() && is_digit(l.ch) {
+                    l.read_char();
+                }
+            };
+            skip_digits(l);
+            let mut float = false;
+            if l.ch == '.' {
+                float = true;
                 l.read_char();
+                skip_digits(l);
+            }
+            let sign = matches!(l.peek_char(), '+' | '-');
+            let exponent_digit = l.input.get(l.read_position + sign as usize);
+            if matches!(l.ch, 'e' | 'E') && exponent_digit.is_some_and(|c| is_digit(*c)) {
+                float = true;
+                l.read_char();
+                if sign {
+                    l.read_char();
+                }
+                skip_digits(l);
+            }
+            let literal = l.input[position..l.position].to_vec();
+            if float {
+                Token::FLOAT(literal)
+            } else {
+                Token::INT(literal)
             }
-            l.input[position..l.position].to_vec()
         };
 
         let mut tok: Token;
-        self.skip_whitespace();
         match self.ch {
             '=' => {
                 tok = Token::Operator(Operator::EQUALS);
@@ -100,6 +206,9 @@ impl Lexer {
                 if self.peek_char() == '=' {
                     self.read_char(); // consume '='
                     tok = Token::Operator(Operator::LTorE);
+                } else if self.peek_char() == '>' {
+                    self.read_char(); // consume '>'
+                    tok = Token::Operator(Operator::NE);
                 }
             }
             '>' => {
@@ -121,17 +230,32 @@ impl Lexer {
             ',' => {
                 tok = Token::COMMA(self.ch);
             }
+            '.' if is_digit(self.peek_char()) => {
+                return Ok(read_number(self));
+            }
+            '.' => {
+                tok = Token::DOT(self.ch);
+            }
             '{' => {
                 tok = Token::LBRACE(self.ch);
             }
             '}' => {
                 tok = Token::RBRACE(self.ch);
             }
-            '"' => {
-                tok = Token::QUOTE(self.ch);
-            }
             '\'' => {
-                tok = Token::SINGLEQUOTE(self.ch);
+                return self.read_quoted('\'').map(Token::STRING);
+            }
+            // A double-quoted identifier is never a keyword: "select".
+            '"' => {
+                let pos = self.pos();
+                let ident = self.read_quoted('"')?;
+                if ident.is_empty() {
+                    return Err(SqlError::LexError {
+                        pos,
+                        reason: "empty quoted identifier".to_string(),
+                    });
+                }
+                return Ok(Token::IDENT(ident));
             }
             '\0' => {
                 tok = Token::EOF;
@@ -141,35 +265,153 @@ impl Lexer {
                     let ident: Vec<char> = read_identifier(self);
                     match token::get_keyword_token(&ident) {
                         Ok(keywork_token) => {
-                            return keywork_token;
+                            return Ok(keywork_token);
                         }
                         Err(_err) => {
-                            return Token::IDENT(ident);
+                            return Ok(Token::IDENT(ident));
                         }
                     }
                 } else if is_digit(self.ch) {
-                    let ident: Vec<char> = read_number(self);
-                    return Token::INT(ident);
+                    return Ok(read_number(self));
                 } else {
-                    return Token::ILLEGAL;
+                    return Err(SqlError::LexError {
+                        pos: self.pos(),
+                        reason: format!("unexpected character '{}'", self.ch),
+                    });
                 }
             }
         }
         self.read_char();
-        tok
+        Ok(tok)
     }
 }
 
-pub fn get_tokens(input: &str) -> Vec<Token> {
+/// Tokens of `input` without the final EOF, and where each starts. The
+/// positions have one more entry: the end of the input.
+pub fn get_tokens(input: &str) -> Result<(Vec<Token>, Vec<Pos>)> {
     let mut l = Lexer::new(input.chars().collect());
     let mut tokens: Vec<Token> = Vec::new();
+    let mut positions = Vec::new();
     loop {
-        let token = l.next_token();
+        let (token, pos) = l.next_token()?;
+        positions.push(pos);
         if token == Token::EOF {
             break;
         } else {
             tokens.push(token);
         }
     }
-    tokens
+    Ok((tokens, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use types::tokens::Command;
+
+    use super::*;
+
+    fn tokens(input: &str) -> Vec<Token> {
+        get_tokens(input).unwrap().0
+    }
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn test_string_literals_and_quoted_identifiers() {
+        assert_eq!(
+            tokens("'it''s' '' 'naïve ✓' \"select\""),
+            [
+                Token::STRING(chars("it's")),
+                Token::STRING(vec![]),
+                Token::STRING(chars("naïve ✓")),
+                Token::IDENT(chars("select")),
+            ]
+        );
+        assert!(matches!(
+            get_tokens("SELECT 'abc"),
+            Err(SqlError::UnterminatedString {
+                pos: Pos { line: 1, column: 8 }
+            })
+        ));
+        assert!(matches!(get_tokens("\"\""), Err(SqlError::LexError { .. })));
+    }
+
+    #[test]
+    fn test_comments_are_skipped() {
+        let (found, positions) = get_tokens(
+            "-- leading\nSELECT /* inline -- not a line comment */ a -- trailing\n/**/;",
+        )
+        .unwrap();
+        assert_eq!(
+            found,
+            [
+                Token::Command(Command::SELECT),
+                Token::IDENT(chars("a")),
+                Token::SEMICOLON(';'),
+            ]
+        );
+        assert_eq!(
+            positions[1],
+            Pos {
+                line: 2,
+                column: 43
+            }
+        );
+        assert_eq!(positions[2], Pos { line: 3, column: 5 });
+        assert!(tokens("-- only a comment").is_empty());
+        match get_tokens("SELECT 1 /* open") {
+            Err(SqlError::LexError { pos, reason }) => {
+                assert_eq!(
+                    pos,
+                    Pos {
+                        line: 1,
+                        column: 10
+                    }
+                );
+                assert_eq!(reason, "unterminated comment");
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_illegal_character_is_an_error() {
+        for (input, column) in [("SELECT % FROM a;", 8), ("SELECT a FROM b WHERE c = ?", 27)] {
+            match get_tokens(input) {
+                Err(SqlError::LexError { pos, reason }) => {
+                    assert_eq!(pos, Pos { line: 1, column });
+                    assert!(reason.starts_with("unexpected character"), "{}", reason);
+                }
+                other => panic!("{:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_number_literals() {
+        assert_eq!(
+            tokens("42 1.5 .5 3. 1e3 2.5E-4 7e+2 9e x1"),
+            [
+                Token::INT(chars("42")),
+                Token::FLOAT(chars("1.5")),
+                Token::FLOAT(chars(".5")),
+                Token::FLOAT(chars("3.")),
+                Token::FLOAT(chars("1e3")),
+                Token::FLOAT(chars("2.5E-4")),
+                Token::FLOAT(chars("7e+2")),
+                // Without digits after it the `e` starts an identifier.
+                Token::INT(chars("9")),
+                Token::IDENT(chars("e")),
+                Token::IDENT(chars("x")),
+                Token::INT(chars("1")),
+            ]
+        );
+        // The sign is a token of its own; the parser joins it to the number.
+        assert_eq!(
+            tokens("-42"),
+            [Token::Operator(Operator::MINUS), Token::INT(chars("42"))]
+        );
+    }
 }
@@ -1,41 +1,165 @@
 pub mod ast;
+pub mod decimal;
 pub mod error;
 pub mod lexer;
 pub mod parser;
+pub mod temporal;
 pub mod token;
 
 use error::{Result, SqlError};
 
 use parser::Parser;
 use tracing::debug;
-use types::tokens::{Command, Token};
+use types::tokens::Token;
 
 use crate::ast::ASTNode;
 
+/// Parse a single statement; anything after its `;` is an error.
 pub fn parse_command(command: &str) -> Result<ASTNode> {
-    let trimmed_command = command.trim();
+    let mut parser = tokenize(command)?;
+    let statement = parser.parse_statement()?;
+    match parser.peek() {
+        None => Ok(statement),
+        Some(found) => Err(SqlError::UnexpectedToken {
+            expected: Token::EOF,
+            found: found.clone(),
+            pos: parser.pos(),
+        }),
+    }
+}
 
-    let tokens = lexer::get_tokens(trimmed_command);
+/// Parse every statement of a script, in order. Statements are separated by
+/// `;`; empty statements are skipped. Nothing is returned if any statement
+/// fails to parse.
+pub fn parse_script(script: &str) -> Result<Vec<ASTNode>> {
+    tokenize(script)?.parse_script()
+}
+
+fn tokenize(text: &str) -> Result<Parser> {
+    let (tokens, positions) = lexer::get_tokens(text)?;
     debug!("Tokens: {tokens:?}");
+    Ok(Parser::new(tokens, positions))
+}
 
-    let mut parser = Parser::new(tokens);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::{ASTValue, InsertValue},
+        error::Pos,
+    };
 
-    match parser.peek() {
-        Some(Token::Command(Command::CREATE)) => {
-            if let Some(Token::Command(Command::TABLE)) = parser.tokens.get(1) {
-                parser.parse_create_table()
-            } else if let Some(Token::Command(Command::DATABASE)) = parser.tokens.get(1) {
-                parser.parse_create_database()
-            } else {
-                Err(SqlError::UnsupportedCommand)
+    #[test]
+    fn test_script_is_split_on_semicolons() {
+        let statements =
+            parse_script("CREATE DATABASE d;; USE d\n;INSERT INTO t VALUES ('a;b', 'it''s');\n;")
+                .unwrap();
+        assert_eq!(statements.len(), 3);
+        assert!(
+            matches!(&statements[0], ASTNode::CreateDatabase { database_name, .. } if database_name == "d")
+        );
+        let ASTNode::Insert { rows, .. } = &statements[2] else {
+            panic!("{:?}", statements[2]);
+        };
+        assert_eq!(
+            rows[0],
+            [
+                InsertValue::Value(ASTValue::String("a;b".to_string())),
+                InsertValue::Value(ASTValue::String("it's".to_string())),
+            ]
+        );
+        assert!(parse_script("  -- nothing\n ; ").unwrap().is_empty());
+        // One bad statement fails the whole script.
+        assert!(parse_script("USE d; SELEC 1; USE e").is_err());
+    }
+
+    #[test]
+    fn test_every_statement_ends_at_its_semicolon() {
+        let script = "BEGIN; CREATE INDEX i ON t (a); EXPLAIN SELECT a FROM t; DROP INDEX i; \
+                      TRUNCATE t; DROP TABLE t; SHOW TABLES; COMMIT";
+        assert_eq!(parse_script(script).unwrap().len(), 8);
+        for text in [
+            "BEGIN COMMIT",
+            "DROP INDEX i DROP INDEX j",
+            "CREATE INDEX i ON t (a) USE d",
+            "SELECT a FROM t USE d",
+            "TRUNCATE t USE d",
+        ] {
+            match parse_script(text) {
+                Err(SqlError::UnexpectedToken { expected, .. }) => {
+                    assert_eq!(expected, Token::SEMICOLON(';'), "{}", text)
+                }
+                other => panic!("{}: {:?}", text, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_command_rejects_a_second_statement() {
+        assert!(parse_command("USE d;").is_ok());
+        match parse_command("USE d; USE e") {
+            Err(SqlError::UnexpectedToken { found, pos, .. }) => {
+                assert_eq!(found, Token::Command(types::tokens::Command::USE));
+                assert_eq!(pos, Pos { line: 1, column: 8 });
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_errors_point_at_the_offending_token() {
+        let script =
+            "CREATE TABLE people (\n    id INTEGER PRIMARY KEY,\n    name VARCHAR NOT NULL\n);";
+        match parse_script(script) {
+            Err(SqlError::UnexpectedToken { found, pos, .. }) => {
+                assert_eq!(found, Token::IDENT("VARCHAR".chars().collect()));
+                assert_eq!(
+                    pos,
+                    Pos {
+                        line: 3,
+                        column: 10
+                    }
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+        match parse_command("INSERT INTO 5 VALUES (1)") {
+            Err(SqlError::UnexpectedToken { found, pos, .. }) => {
+                assert_eq!(found, Token::INT(vec!['5']));
+                assert_eq!(
+                    pos,
+                    Pos {
+                        line: 1,
+                        column: 13
+                    }
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+        match parse_command("SELECT a FROM t LIMIT x") {
+            Err(SqlError::UnexpectedToken { found, pos, .. }) => {
+                assert_eq!(found, Token::IDENT(vec!['x']));
+                assert_eq!(
+                    pos,
+                    Pos {
+                        line: 1,
+                        column: 23
+                    }
+                );
             }
+            other => panic!("{:?}", other),
         }
-        Some(Token::Command(Command::SELECT)) => parser.parse_select(),
-        Some(Token::Command(Command::INSERT)) => parser.parse_insert(),
-        Some(Token::Command(Command::UPDATE)) => parser.parse_update(),
-        Some(Token::Command(Command::DELETE)) => parser.parse_delete(),
-        Some(Token::Command(Command::USE)) => parser.parse_use(),
-        Some(Token::Command(Command::SHOW)) => parser.parse_show(),
-        _ => Err(SqlError::UnsupportedCommand),
+        let err = parse_command("SELECT a FROM").unwrap_err();
+        assert!(
+            matches!(
+                err,
+                SqlError::UnexpectedToken {
+                    found: Token::EOF,
+                    ..
+                }
+            ),
+            "{:?}",
+            err
+        );
     }
 }
@@ -1,21 +1,31 @@
-use types::tokens::{Command, DataType, Operator, Token};
+use types::tokens::{Command, DataType, Helper, Operator, Token};
 
-use super::ast::{ASTNode, ASTValue, Assignment, ColumnDefinition, Condition};
+use super::ast::{
+    ASTNode, ASTValue, AggregateCall, AggregateFunc, AlterAction, ArithOp, Assignment,
+    ColumnConstraint, ColumnDefinition, ColumnRef, DefaultExpr, Expr, ForeignKey, InsertValue,
+    Join, JoinKind, OrderByKey, ReferentialAction, ScalarFunc, Select, SelectItem, TableRef,
+};
 
 use crate::{
     ast::ShowType,
-    error::{Result, SqlError},
+    decimal::{Decimal, MAX_PRECISION},
+    error::{Pos, Result, SqlError},
+    temporal::{DateField, parse_date, parse_time, parse_timestamp},
 };
 
 pub struct Parser {
     pub tokens: Vec<Token>,
+    /// Where each token starts, then where the input ends.
+    positions: Vec<Pos>,
     position: usize,
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self {
+    /// `tokens` and `positions` as returned by `lexer::get_tokens`.
+    pub fn new(tokens: Vec<Token>, positions: Vec<Pos>) -> Self {
         Parser {
             tokens,
+            positions,
             position: 0,
         }
     }
@@ -24,6 +34,23 @@ impl Parser {
         self.tokens.get(self.position)
     }
 
+    pub fn at_end(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    /// Position of the current token, or of the end of the input.
+    pub fn pos(&self) -> Pos {
+        self.pos_at(self.position)
+    }
+
+    fn pos_at(&self, index: usize) -> Pos {
+        let last = self.positions.len().saturating_sub(1);
+        self.positions
+            .get(index.min(last))
+            .copied()
+            .unwrap_or_default()
+    }
+
     fn consume(&mut self) -> Option<&Token> {
         let token = self.tokens.get(self.position);
         self.position += 1;
@@ -31,80 +58,124 @@ impl Parser {
     }
 
     fn expect(&mut self, expected: Token) -> Result<()> {
-        if let Some(token) = self.consume() {
-            if *token == expected {
+        if self.peek() != Some(&expected) {
+            return Err(self.unexpected(expected));
+        }
+        self.consume();
+        Ok(())
+    }
+
+    // `expected` was wanted but the current token, or the end of the input,
+    // was found there.
+    fn unexpected(&self, expected: Token) -> SqlError {
+        SqlError::UnexpectedToken {
+            expected,
+            found: self.peek().cloned().unwrap_or(Token::EOF),
+            pos: self.pos(),
+        }
+    }
+
+    /// One statement, with the `;` that ends it if there is one. After the
+    /// statement there must be a `;` or the end of the input.
+    pub fn parse_statement(&mut self) -> Result<ASTNode> {
+        let statement = self.parse_statement_body()?;
+        self.end_statement()?;
+        Ok(statement)
+    }
+
+    // A statement up to, not including, its `;`.
+    fn parse_statement_body(&mut self) -> Result<ASTNode> {
+        let statement = match self.peek() {
+            Some(Token::Command(Command::EXPLAIN)) => {
+                self.consume();
+                ASTNode::Explain {
+                    statement: Box::new(self.parse_statement_body()?),
+                }
+            }
+            Some(Token::Command(Command::CREATE)) => match self.tokens.get(self.position + 1) {
+                Some(Token::Command(Command::TABLE)) => self.parse_create_table()?,
+                Some(Token::Command(Command::DATABASE)) => self.parse_create_database()?,
+                Some(Token::Command(Command::INDEX)) => self.parse_create_index()?,
+                _ => return Err(SqlError::UnsupportedCommand { pos: self.pos() }),
+            },
+            Some(Token::Command(Command::SELECT)) => self.parse_select()?,
+            Some(Token::Command(Command::INSERT)) => self.parse_insert()?,
+            Some(Token::Command(Command::UPDATE)) => self.parse_update()?,
+            Some(Token::Command(Command::DELETE)) => self.parse_delete()?,
+            Some(Token::Command(Command::USE)) => self.parse_use()?,
+            Some(Token::Command(Command::SHOW)) => self.parse_show()?,
+            Some(Token::Command(Command::BEGIN))
+            | Some(Token::Command(Command::COMMIT))
+            | Some(Token::Command(Command::ROLLBACK)) => self.parse_transaction()?,
+            Some(Token::Helper(Helper::DROP)) => match self.tokens.get(self.position + 1) {
+                Some(Token::Command(Command::INDEX)) => self.parse_drop_index()?,
+                Some(Token::Command(Command::TABLE)) => self.parse_drop_table()?,
+                Some(Token::Command(Command::DATABASE)) => self.parse_drop_database()?,
+                _ => return Err(SqlError::UnsupportedCommand { pos: self.pos() }),
+            },
+            Some(Token::Helper(Helper::ALTER)) => self.parse_alter_table()?,
+            Some(Token::Command(Command::TRUNCATE)) => self.parse_truncate()?,
+            _ => return Err(SqlError::UnsupportedCommand { pos: self.pos() }),
+        };
+        Ok(statement)
+    }
+
+    /// Statements up to the end of the input, skipping empty ones.
+    pub fn parse_script(&mut self) -> Result<Vec<ASTNode>> {
+        let mut statements = Vec::new();
+        loop {
+            while let Some(Token::SEMICOLON(_)) = self.peek() {
+                self.consume();
+            }
+            if self.at_end() {
+                return Ok(statements);
+            }
+            statements.push(self.parse_statement()?);
+        }
+    }
+
+    // The `;` ending a statement, unless the input ends there.
+    fn end_statement(&mut self) -> Result<()> {
+        match self.peek() {
+            None => Ok(()),
+            Some(Token::SEMICOLON(_)) => {
+                self.consume();
                 Ok(())
-            } else {
-                Err(SqlError::UnexpectedToken {
-                    expected: expected.clone(),
-                    found: token.clone(),
-                    pos: self.position,
-                })
             }
-        } else {
-            Err(SqlError::UnexpectedToken {
-                expected: expected.clone(),
-                found: Token::EOF,
-                pos: self.position,
-            })
+            Some(_) => Err(self.unexpected(Token::SEMICOLON(';'))),
         }
     }
+
     pub fn parse_create_table(&mut self) -> Result<ASTNode> {
         self.expect(Token::Command(Command::CREATE))?;
 
         self.expect(Token::Command(Command::TABLE))?;
+        let if_not_exists = self.parse_if_not_exists()?;
 
-        let table_name = if let Some(Token::IDENT(name)) = self.consume() {
-            name.iter().collect::<String>()
-        } else {
-            return Err(SqlError::UnexpectedToken {
-                expected: Token::IDENT(vec![]),
-                found: self.peek().cloned().unwrap_or(Token::EOF),
-                pos: self.position,
-            });
-        };
+        let table_name = self.parse_ident()?;
 
         self.expect(Token::LPAREN('('))?;
 
         let mut columns = Vec::new();
+        let mut foreign_keys = Vec::new();
         while let Some(token) = self.peek() {
             match token {
-                Token::IDENT(_) => {
-                    let column_name = if let Some(Token::IDENT(name)) = self.consume() {
-                        name.iter().collect::<String>()
-                    } else {
-                        return Err(SqlError::UnexpectedToken {
-                            expected: Token::IDENT(vec![]),
-                            found: self.peek().cloned().unwrap_or(Token::EOF),
-                            pos: self.position,
-                        });
-                    };
-
-                    let column_type = if let Some(Token::DataType(typ)) = self.consume() {
-                        typ.clone()
-                    } else {
-                        return Err(SqlError::UnexpectedToken {
-                            expected: Token::DataType(DataType::INTEGER),
-                            found: self.peek().cloned().unwrap_or(Token::EOF),
-                            pos: self.position,
-                        });
-                    };
-
-                    let mut constraints = Vec::new();
-                    while let Some(Token::Helper(constr)) = self.peek() {
-                        constraints.push(constr.to_string().chars().collect());
+                Token::Helper(Helper::FOREIGN) => {
+                    foreign_keys.push(self.parse_foreign_key()?);
+                    if let Some(Token::COMMA(',')) = self.peek() {
                         self.consume();
+                    } else {
+                        self.expect(Token::RPAREN(')'))?;
+                        break;
                     }
-
-                    columns.push(ColumnDefinition {
-                        column_name,
-                        column_type,
-                        columns_constraints: constraints,
-                    });
+                }
+                Token::IDENT(_) => {
+                    columns.push(self.parse_column_definition()?);
 
                     if let Some(Token::COMMA(',')) = self.peek() {
                         self.consume();
                     } else {
+                        self.expect(Token::RPAREN(')'))?;
                         break;
                     }
                 }
@@ -113,11 +184,7 @@ impl Parser {
                     break;
                 }
                 _ => {
-                    return Err(SqlError::UnexpectedToken {
-                        expected: Token::IDENT(vec![]),
-                        found: self.peek().cloned().unwrap_or(Token::EOF),
-                        pos: self.position,
-                    });
+                    return Err(self.unexpected(Token::IDENT(vec![])));
                 }
             }
         }
@@ -125,164 +192,564 @@ impl Parser {
         Ok(ASTNode::CreateTable {
             table_name,
             columns,
+            foreign_keys,
+            if_not_exists,
+        })
+    }
+
+    // <name> <type> [<constraint> ...]
+    fn parse_column_definition(&mut self) -> Result<ColumnDefinition> {
+        let column_name = self.parse_ident()?;
+
+        let Some(Token::DataType(column_type)) = self.peek() else {
+            return Err(self.unexpected(Token::DataType(DataType::INTEGER)));
+        };
+        let column_type = column_type.clone();
+        self.consume();
+        let column_type = match column_type {
+            DataType::DECIMAL(None) => DataType::DECIMAL(self.parse_precision_scale("DECIMAL")?),
+            DataType::NUMERIC(None) => DataType::NUMERIC(self.parse_precision_scale("NUMERIC")?),
+            other => other,
+        };
+
+        let mut constraints = Vec::new();
+        while let Some(Token::Helper(_)) = self.peek() {
+            constraints.push(self.parse_column_constraint(&column_name)?);
+        }
+
+        Ok(ColumnDefinition {
+            column_name,
+            column_type,
+            columns_constraints: constraints,
         })
     }
 
+    // [(precision [, scale])] after DECIMAL / NUMERIC; the scale defaults to 0.
+    fn parse_precision_scale(&mut self, type_name: &str) -> Result<Option<(u8, u8)>> {
+        if !matches!(self.peek(), Some(Token::LPAREN(_))) {
+            return Ok(None);
+        }
+        let pos = self.pos();
+        self.consume();
+        let precision = self.parse_count()?;
+        let scale = if let Some(Token::COMMA(_)) = self.peek() {
+            self.consume();
+            self.parse_count()?
+        } else {
+            0
+        };
+        self.expect(Token::RPAREN(')'))?;
+        let invalid = |reason: String| SqlError::InvalidType {
+            data_type: format!("{}({},{})", type_name, precision, scale),
+            reason,
+            pos,
+        };
+        if !(1..=u64::from(MAX_PRECISION)).contains(&precision) {
+            return Err(invalid(format!(
+                "precision must be between 1 and {}",
+                MAX_PRECISION
+            )));
+        }
+        if scale > precision {
+            return Err(invalid("scale must not exceed the precision".to_string()));
+        }
+        Ok(Some((precision as u8, scale as u8)))
+    }
+
+    // NULL | NOT NULL | PRIMARY KEY | UNIQUE | AUTOINCREMENT | DEFAULT ...
+    // | REFERENCES t [(col)] [ON DELETE ...]
+    fn parse_column_constraint(&mut self, column_name: &str) -> Result<ColumnConstraint> {
+        if let Some(Token::Helper(Helper::REFERENCES)) = self.peek() {
+            let fk = self.parse_references(vec![column_name.to_string()])?;
+            return Ok(ColumnConstraint::References(fk));
+        }
+        let pos = self.pos();
+        let constraint = match self.consume() {
+            Some(Token::Helper(Helper::NULL)) => ColumnConstraint::Null,
+            Some(Token::Helper(Helper::NOT)) => {
+                self.expect(Token::Helper(Helper::NULL))?;
+                ColumnConstraint::NotNull
+            }
+            Some(Token::Helper(Helper::PRIMARY)) => {
+                self.expect(Token::Helper(Helper::KEY))?;
+                ColumnConstraint::PrimaryKey
+            }
+            Some(Token::Helper(Helper::UNIQUE)) => ColumnConstraint::Unique,
+            Some(Token::Helper(Helper::AUTOINCREMENT)) => ColumnConstraint::AutoIncrement,
+            Some(Token::Helper(Helper::DEFAULT)) => {
+                ColumnConstraint::Default(self.parse_default_operand()?)
+            }
+            other => {
+                return Err(SqlError::UnsupportedConstraint {
+                    constraint: match other {
+                        Some(Token::Helper(h)) => h.to_string(),
+                        other => format!("{:?}", other.cloned().unwrap_or(Token::EOF)),
+                    },
+                    pos,
+                });
+            }
+        };
+        Ok(constraint)
+    }
+
+    // DEFAULT literal | DEFAULT -literal | DEFAULT (expr)
+    fn parse_default_operand(&mut self) -> Result<DefaultExpr> {
+        match self.peek() {
+            Some(Token::LPAREN('(')) => {
+                self.consume();
+                let expr = self.parse_default_sum()?;
+                self.expect(Token::RPAREN(')'))?;
+                Ok(expr)
+            }
+            Some(Token::Operator(Operator::MINUS)) if !self.at_negative_number() => {
+                self.consume();
+                Ok(DefaultExpr::Negate(Box::new(self.parse_default_operand()?)))
+            }
+            _ => Ok(DefaultExpr::Value(self.parse_value()?)),
+        }
+    }
+
+    // term (('+' | '-') term)*
+    fn parse_default_sum(&mut self) -> Result<DefaultExpr> {
+        let mut left = self.parse_default_product()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Operator(Operator::PLUS)) => ArithOp::Add,
+                Some(Token::Operator(Operator::MINUS)) => ArithOp::Sub,
+                _ => return Ok(left),
+            };
+            self.consume();
+            let right = self.parse_default_product()?;
+            left = DefaultExpr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+    }
+
+    // operand (('*' | '/') operand)*
+    fn parse_default_product(&mut self) -> Result<DefaultExpr> {
+        let mut left = self.parse_default_operand()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Operator(Operator::ASTERISK)) => ArithOp::Mul,
+                Some(Token::Operator(Operator::DIVIDE)) => ArithOp::Div,
+                _ => return Ok(left),
+            };
+            self.consume();
+            let right = self.parse_default_operand()?;
+            left = DefaultExpr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+    }
+
+    // FOREIGN KEY (col, ...) REFERENCES t [(col, ...)] [ON DELETE ...]
+    fn parse_foreign_key(&mut self) -> Result<ForeignKey> {
+        self.expect(Token::Helper(Helper::FOREIGN))?;
+        self.expect(Token::Helper(Helper::KEY))?;
+        let columns = self.parse_ident_list()?;
+        self.parse_references(columns)
+    }
+
+    fn parse_references(&mut self, columns: Vec<String>) -> Result<ForeignKey> {
+        self.expect(Token::Helper(Helper::REFERENCES))?;
+        let ref_table = self.parse_ident()?;
+        let ref_columns = if let Some(Token::LPAREN('(')) = self.peek() {
+            self.parse_ident_list()?
+        } else {
+            Vec::new()
+        };
+
+        let mut on_delete = ReferentialAction::default();
+        if let Some(Token::Helper(Helper::ON)) = self.peek() {
+            self.consume();
+            self.expect(Token::Command(Command::DELETE))?;
+            on_delete = match self.peek() {
+                Some(Token::Helper(Helper::RESTRICT)) => ReferentialAction::Restrict,
+                Some(Token::Helper(Helper::CASCADE)) => ReferentialAction::Cascade,
+                Some(Token::Command(Command::SET)) => ReferentialAction::SetNull,
+                _ => return Err(self.unexpected(Token::Helper(Helper::RESTRICT))),
+            };
+            self.consume();
+            if on_delete == ReferentialAction::SetNull {
+                self.expect(Token::Helper(Helper::NULL))?;
+            }
+        }
+
+        Ok(ForeignKey {
+            columns,
+            ref_table,
+            ref_columns,
+            on_delete,
+        })
+    }
+
+    // (ident, ident, ...)
+    fn parse_ident_list(&mut self) -> Result<Vec<String>> {
+        self.expect(Token::LPAREN('('))?;
+        let mut idents = vec![self.parse_ident()?];
+        while let Some(Token::COMMA(',')) = self.peek() {
+            self.consume();
+            idents.push(self.parse_ident()?);
+        }
+        self.expect(Token::RPAREN(')'))?;
+        Ok(idents)
+    }
+
     pub fn parse_create_database(&mut self) -> Result<ASTNode> {
         self.expect(Token::Command(Command::CREATE))?;
 
         self.expect(Token::Command(Command::DATABASE))?;
+        let if_not_exists = self.parse_if_not_exists()?;
 
-        let database_name = if let Some(Token::IDENT(name)) = self.consume() {
-            name.iter().collect::<String>()
-        } else {
-            return Err(SqlError::UnexpectedToken {
-                expected: Token::IDENT(vec![]),
-                found: self.peek().cloned().unwrap_or(Token::EOF),
-                pos: self.position,
-            });
-        };
+        let database_name = self.parse_ident()?;
 
-        Ok(ASTNode::CreateDatabase { database_name })
+        Ok(ASTNode::CreateDatabase {
+            database_name,
+            if_not_exists,
+        })
     }
 }
 
 impl Parser {
-    pub fn parse_condition(&mut self) -> Result<Condition> {
-        self.parse_expression()
+    // Expressions, loosest-binding first:
+    //
+    //   expr       := and (OR and)*
+    //   and        := not (AND not)*
+    //   not        := NOT not | comparison
+    //   comparison := sum [(= | != | <> | < | <= | > | >=) sum | IS [NOT] NULL]
+    //   sum        := product ((+ | -) product)*
+    //   product    := unary ((* | /) unary)*
+    //   unary      := - unary | primary
+    //   primary    := literal | aggregate | function | column | ( expr )
+    pub fn parse_expr(&mut self) -> Result<Expr> {
+        self.parse_left_assoc(&[Operator::OR], Self::parse_and)
     }
 
-    fn parse_expression(&mut self) -> Result<Condition> {
-        let mut left = self.parse_term()?;
-        while let Some(Token::Operator(op)) = self.peek() {
-            if op == &Operator::AND || op == &Operator::OR {
-                let op = if let Token::Operator(op) = self.consume().unwrap() {
-                    op.clone()
-                } else {
-                    return Err(SqlError::UnexpectedToken {
-                        expected: Token::Operator(Operator::AND),
-                        found: self.peek().cloned().unwrap_or(Token::EOF),
-                        pos: self.position,
-                    });
-                };
-                let right = self.parse_term()?;
-                left = Condition::Comparison {
-                    operator: op,
-                    left: Box::new(left),
-                    right: Box::new(right),
-                };
-            } else {
-                break;
+    fn parse_and(&mut self) -> Result<Expr> {
+        self.parse_left_assoc(&[Operator::AND], Self::parse_not)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if let Some(Token::Helper(Helper::NOT)) = self.peek() {
+            self.consume();
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    // Comparisons do not chain: `a < b < c` is an error.
+    fn parse_comparison(&mut self) -> Result<Expr> {
+        let left = self.parse_sum()?;
+        if let Some(Token::Helper(Helper::IS)) = self.peek() {
+            self.consume();
+            let negated = matches!(self.peek(), Some(Token::Helper(Helper::NOT)));
+            if negated {
+                self.consume();
             }
+            self.expect(Token::Helper(Helper::NULL))?;
+            return Ok(Expr::IsNull {
+                expr: Box::new(left),
+                negated,
+            });
         }
+        let op = match self.peek() {
+            Some(Token::Operator(
+                op @ (Operator::EQUALS
+                | Operator::NE
+                | Operator::LT
+                | Operator::LTorE
+                | Operator::GT
+                | Operator::GTorE),
+            )) => op.clone(),
+            _ => return Ok(left),
+        };
+        self.consume();
+        Ok(Expr::binary(op, left, self.parse_sum()?))
+    }
+
+    fn parse_sum(&mut self) -> Result<Expr> {
+        self.parse_left_assoc(&[Operator::PLUS, Operator::MINUS], Self::parse_product)
+    }
 
-        Ok(left)
+    fn parse_product(&mut self) -> Result<Expr> {
+        self.parse_left_assoc(&[Operator::ASTERISK, Operator::DIVIDE], Self::parse_unary)
     }
 
-    fn parse_term(&mut self) -> Result<Condition> {
-        if let Some(Token::LPAREN('(')) = self.peek() {
+    // operand (op operand)* for any of `ops`, grouping to the left.
+    fn parse_left_assoc(
+        &mut self,
+        ops: &[Operator],
+        operand: fn(&mut Self) -> Result<Expr>,
+    ) -> Result<Expr> {
+        let mut left = operand(self)?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Operator(op)) if ops.contains(op) => op.clone(),
+                _ => return Ok(left),
+            };
             self.consume();
-            let expr = self.parse_expression()?;
-            self.expect(Token::RPAREN(')'))?;
-            return Ok(expr);
+            left = Expr::binary(op, left, operand(self)?);
         }
-        let left = match self.consume() {
-            Some(Token::IDENT(col)) => Condition::Column(col.iter().collect::<String>()),
-            Some(token) => {
-                return Err(SqlError::UnexpectedToken {
-                    expected: Token::IDENT(vec![]),
-                    found: token.clone(),
-                    pos: self.position,
-                });
-            }
-            None => {
-                return Err(SqlError::UnexpectedToken {
-                    expected: Token::IDENT(vec![]),
-                    found: Token::EOF,
-                    pos: self.position,
-                });
+    }
+
+    // `-` right before a number is part of the literal, so that the most
+    // negative integer can be written.
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.at_negative_number() {
+            return Ok(Expr::Value(self.parse_value()?));
+        }
+        if let Some(Token::Operator(Operator::MINUS)) = self.peek() {
+            self.consume();
+            return Ok(Expr::Negate(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        if let Some(call) = self.parse_aggregate()? {
+            return Ok(Expr::Aggregate(call));
+        }
+        if let Some(function) = self.parse_function()? {
+            return Ok(function);
+        }
+        match self.peek() {
+            Some(Token::LPAREN('(')) => {
+                self.consume();
+                let expr = self.parse_expr()?;
+                self.expect(Token::RPAREN(')'))?;
+                Ok(expr)
             }
+            Some(Token::IDENT(_)) => Ok(Expr::Column(self.parse_column_ref()?)),
+            Some(
+                Token::INT(_)
+                | Token::FLOAT(_)
+                | Token::STRING(_)
+                | Token::Helper(Helper::NULL)
+                | Token::TRUE
+                | Token::FALSE
+                | Token::DataType(
+                    DataType::DATE | DataType::TIME | DataType::TIMESTAMP | DataType::DATETIME,
+                ),
+            ) => Ok(Expr::Value(self.parse_value()?)),
+            other => Err(SqlError::UnexpectedToken {
+                expected: Token::IDENT(vec![]),
+                found: other.cloned().unwrap_or(Token::EOF),
+                pos: self.pos(),
+            }),
+        }
+    }
+
+    // COALESCE(expr, ...), NULLIF(expr, expr), NOW(), CURRENT_DATE,
+    // CURRENT_TIME, CURRENT_TIMESTAMP, EXTRACT(field FROM expr) or
+    // DATE_TRUNC('field', expr); None if the next tokens are not a call of a
+    // scalar function.
+    fn parse_function(&mut self) -> Result<Option<Expr>> {
+        let Some(Token::IDENT(name)) = self.peek() else {
+            return Ok(None);
         };
-        let op = match self.consume() {
-            Some(Token::Operator(op)) => op.clone(),
-            Some(token) => {
-                return Err(SqlError::UnexpectedToken {
-                    expected: Token::Operator(Operator::AND),
-                    found: token.clone(),
-                    pos: self.position,
-                });
-            }
-            None => {
-                return Err(SqlError::UnexpectedToken {
-                    expected: Token::Operator(Operator::AND),
-                    found: Token::EOF,
-                    pos: self.position,
-                });
-            }
+        let name: String = name.iter().collect();
+        if !matches!(self.tokens.get(self.position + 1), Some(Token::LPAREN('('))) {
+            let Some(func) = ScalarFunc::from_keyword(&name) else {
+                return Ok(None);
+            };
+            self.consume();
+            return Ok(Some(Expr::Function {
+                func,
+                args: Vec::new(),
+            }));
+        }
+        let func = match name.to_ascii_lowercase().as_str() {
+            "extract" => return self.parse_extract().map(Some),
+            "date_trunc" => return self.parse_date_trunc().map(Some),
+            _ => ScalarFunc::from_name(&name),
         };
-
-        let right = match self.peek() {
-            Some(Token::IDENT(col)) => {
-                let col_name = col.iter().collect::<String>();
-                self.consume(); // consume IDENT
-                Condition::Column(col_name)
+        let Some(func) = func else {
+            return Ok(None);
+        };
+        self.consume();
+        self.consume();
+        let mut args = Vec::new();
+        match func {
+            ScalarFunc::Coalesce => {
+                args.push(self.parse_expr()?);
+                while let Some(Token::COMMA(',')) = self.peek() {
+                    self.consume();
+                    args.push(self.parse_expr()?);
+                }
             }
-            Some(Token::INT(_)) | Some(Token::SINGLEQUOTE(_)) => {
-                let value = self.parse_value()?; // consume happens *inside* parse_value
-                Condition::Value(value)
+            ScalarFunc::NullIf => {
+                args.push(self.parse_expr()?);
+                self.expect(Token::COMMA(','))?;
+                args.push(self.parse_expr()?);
             }
-            Some(token) => {
+            _ => {}
+        }
+        self.expect(Token::RPAREN(')'))?;
+        Ok(Some(Expr::Function { func, args }))
+    }
+
+    // EXTRACT ( field FROM expr )
+    fn parse_extract(&mut self) -> Result<Expr> {
+        self.consume();
+        self.consume();
+        let pos = self.pos();
+        let field = match self.consume() {
+            Some(Token::IDENT(name)) => name.iter().collect::<String>(),
+            other => {
                 return Err(SqlError::UnexpectedToken {
                     expected: Token::IDENT(vec![]),
-                    found: token.clone(),
-                    pos: self.position,
+                    found: other.cloned().unwrap_or(Token::EOF),
+                    pos,
                 });
             }
-            None => {
+        };
+        let field = DateField::from_name(&field).ok_or(SqlError::InvalidDateField {
+            field,
+            func: "EXTRACT".to_string(),
+            pos,
+        })?;
+        self.expect(Token::Command(Command::FROM))?;
+        let arg = self.parse_expr()?;
+        self.expect(Token::RPAREN(')'))?;
+        Ok(Expr::Function {
+            func: ScalarFunc::Extract(field),
+            args: vec![arg],
+        })
+    }
+
+    // DATE_TRUNC ( 'field', expr )
+    fn parse_date_trunc(&mut self) -> Result<Expr> {
+        self.consume();
+        self.consume();
+        let pos = self.pos();
+        let field = match self.consume() {
+            Some(Token::STRING(name)) => name.iter().collect::<String>(),
+            other => {
                 return Err(SqlError::UnexpectedToken {
-                    expected: Token::IDENT(vec![]),
-                    found: Token::EOF,
-                    pos: self.position,
+                    expected: Token::STRING(vec![]),
+                    found: other.cloned().unwrap_or(Token::EOF),
+                    pos,
                 });
             }
         };
-        Ok(Condition::Comparison {
-            operator: op,
-            left: Box::new(left),
-            right: Box::new(right),
+        let field = DateField::from_name(&field)
+            .filter(DateField::is_unit)
+            .ok_or(SqlError::InvalidDateField {
+                field,
+                func: "DATE_TRUNC".to_string(),
+                pos,
+            })?;
+        self.expect(Token::COMMA(','))?;
+        let arg = self.parse_expr()?;
+        self.expect(Token::RPAREN(')'))?;
+        Ok(Expr::Function {
+            func: ScalarFunc::DateTrunc(field),
+            args: vec![arg],
         })
     }
+
+    fn at_negative_number(&self) -> bool {
+        matches!(self.peek(), Some(Token::Operator(Operator::MINUS)))
+            && matches!(
+                self.tokens.get(self.position + 1),
+                Some(Token::INT(_) | Token::FLOAT(_))
+            )
+    }
+
+    // A literal: [-] number, 'string', NULL, TRUE, FALSE, or a typed date /
+    // time literal such as DATE '2026-10-17'.
     fn parse_value(&mut self) -> Result<ASTValue> {
+        let sign = if self.at_negative_number() {
+            self.consume();
+            "-"
+        } else {
+            ""
+        };
         match self.peek() {
+            Some(Token::Helper(Helper::NULL)) => {
+                self.consume();
+                Ok(ASTValue::Null)
+            }
+            Some(Token::TRUE) => {
+                self.consume();
+                Ok(ASTValue::Boolean(true))
+            }
+            Some(Token::FALSE) => {
+                self.consume();
+                Ok(ASTValue::Boolean(false))
+            }
             Some(Token::INT(val)) => {
-                let int_value = val.iter().collect::<String>().parse::<i64>().map_err(|_| {
-                    SqlError::InvalidNumber {
-                        literal: val.iter().collect(),
-                        pos: self.position,
-                    }
-                })?;
+                let literal = format!("{}{}", sign, val.iter().collect::<String>());
+                let int_value = literal
+                    .parse::<i64>()
+                    .map_err(|_| SqlError::InvalidNumber {
+                        literal: literal.clone(),
+                        pos: self.pos(),
+                    })?;
                 self.consume();
                 Ok(ASTValue::Int(int_value))
             }
-            Some(Token::SINGLEQUOTE(_)) => {
-                self.consume(); // Consume opening quote
-                if let Some(Token::IDENT(val)) = self.consume() {
-                    let str_value = val.iter().collect();
-                    self.expect(Token::SINGLEQUOTE('\''))?;
-                    Ok(ASTValue::String(str_value))
-                } else {
-                    Err(SqlError::UnexpectedToken {
-                        expected: Token::IDENT(vec![]),
-                        found: Token::EOF,
-                        pos: self.position,
-                    })
+            Some(Token::FLOAT(val)) => {
+                let literal = format!("{}{}", sign, val.iter().collect::<String>());
+                // Without an exponent the literal is exact, as in `price * 1.07`.
+                if !literal.contains(['e', 'E'])
+                    && let Ok(decimal) = literal.parse::<Decimal>()
+                {
+                    self.consume();
+                    return Ok(ASTValue::Decimal(decimal));
                 }
+                let float_value = literal
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|f| f.is_finite())
+                    .ok_or_else(|| SqlError::InvalidNumber {
+                        literal: literal.clone(),
+                        pos: self.pos(),
+                    })?;
+                self.consume();
+                Ok(ASTValue::Float(float_value))
             }
-            _ => Err(SqlError::UnexpectedToken {
-                expected: Token::IDENT(vec![]),
-                found: Token::EOF,
-                pos: self.position,
-            }),
+            Some(Token::STRING(val)) => {
+                let str_value = val.iter().collect();
+                self.consume();
+                Ok(ASTValue::String(str_value))
+            }
+            Some(Token::DataType(
+                data_type @ (DataType::DATE
+                | DataType::TIME
+                | DataType::TIMESTAMP
+                | DataType::DATETIME),
+            )) => {
+                let data_type = data_type.clone();
+                self.consume();
+                let pos = self.pos();
+                let literal: String = match self.consume() {
+                    Some(Token::STRING(val)) => val.iter().collect(),
+                    other => {
+                        return Err(SqlError::UnexpectedToken {
+                            expected: Token::STRING(vec![]),
+                            found: other.cloned().unwrap_or(Token::EOF),
+                            pos,
+                        });
+                    }
+                };
+                let value = match data_type {
+                    DataType::DATE => parse_date(&literal).map(ASTValue::Date),
+                    DataType::TIME => parse_time(&literal).map(ASTValue::Time),
+                    _ => parse_timestamp(&literal).map(ASTValue::Timestamp),
+                };
+                value.ok_or(SqlError::InvalidLiteral {
+                    data_type: data_type.to_string(),
+                    literal,
+                    pos,
+                })
+            }
+            _ => Err(self.unexpected(Token::IDENT(vec![]))),
         }
     }
 }
@@ -294,10 +761,10 @@ impl Parser {
         let mut columns = Vec::new();
         if let Some(Token::Operator(Operator::ASTERISK)) = self.peek() {
             self.consume();
-            columns = vec!["*".to_string()];
+            columns = vec![SelectItem::Wildcard];
         } else {
-            while let Some(Token::IDENT(col)) = self.consume() {
-                columns.push(col.iter().collect::<String>());
+            loop {
+                columns.push(self.parse_select_item()?);
                 if let Some(Token::COMMA(',')) = self.peek() {
                     self.consume();
                 } else {
@@ -308,30 +775,209 @@ impl Parser {
 
         self.expect(Token::Command(Command::FROM))?;
 
-        let table_name = if let Some(Token::IDENT(name)) = self.consume() {
-            name.iter().collect::<String>()
+        let from = self.parse_table_ref()?;
+        let mut joins = Vec::new();
+        while let Some(kind) = self.parse_join_kind()? {
+            let table = self.parse_table_ref()?;
+            self.expect(Token::Helper(Helper::ON))?;
+            let on = self.parse_expr()?;
+            joins.push(Join { kind, table, on });
+        }
+
+        let where_clause = if let Some(Token::Command(Command::WHERE)) = self.peek() {
+            self.consume();
+            Some(self.parse_expr()?)
         } else {
-            return Err(SqlError::UnexpectedToken {
-                expected: Token::IDENT(vec![]),
-                found: Token::EOF,
-                pos: self.position,
-            });
+            None
         };
 
-        let where_clause = if let Some(Token::Command(Command::WHERE)) = self.peek() {
+        let mut group_by = Vec::new();
+        if let Some(Token::Command(Command::GROUP)) = self.peek() {
             self.consume();
-            Some(self.parse_condition()?)
+            self.expect(Token::Command(Command::BY))?;
+            group_by.push(self.parse_column_ref()?);
+            while let Some(Token::COMMA(',')) = self.peek() {
+                self.consume();
+                group_by.push(self.parse_column_ref()?);
+            }
+        }
+
+        let having = if let Some(Token::Command(Command::HAVING)) = self.peek() {
+            self.consume();
+            Some(self.parse_expr()?)
         } else {
             None
         };
 
-        self.expect(Token::SEMICOLON(';'))?;
-        Ok(ASTNode::Select {
+        let mut order_by = Vec::new();
+        if let Some(Token::Command(Command::ORDER)) = self.peek() {
+            self.consume();
+            self.expect(Token::Command(Command::BY))?;
+            order_by.push(self.parse_order_by_key()?);
+            while let Some(Token::COMMA(',')) = self.peek() {
+                self.consume();
+                order_by.push(self.parse_order_by_key()?);
+            }
+        }
+
+        let mut limit = None;
+        if let Some(Token::Command(Command::LIMIT)) = self.peek() {
+            self.consume();
+            limit = Some(self.parse_count()?);
+        }
+        let mut offset = None;
+        if let Some(Token::Command(Command::OFFSET)) = self.peek() {
+            self.consume();
+            offset = Some(self.parse_count()?);
+        }
+
+        Ok(ASTNode::Select(Select {
             columns,
-            table_name,
+            from,
+            joins,
             where_clause,
+            group_by,
+            having,
+            order_by,
+            limit,
+            offset,
+        }))
+    }
+
+    // table [[AS] alias]
+    fn parse_table_ref(&mut self) -> Result<TableRef> {
+        let name = self.parse_ident()?;
+        let alias = match self.peek() {
+            Some(Token::Helper(Helper::AS)) => {
+                self.consume();
+                Some(self.parse_ident()?)
+            }
+            Some(Token::IDENT(_)) => Some(self.parse_ident()?),
+            _ => None,
+        };
+        Ok(TableRef { name, alias })
+    }
+
+    // JOIN | INNER JOIN | LEFT [OUTER] JOIN; None if no join follows.
+    fn parse_join_kind(&mut self) -> Result<Option<JoinKind>> {
+        let kind = match self.peek() {
+            Some(Token::Helper(Helper::JOIN)) => JoinKind::Inner,
+            Some(Token::Helper(Helper::INNER)) => {
+                self.consume();
+                JoinKind::Inner
+            }
+            Some(Token::Helper(Helper::LEFT)) => {
+                self.consume();
+                if let Some(Token::Helper(Helper::OUTER)) = self.peek() {
+                    self.consume();
+                }
+                JoinKind::Left
+            }
+            _ => return Ok(None),
+        };
+        self.expect(Token::Helper(Helper::JOIN))?;
+        Ok(Some(kind))
+    }
+
+    // column | table.column
+    fn parse_column_ref(&mut self) -> Result<ColumnRef> {
+        let first = self.parse_ident()?;
+        if let Some(Token::DOT('.')) = self.peek() {
+            self.consume();
+            return Ok(ColumnRef {
+                table: Some(first),
+                column: self.parse_ident()?,
+            });
+        }
+        Ok(ColumnRef::new(first))
+    }
+
+    // expr [[AS] alias]
+    fn parse_select_item(&mut self) -> Result<SelectItem> {
+        let expr = self.parse_expr()?;
+        let alias = match self.peek() {
+            Some(Token::Helper(Helper::AS)) => {
+                self.consume();
+                Some(self.parse_ident()?)
+            }
+            Some(Token::IDENT(_)) => Some(self.parse_ident()?),
+            _ => None,
+        };
+        Ok(SelectItem::Expr { expr, alias })
+    }
+
+    // An aggregate call if the next tokens are a function name and '('.
+    // Function names are plain identifiers, so `count` etc. stay usable as
+    // column names.
+    fn parse_aggregate(&mut self) -> Result<Option<AggregateCall>> {
+        let func = match (self.peek(), self.tokens.get(self.position + 1)) {
+            (Some(Token::IDENT(name)), Some(Token::LPAREN('('))) => {
+                AggregateFunc::from_name(&name.iter().collect::<String>())
+            }
+            _ => None,
+        };
+        let Some(func) = func else {
+            return Ok(None);
+        };
+        self.consume();
+        self.consume();
+        let column = match self.peek() {
+            Some(Token::Operator(Operator::ASTERISK)) if func == AggregateFunc::Count => {
+                self.consume();
+                None
+            }
+            _ => Some(self.parse_column_ref()?),
+        };
+        self.expect(Token::RPAREN(')'))?;
+        Ok(Some(AggregateCall { func, column }))
+    }
+
+    // expr [ASC | DESC] [NULLS FIRST | NULLS LAST]
+    //
+    // NULLS, FIRST and LAST are matched as identifiers so they stay usable
+    // as column names.
+    fn parse_order_by_key(&mut self) -> Result<OrderByKey> {
+        let expr = self.parse_expr()?;
+        let descending = match self.peek() {
+            Some(Token::Command(Command::ASC)) => {
+                self.consume();
+                false
+            }
+            Some(Token::Command(Command::DESC)) => {
+                self.consume();
+                true
+            }
+            _ => false,
+        };
+        let mut nulls_first = None;
+        if let Some(Token::Helper(Helper::NULLS)) = self.peek() {
+            self.consume();
+            nulls_first = Some(match self.peek() {
+                Some(Token::Helper(Helper::FIRST)) => true,
+                Some(Token::Helper(Helper::LAST)) => false,
+                _ => return Err(self.unexpected(Token::Helper(Helper::FIRST))),
+            });
+            self.consume();
+        }
+        Ok(OrderByKey {
+            expr,
+            descending,
+            nulls_first,
         })
     }
+
+    // Non-negative integer for LIMIT / OFFSET.
+    fn parse_count(&mut self) -> Result<u64> {
+        let Some(Token::INT(val)) = self.peek() else {
+            return Err(self.unexpected(Token::INT(vec![])));
+        };
+        let literal: String = val.iter().collect();
+        let pos = self.pos();
+        self.consume();
+        literal
+            .parse::<u64>()
+            .map_err(|_| SqlError::InvalidNumber { literal, pos })
+    }
 }
 
 impl Parser {
@@ -339,45 +985,50 @@ impl Parser {
         self.expect(Token::Command(Command::INSERT))?;
         self.expect(Token::Command(Command::INTO))?;
 
-        let table_name = if let Some(Token::IDENT(name)) = self.consume() {
-            name.iter().collect::<String>()
+        let table_name = self.parse_ident()?;
+
+        let columns = if let Some(Token::LPAREN('(')) = self.peek() {
+            Some(self.parse_ident_list()?)
         } else {
-            return Err(SqlError::UnexpectedToken {
-                expected: Token::IDENT(vec![]),
-                found: Token::EOF,
-                pos: self.position,
-            });
+            None
         };
 
         self.expect(Token::Command(Command::VALUES))?;
 
+        let mut rows = vec![self.parse_insert_row()?];
+        while let Some(Token::COMMA(',')) = self.peek() {
+            self.consume();
+            rows.push(self.parse_insert_row()?);
+        }
+
+        Ok(ASTNode::Insert {
+            table_name,
+            columns,
+            rows,
+        })
+    }
+
+    // ( value, ... ) of one VALUES row
+    fn parse_insert_row(&mut self) -> Result<Vec<InsertValue>> {
         let mut values = Vec::new();
         self.expect(Token::LPAREN('('))?;
         while let Some(token) = self.peek() {
             match token {
-                Token::INT(val) => {
-                    let int_value =
-                        val.iter().collect::<String>().parse::<i64>().map_err(|_| {
-                            SqlError::InvalidNumber {
-                                literal: val.iter().collect(),
-                                pos: self.position,
-                            }
-                        })?;
-                    values.push(ASTValue::Int(int_value));
+                Token::Helper(Helper::DEFAULT) => {
+                    values.push(InsertValue::Default);
                     self.consume();
                 }
-                Token::SINGLEQUOTE(_) => {
-                    self.consume(); // Consume opening quote
-                    if let Some(Token::IDENT(val)) = self.consume() {
-                        values.push(ASTValue::String(val.iter().collect())); // Consume closing quote
-                    } else {
-                        return Err(SqlError::UnexpectedToken {
-                            expected: Token::IDENT(vec![]),
-                            found: Token::EOF,
-                            pos: self.position,
-                        });
-                    }
-                    self.expect(Token::SINGLEQUOTE('\''))?;
+                Token::INT(_)
+                | Token::FLOAT(_)
+                | Token::STRING(_)
+                | Token::Helper(Helper::NULL)
+                | Token::TRUE
+                | Token::FALSE
+                | Token::DataType(
+                    DataType::DATE | DataType::TIME | DataType::TIMESTAMP | DataType::DATETIME,
+                )
+                | Token::Operator(Operator::MINUS) => {
+                    values.push(InsertValue::Value(self.parse_value()?));
                 }
                 Token::COMMA(',') => {
                     self.consume();
@@ -387,17 +1038,12 @@ impl Parser {
                     break;
                 }
                 _ => {
-                    return Err(SqlError::UnexpectedToken {
-                        expected: Token::IDENT(vec![]),
-                        found: Token::EOF,
-                        pos: self.position,
-                    });
+                    return Err(self.unexpected(Token::IDENT(vec![])));
                 }
             }
         }
 
-        self.expect(Token::SEMICOLON(';'))?;
-        Ok(ASTNode::Insert { table_name, values })
+        Ok(values)
     }
 }
 
@@ -405,15 +1051,7 @@ impl Parser {
     pub fn parse_update(&mut self) -> Result<ASTNode> {
         self.expect(Token::Command(Command::UPDATE))?;
 
-        let table_name = if let Some(Token::IDENT(name)) = self.consume() {
-            name.iter().collect::<String>()
-        } else {
-            return Err(SqlError::UnexpectedToken {
-                expected: Token::IDENT(vec![]),
-                found: Token::EOF,
-                pos: self.position,
-            });
-        };
+        let table_name = self.parse_ident()?;
 
         self.expect(Token::Command(Command::SET))?;
 
@@ -421,7 +1059,7 @@ impl Parser {
         while let Some(Token::IDENT(col)) = self.consume() {
             let column_name = col.iter().collect::<String>();
             self.expect(Token::Operator(Operator::EQUALS))?;
-            let value = self.parse_value()?;
+            let value = self.parse_expr()?;
             assignments.push(Assignment {
                 column: column_name,
                 value,
@@ -435,11 +1073,10 @@ impl Parser {
 
         let where_clause = if let Some(Token::Command(Command::WHERE)) = self.peek() {
             self.consume();
-            Some(self.parse_condition()?)
+            Some(self.parse_expr()?)
         } else {
             None
         };
-        self.expect(Token::SEMICOLON(';'))?;
 
         Ok(ASTNode::Update {
             table_name,
@@ -454,19 +1091,11 @@ impl Parser {
         self.expect(Token::Command(Command::DELETE))?;
         self.expect(Token::Command(Command::FROM))?;
 
-        let table_name = if let Some(Token::IDENT(name)) = self.consume() {
-            name.iter().collect::<String>()
-        } else {
-            return Err(SqlError::UnexpectedToken {
-                expected: Token::IDENT(vec![]),
-                found: Token::EOF,
-                pos: self.position,
-            });
-        };
+        let table_name = self.parse_ident()?;
 
         let where_clause = if let Some(Token::Command(Command::WHERE)) = self.peek() {
             self.consume();
-            Some(self.parse_condition()?)
+            Some(self.parse_expr()?)
         } else {
             None
         };
@@ -482,15 +1111,7 @@ impl Parser {
     pub fn parse_use(&mut self) -> Result<ASTNode> {
         self.expect(Token::Command(Command::USE))?;
 
-        let database_name = if let Some(Token::IDENT(name)) = self.consume() {
-            name.iter().collect::<String>()
-        } else {
-            return Err(SqlError::UnexpectedToken {
-                expected: Token::IDENT(vec![]),
-                found: Token::EOF,
-                pos: self.position,
-            });
-        };
+        let database_name = self.parse_ident()?;
 
         Ok(ASTNode::USE { database_name })
     }
@@ -507,9 +1128,345 @@ impl Parser {
             Some(Token::Command(Command::DATABASES)) => ASTNode::Show {
                 show_type: ShowType::DATABASES,
             },
-            _ => return Err(SqlError::ShowNotSupported),
+            _ => {
+                return Err(SqlError::ShowNotSupported {
+                    pos: self.pos_at(self.position - 1),
+                });
+            }
         };
-        self.expect(Token::SEMICOLON(';'))?;
         Ok(show_ast)
     }
 }
+
+impl Parser {
+    // BEGIN [TRANSACTION] | COMMIT [TRANSACTION] | ROLLBACK [TRANSACTION]
+    pub fn parse_transaction(&mut self) -> Result<ASTNode> {
+        let ast = match self.consume() {
+            Some(Token::Command(Command::BEGIN)) => ASTNode::Begin,
+            Some(Token::Command(Command::COMMIT)) => ASTNode::Commit,
+            Some(Token::Command(Command::ROLLBACK)) => ASTNode::Rollback,
+            _ => {
+                return Err(SqlError::UnsupportedCommand {
+                    pos: self.pos_at(self.position - 1),
+                });
+            }
+        };
+        if let Some(Token::Command(Command::TRANSACTION)) = self.peek() {
+            self.consume();
+        }
+        Ok(ast)
+    }
+}
+
+impl Parser {
+    fn parse_ident(&mut self) -> Result<String> {
+        let Some(Token::IDENT(name)) = self.peek() else {
+            return Err(self.unexpected(Token::IDENT(vec![])));
+        };
+        let name = name.iter().collect();
+        self.consume();
+        Ok(name)
+    }
+
+    // CREATE INDEX <name> ON <table> (<column>)
+    pub fn parse_create_index(&mut self) -> Result<ASTNode> {
+        self.expect(Token::Command(Command::CREATE))?;
+        self.expect(Token::Command(Command::INDEX))?;
+        let index_name = self.parse_ident()?;
+        self.expect(Token::Helper(Helper::ON))?;
+        let table_name = self.parse_ident()?;
+        self.expect(Token::LPAREN('('))?;
+        let column_name = self.parse_ident()?;
+        self.expect(Token::RPAREN(')'))?;
+        Ok(ASTNode::CreateIndex {
+            index_name,
+            table_name,
+            column_name,
+        })
+    }
+
+    // DROP INDEX <name>
+    pub fn parse_drop_index(&mut self) -> Result<ASTNode> {
+        self.expect(Token::Helper(Helper::DROP))?;
+        self.expect(Token::Command(Command::INDEX))?;
+        let index_name = self.parse_ident()?;
+        Ok(ASTNode::DropIndex { index_name })
+    }
+
+    // DROP TABLE [IF EXISTS] <name>
+    pub fn parse_drop_table(&mut self) -> Result<ASTNode> {
+        self.expect(Token::Helper(Helper::DROP))?;
+        self.expect(Token::Command(Command::TABLE))?;
+        let if_exists = self.parse_if_exists()?;
+        let table_name = self.parse_ident()?;
+        Ok(ASTNode::DropTable {
+            table_name,
+            if_exists,
+        })
+    }
+
+    // DROP DATABASE [IF EXISTS] <name>
+    pub fn parse_drop_database(&mut self) -> Result<ASTNode> {
+        self.expect(Token::Helper(Helper::DROP))?;
+        self.expect(Token::Command(Command::DATABASE))?;
+        let if_exists = self.parse_if_exists()?;
+        let database_name = self.parse_ident()?;
+        Ok(ASTNode::DropDatabase {
+            database_name,
+            if_exists,
+        })
+    }
+
+    // TRUNCATE [TABLE] <name>
+    pub fn parse_truncate(&mut self) -> Result<ASTNode> {
+        self.expect(Token::Command(Command::TRUNCATE))?;
+        if let Some(Token::Command(Command::TABLE)) = self.peek() {
+            self.consume();
+        }
+        let table_name = self.parse_ident()?;
+        Ok(ASTNode::Truncate { table_name })
+    }
+
+    // An optional `IF EXISTS`.
+    fn parse_if_exists(&mut self) -> Result<bool> {
+        if self.peek() != Some(&Token::Helper(Helper::IF)) {
+            return Ok(false);
+        }
+        self.consume();
+        self.expect(Token::Helper(Helper::EXISTS))?;
+        Ok(true)
+    }
+
+    // An optional `IF NOT EXISTS`.
+    fn parse_if_not_exists(&mut self) -> Result<bool> {
+        if self.peek() != Some(&Token::Helper(Helper::IF)) {
+            return Ok(false);
+        }
+        self.consume();
+        self.expect(Token::Helper(Helper::NOT))?;
+        self.expect(Token::Helper(Helper::EXISTS))?;
+        Ok(true)
+    }
+
+    // ALTER TABLE <table> ADD [COLUMN] <column definition>
+    //                   | DROP [COLUMN] <column>
+    //                   | RENAME [COLUMN] <column> TO <new name>
+    pub fn parse_alter_table(&mut self) -> Result<ASTNode> {
+        self.expect(Token::Helper(Helper::ALTER))?;
+        self.expect(Token::Command(Command::TABLE))?;
+        let table_name = self.parse_ident()?;
+        let action = match self.peek() {
+            Some(Token::Helper(Helper::ADD)) => {
+                self.consume();
+                self.skip_column_keyword();
+                AlterAction::AddColumn(self.parse_column_definition()?)
+            }
+            Some(Token::Helper(Helper::DROP)) => {
+                self.consume();
+                self.skip_column_keyword();
+                AlterAction::DropColumn(self.parse_ident()?)
+            }
+            Some(Token::Helper(Helper::RENAME)) => {
+                self.consume();
+                self.skip_column_keyword();
+                let from = self.parse_ident()?;
+                self.expect(Token::Helper(Helper::TO))?;
+                let to = self.parse_ident()?;
+                AlterAction::RenameColumn { from, to }
+            }
+            _ => return Err(SqlError::UnsupportedCommand { pos: self.pos() }),
+        };
+        Ok(ASTNode::AlterTable { table_name, action })
+    }
+
+    fn skip_column_keyword(&mut self) {
+        if let Some(Token::Helper(Helper::COLUMN)) = self.peek() {
+            self.consume();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::get_tokens;
+
+    fn expr(text: &str) -> Expr {
+        let (tokens, positions) = get_tokens(text).unwrap();
+        let mut parser = Parser::new(tokens, positions);
+        let expr = parser.parse_expr().unwrap();
+        assert!(parser.at_end(), "{} was not parsed to the end", text);
+        expr
+    }
+
+    fn col(name: &str) -> Expr {
+        Expr::Column(ColumnRef::new(name))
+    }
+
+    fn int(i: i64) -> Expr {
+        Expr::Value(ASTValue::Int(i))
+    }
+
+    fn bin(op: Operator, left: Expr, right: Expr) -> Expr {
+        Expr::binary(op, left, right)
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        use Operator::*;
+        assert_eq!(
+            expr("1 + 2 * 3"),
+            bin(PLUS, int(1), bin(ASTERISK, int(2), int(3)))
+        );
+        assert_eq!(
+            expr("(1 + 2) * 3"),
+            bin(ASTERISK, bin(PLUS, int(1), int(2)), int(3))
+        );
+        assert_eq!(
+            expr("a - b - c / d / e"),
+            bin(
+                MINUS,
+                bin(MINUS, col("a"), col("b")),
+                bin(DIVIDE, bin(DIVIDE, col("c"), col("d")), col("e"))
+            )
+        );
+        assert_eq!(
+            expr("a = 1 OR b < 2 AND NOT c >= 3"),
+            bin(
+                OR,
+                bin(EQUALS, col("a"), int(1)),
+                bin(
+                    AND,
+                    bin(LT, col("b"), int(2)),
+                    Expr::Not(Box::new(bin(GTorE, col("c"), int(3))))
+                )
+            )
+        );
+        assert_eq!(
+            expr("-a * 2 <> b"),
+            bin(
+                NE,
+                bin(ASTERISK, Expr::Negate(Box::new(col("a"))), int(2)),
+                col("b")
+            )
+        );
+    }
+
+    #[test]
+    fn test_display_keeps_grouping() {
+        for text in [
+            "(a + b) * c",
+            "a - (b - c)",
+            "NOT (a = 1 OR b = 2) AND c",
+            "-(a + 1) / 2",
+            "(a OR b) AND (c OR d)",
+        ] {
+            let parsed = expr(text);
+            assert_eq!(expr(&parsed.to_string()), parsed, "{}", text);
+        }
+        assert_eq!(expr("((a)) + (b * c)").to_string(), "a + b * c");
+    }
+
+    #[test]
+    fn test_is_null_and_null_functions() {
+        use Operator::*;
+        let is_null = |e: Expr, negated: bool| Expr::IsNull {
+            expr: Box::new(e),
+            negated,
+        };
+        assert_eq!(
+            expr("a IS NULL OR b + 1 IS NOT NULL"),
+            bin(
+                OR,
+                is_null(col("a"), false),
+                is_null(bin(PLUS, col("b"), int(1)), true)
+            )
+        );
+        assert_eq!(
+            expr("NOT a IS NULL"),
+            Expr::Not(Box::new(is_null(col("a"), false)))
+        );
+        assert_eq!(
+            expr("a = NULL"),
+            bin(EQUALS, col("a"), Expr::Value(ASTValue::Null))
+        );
+        assert_eq!(
+            expr("COALESCE(a, NULLIF(b, 0), 1)"),
+            Expr::Function {
+                func: ScalarFunc::Coalesce,
+                args: vec![
+                    col("a"),
+                    Expr::Function {
+                        func: ScalarFunc::NullIf,
+                        args: vec![col("b"), int(0)],
+                    },
+                    int(1),
+                ],
+            }
+        );
+        let parsed = expr("(a + 1 IS NULL) = FALSE");
+        assert_eq!(expr(&parsed.to_string()), parsed);
+
+        let (tokens, positions) = get_tokens("a IS 1").unwrap();
+        assert!(Parser::new(tokens, positions).parse_expr().is_err());
+    }
+
+    #[test]
+    fn test_negative_and_exponent_literals() {
+        use Operator::*;
+        let float = |f: f64| Expr::Value(ASTValue::Float(f));
+        let decimal = |s: &str| Expr::Value(ASTValue::Decimal(s.parse().unwrap()));
+        assert_eq!(expr("-42"), int(-42));
+        assert_eq!(expr("-9223372036854775808"), int(i64::MIN));
+        assert_eq!(expr("1.5e3"), float(1500.0));
+        assert_eq!(expr("-2.5E-2"), float(-0.025));
+        assert_eq!(expr(".5"), decimal("0.5"));
+        assert_eq!(expr("-19.99"), decimal("-19.99"));
+        // Only a leading `-` belongs to the literal.
+        assert_eq!(expr("a -1"), bin(MINUS, col("a"), int(1)));
+        assert_eq!(expr("2 * -3"), bin(ASTERISK, int(2), int(-3)));
+        assert_eq!(expr("- -1"), Expr::Negate(Box::new(int(-1))));
+
+        let (tokens, positions) = get_tokens("9223372036854775808").unwrap();
+        assert!(matches!(
+            Parser::new(tokens, positions).parse_expr(),
+            Err(SqlError::InvalidNumber { .. })
+        ));
+    }
+
+    fn statement(text: &str) -> ASTNode {
+        let (tokens, positions) = get_tokens(text).unwrap();
+        Parser::new(tokens, positions).parse_statement().unwrap()
+    }
+
+    #[test]
+    fn test_quoted_names_are_never_keywords() {
+        assert!(matches!(
+            statement("CREATE TABLE \"if\" (\"to\" INTEGER);"),
+            ASTNode::CreateTable { table_name, if_not_exists: false, columns, .. }
+                if table_name == "if" && columns[0].column_name == "to"
+        ));
+        assert!(matches!(
+            statement("CREATE TABLE IF NOT EXISTS \"exists\" (id INTEGER);"),
+            ASTNode::CreateTable { table_name, if_not_exists: true, .. } if table_name == "exists"
+        ));
+        assert!(matches!(
+            statement("DROP TABLE \"if\";"),
+            ASTNode::DropTable { table_name, if_exists: false } if table_name == "if"
+        ));
+        assert!(matches!(
+            statement("TRUNCATE \"truncate\";"),
+            ASTNode::Truncate { table_name } if table_name == "truncate"
+        ));
+        assert!(matches!(
+            statement("ALTER TABLE t RENAME \"to\" TO \"rename\";"),
+            ASTNode::AlterTable {
+                action: AlterAction::RenameColumn { from, to },
+                ..
+            } if from == "to" && to == "rename"
+        ));
+        // A quoted name where the keyword belongs is just a name.
+        let (tokens, positions) = get_tokens("\"truncate\" t;").unwrap();
+        assert!(Parser::new(tokens, positions).parse_statement().is_err());
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+// Calendar values as stored, all without a time zone:
+// DATE is days since 1970-01-01, TIME microseconds since midnight, and
+// TIMESTAMP (DATETIME) microseconds since 1970-01-01 00:00:00.
+
+pub const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+const EPOCH: NaiveDate = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+
+/// `YYYY-MM-DD`.
+pub fn parse_date(s: &str) -> Option<i32> {
+    let date = NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok()?;
+    i32::try_from(date.signed_duration_since(EPOCH).num_days()).ok()
+}
+
+/// `HH:MM[:SS[.ffffff]]`.
+pub fn parse_time(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let time = NaiveTime::parse_from_str(s, "%H:%M:%S%.f")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+        .ok()?;
+    Some(
+        i64::from(time.num_seconds_from_midnight()) * 1_000_000
+            + i64::from(time.nanosecond() / 1_000),
+    )
+}
+
+/// `YYYY-MM-DD[( |T)HH:MM[:SS[.ffffff]]]`; a bare date is its midnight.
+pub fn parse_timestamp(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if let Some(days) = parse_date(s) {
+        return Some(i64::from(days) * MICROS_PER_DAY);
+    }
+    [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+    .map(|dt| dt.and_utc().timestamp_micros())
+}
+
+pub fn format_date(days: i32) -> String {
+    EPOCH
+        .checked_add_signed(chrono::Duration::days(i64::from(days)))
+        .map_or_else(|| format!("<invalid date {}>", days), |d| d.to_string())
+}
+
+pub fn format_time(micros: i64) -> String {
+    let secs = micros.div_euclid(1_000_000);
+    let nanos = micros.rem_euclid(1_000_000) * 1_000;
+    u32::try_from(secs)
+        .ok()
+        .and_then(|s| NaiveTime::from_num_seconds_from_midnight_opt(s, nanos as u32))
+        .map_or_else(
+            || format!("<invalid time {}>", micros),
+            |t| t.format("%H:%M:%S%.f").to_string(),
+        )
+}
+
+pub fn format_timestamp(micros: i64) -> String {
+    DateTime::from_timestamp_micros(micros).map_or_else(
+        || format!("<invalid timestamp {}>", micros),
+        |dt| dt.naive_utc().format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+    )
+}
+
+/// The current local date and time, as a TIMESTAMP.
+pub fn now() -> i64 {
+    Local::now().naive_local().and_utc().timestamp_micros()
+}
+
+/// A part of a date or time, as named in `EXTRACT(field FROM x)` and
+/// `DATE_TRUNC('field', x)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    Year,
+    Quarter,
+    Month,
+    /// ISO week; weeks start on Monday.
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    /// Day of the week, 0 for Sunday. EXTRACT only.
+    Dow,
+    /// Day of the year, from 1. EXTRACT only.
+    Doy,
+    /// Whole seconds since 1970-01-01 00:00:00, or since midnight for a
+    /// TIME. EXTRACT only.
+    Epoch,
+}
+
+impl DateField {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "year" => DateField::Year,
+            "quarter" => DateField::Quarter,
+            "month" => DateField::Month,
+            "week" => DateField::Week,
+            "day" => DateField::Day,
+            "hour" => DateField::Hour,
+            "minute" => DateField::Minute,
+            "second" => DateField::Second,
+            "dow" => DateField::Dow,
+            "doy" => DateField::Doy,
+            "epoch" => DateField::Epoch,
+            _ => return None,
+        })
+    }
+
+    /// Whether DATE_TRUNC can truncate to this field.
+    pub fn is_unit(&self) -> bool {
+        !matches!(self, DateField::Dow | DateField::Doy | DateField::Epoch)
+    }
+
+    /// Whether the field exists in a TIME of day.
+    pub fn in_time(&self) -> bool {
+        matches!(
+            self,
+            DateField::Hour | DateField::Minute | DateField::Second | DateField::Epoch
+        )
+    }
+}
+
+impl fmt::Display for DateField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            DateField::Year => "YEAR",
+            DateField::Quarter => "QUARTER",
+            DateField::Month => "MONTH",
+            DateField::Week => "WEEK",
+            DateField::Day => "DAY",
+            DateField::Hour => "HOUR",
+            DateField::Minute => "MINUTE",
+            DateField::Second => "SECOND",
+            DateField::Dow => "DOW",
+            DateField::Doy => "DOY",
+            DateField::Epoch => "EPOCH",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// `field` of a TIMESTAMP; seconds are whole. A TIME is the timestamp of
+/// its time of day on 1970-01-01.
+pub fn extract(field: DateField, micros: i64) -> Option<i64> {
+    let dt = DateTime::from_timestamp_micros(micros)?.naive_utc();
+    Some(match field {
+        DateField::Year => dt.year().into(),
+        DateField::Quarter => (dt.month0() / 3 + 1).into(),
+        DateField::Month => dt.month().into(),
+        DateField::Week => dt.iso_week().week().into(),
+        DateField::Day => dt.day().into(),
+        DateField::Hour => dt.hour().into(),
+        DateField::Minute => dt.minute().into(),
+        DateField::Second => dt.second().into(),
+        DateField::Dow => dt.weekday().num_days_from_sunday().into(),
+        DateField::Doy => dt.ordinal().into(),
+        DateField::Epoch => micros.div_euclid(1_000_000),
+    })
+}
+
+/// A TIMESTAMP truncated to the start of its `field`; None if `field` is
+/// not a unit.
+pub fn trunc(field: DateField, micros: i64) -> Option<i64> {
+    let dt = DateTime::from_timestamp_micros(micros)?.naive_utc();
+    let date = dt.date();
+    let start = match field {
+        DateField::Year => date.with_ordinal(1)?.and_time(NaiveTime::MIN),
+        DateField::Quarter => NaiveDate::from_ymd_opt(date.year(), date.month0() / 3 * 3 + 1, 1)?
+            .and_time(NaiveTime::MIN),
+        DateField::Month => date.with_day(1)?.and_time(NaiveTime::MIN),
+        DateField::Week => date
+            .week(chrono::Weekday::Mon)
+            .first_day()
+            .and_time(NaiveTime::MIN),
+        DateField::Day => date.and_time(NaiveTime::MIN),
+        DateField::Hour => date.and_hms_opt(dt.hour(), 0, 0)?,
+        DateField::Minute => date.and_hms_opt(dt.hour(), dt.minute(), 0)?,
+        DateField::Second => date.and_hms_opt(dt.hour(), dt.minute(), dt.second())?,
+        DateField::Dow | DateField::Doy | DateField::Epoch => return None,
+    };
+    Some(start.and_utc().timestamp_micros())
+}
@@ -33,6 +33,8 @@ fn match_command(keyword: &str) -> Result<Command, String> {
         "select" => Ok(Command::SELECT),
         "from" => Ok(Command::FROM),
         "where" => Ok(Command::WHERE),
+        "group" => Ok(Command::GROUP),
+        "having" => Ok(Command::HAVING),
         "order" => Ok(Command::ORDER),
         "asc" => Ok(Command::ASC),
         "desc" => Ok(Command::DESC),
@@ -48,6 +50,13 @@ fn match_command(keyword: &str) -> Result<Command, String> {
         "use" => Ok(Command::USE),
         "show" => Ok(Command::SHOW),
         "set" => Ok(Command::SET),
+        "begin" => Ok(Command::BEGIN),
+        "commit" => Ok(Command::COMMIT),
+        "rollback" => Ok(Command::ROLLBACK),
+        "transaction" => Ok(Command::TRANSACTION),
+        "index" => Ok(Command::INDEX),
+        "explain" => Ok(Command::EXPLAIN),
+        "truncate" => Ok(Command::TRUNCATE),
         _ => Err(String::from("Not a command")),
     }
 }
@@ -78,6 +87,21 @@ fn match_helper(keyword: &str) -> Result<Helper, String> {
         "adds" => Ok(Helper::ADDS),
         "default" => Ok(Helper::DEFAULT),
         "autoincrement" => Ok(Helper::AUTOINCREMENT),
+        "cascade" => Ok(Helper::CASCADE),
+        "restrict" => Ok(Helper::RESTRICT),
+        "join" => Ok(Helper::JOIN),
+        "inner" => Ok(Helper::INNER),
+        "left" => Ok(Helper::LEFT),
+        "outer" => Ok(Helper::OUTER),
+        "as" => Ok(Helper::AS),
+        "is" => Ok(Helper::IS),
+        "if" => Ok(Helper::IF),
+        "exists" => Ok(Helper::EXISTS),
+        "rename" => Ok(Helper::RENAME),
+        "to" => Ok(Helper::TO),
+        "nulls" => Ok(Helper::NULLS),
+        "first" => Ok(Helper::FIRST),
+        "last" => Ok(Helper::LAST),
         _ => Err(String::from("Not a helper")),
     }
 }
@@ -95,10 +119,10 @@ fn match_data_type(keyword: &str) -> Result<DataType, String> {
         "char" => Ok(DataType::CHAR),
         "blob" => Ok(DataType::BLOB),
         "json" => Ok(DataType::JSON),
-        "decimal" => Ok(DataType::DECIMAL),
+        "decimal" => Ok(DataType::DECIMAL(None)),
         "double" => Ok(DataType::DOUBLE),
         "real" => Ok(DataType::REAL),
-        "numeric" => Ok(DataType::NUMERIC),
+        "numeric" => Ok(DataType::NUMERIC(None)),
         "tinyint" => Ok(DataType::TINYINT),
         "smallint" => Ok(DataType::SMALLINT),
         "mediumint" => Ok(DataType::MEDIUMINT),
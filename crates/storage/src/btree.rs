@@ -0,0 +1,420 @@
+use sql::{ast::ASTValue, decimal::Decimal};
+
+use crate::{page::PAGE_SIZE, types::RowId};
+
+// B+Tree index pages. Every page of an index file is PAGE_SIZE bytes; page 0
+// holds IndexMeta, all others hold a Node.
+//
+// Meta page layout (LE):
+// magic[4] = b"BTI0"
+// version u32 = 1
+// root u32             // page id of the root node
+// page_count u32       // pages allocated in the file (including meta)
+// flags u8             // bit 0: unique
+// name_len u16 | name bytes
+// column_len u16 | column bytes
+//
+// Node page layout (LE):
+// magic[4] = b"BTN0"
+// kind u8              // 1 = leaf, 2 = internal
+// reserved u8
+// key_count u16
+// next u32             // leaf: right sibling page id (0 = none)
+// first_child u32      // internal: child holding keys < keys[0]
+// entries:
+//   leaf:     key_len u16 | key bytes
+//   internal: key_len u16 | key bytes | child u32 (keys >= this key)
+//
+// Index keys are `encode_value(v) ++ row_id (BE)`, so every entry is unique
+// and plain byte comparison gives SQL order for values of one type.
+const META_MAGIC: [u8; 4] = *b"BTI0";
+const META_VERSION: u32 = 1;
+const META_FLAG_UNIQUE: u8 = 0x01;
+const NODE_MAGIC: [u8; 4] = *b"BTN0";
+pub const NODE_HEADER_LEN: usize = 16;
+
+const KIND_LEAF: u8 = 1;
+const KIND_INTERNAL: u8 = 2;
+
+/// Largest encoded key accepted, so that a split always leaves room on both sides.
+pub const MAX_KEY_LEN: usize = (PAGE_SIZE - NODE_HEADER_LEN) / 4 - 6;
+
+const TAG_BOOL: u8 = 0x01;
+const TAG_INT: u8 = 0x02;
+const TAG_FLOAT: u8 = 0x03;
+const TAG_STRING: u8 = 0x04;
+const TAG_DECIMAL: u8 = 0x05;
+const TAG_DATE: u8 = 0x06;
+const TAG_TIME: u8 = 0x07;
+const TAG_TIMESTAMP: u8 = 0x08;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexMeta {
+    pub name: String,
+    pub column: String,
+    pub root: u32,
+    pub page_count: u32,
+    /// Backs a PRIMARY KEY / UNIQUE column: a value may occur only once.
+    pub unique: bool,
+}
+
+impl IndexMeta {
+    pub fn to_page(&self) -> [u8; PAGE_SIZE] {
+        let mut buf = [0u8; PAGE_SIZE];
+        let mut out = Vec::with_capacity(32 + self.name.len() + self.column.len());
+        out.extend_from_slice(&META_MAGIC);
+        out.extend_from_slice(&META_VERSION.to_le_bytes());
+        out.extend_from_slice(&self.root.to_le_bytes());
+        out.extend_from_slice(&self.page_count.to_le_bytes());
+        out.push(if self.unique { META_FLAG_UNIQUE } else { 0 });
+        for s in [&self.name, &self.column] {
+            let b = s.as_bytes();
+            let len = u16::try_from(b.len()).unwrap_or(u16::MAX);
+            out.extend_from_slice(&len.to_le_bytes());
+            out.extend_from_slice(&b[..len as usize]);
+        }
+        buf[..out.len()].copy_from_slice(&out);
+        buf
+    }
+
+    pub fn from_page(buf: &[u8]) -> Result<Self, String> {
+        if buf.len() < 17 || buf[0..4] != META_MAGIC {
+            return Err("bad index meta magic".into());
+        }
+        let version = u32::from_le_bytes(buf[4..8].try_into().unwrap());
+        if version != META_VERSION {
+            return Err(format!("unsupported index version {}", version));
+        }
+        let root = u32::from_le_bytes(buf[8..12].try_into().unwrap());
+        let page_count = u32::from_le_bytes(buf[12..16].try_into().unwrap());
+        let unique = buf[16] & META_FLAG_UNIQUE != 0;
+        let mut p = &buf[17..];
+        let mut strings = Vec::with_capacity(2);
+        for _ in 0..2 {
+            if p.len() < 2 {
+                return Err("index meta truncated".into());
+            }
+            let len = u16::from_le_bytes(p[..2].try_into().unwrap()) as usize;
+            if p.len() < 2 + len {
+                return Err("index meta truncated".into());
+            }
+            let s = std::str::from_utf8(&p[2..2 + len])
+                .map_err(|_| "invalid utf-8 in index meta".to_string())?;
+            strings.push(s.to_string());
+            p = &p[2 + len..];
+        }
+        let column = strings.pop().unwrap();
+        let name = strings.pop().unwrap();
+        Ok(Self {
+            name,
+            column,
+            root,
+            page_count,
+            unique,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub leaf: bool,
+    pub keys: Vec<Vec<u8>>,
+    // Internal nodes only: keys.len() + 1 children; children[0] is first_child.
+    pub children: Vec<u32>,
+    pub next: u32,
+}
+
+impl Node {
+    pub fn new_leaf() -> Self {
+        Self {
+            leaf: true,
+            keys: Vec::new(),
+            children: Vec::new(),
+            next: 0,
+        }
+    }
+
+    pub fn new_internal(first_child: u32) -> Self {
+        Self {
+            leaf: false,
+            keys: Vec::new(),
+            children: vec![first_child],
+            next: 0,
+        }
+    }
+
+    pub fn encoded_len(&self) -> usize {
+        let per_entry = if self.leaf { 2 } else { 2 + 4 };
+        NODE_HEADER_LEN + self.keys.iter().map(|k| k.len() + per_entry).sum::<usize>()
+    }
+
+    pub fn fits(&self) -> bool {
+        self.encoded_len() <= PAGE_SIZE
+    }
+
+    pub fn to_page(&self) -> Result<[u8; PAGE_SIZE], String> {
+        if !self.fits() {
+            return Err("btree node overflow".into());
+        }
+        let mut buf = [0u8; PAGE_SIZE];
+        buf[0..4].copy_from_slice(&NODE_MAGIC);
+        buf[4] = if self.leaf { KIND_LEAF } else { KIND_INTERNAL };
+        let count = u16::try_from(self.keys.len()).map_err(|_| "too many keys")?;
+        buf[6..8].copy_from_slice(&count.to_le_bytes());
+        buf[8..12].copy_from_slice(&self.next.to_le_bytes());
+        let first_child = if self.leaf { 0 } else { self.children[0] };
+        buf[12..16].copy_from_slice(&first_child.to_le_bytes());
+
+        let mut off = NODE_HEADER_LEN;
+        for (i, key) in self.keys.iter().enumerate() {
+            buf[off..off + 2].copy_from_slice(&(key.len() as u16).to_le_bytes());
+            off += 2;
+            buf[off..off + key.len()].copy_from_slice(key);
+            off += key.len();
+            if !self.leaf {
+                buf[off..off + 4].copy_from_slice(&self.children[i + 1].to_le_bytes());
+                off += 4;
+            }
+        }
+        Ok(buf)
+    }
+
+    pub fn from_page(buf: &[u8]) -> Result<Self, String> {
+        if buf.len() < NODE_HEADER_LEN || buf[0..4] != NODE_MAGIC {
+            return Err("bad btree node magic".into());
+        }
+        let leaf = match buf[4] {
+            KIND_LEAF => true,
+            KIND_INTERNAL => false,
+            k => return Err(format!("unknown btree node kind {}", k)),
+        };
+        let count = u16::from_le_bytes(buf[6..8].try_into().unwrap()) as usize;
+        let next = u32::from_le_bytes(buf[8..12].try_into().unwrap());
+        let first_child = u32::from_le_bytes(buf[12..16].try_into().unwrap());
+
+        let mut keys = Vec::with_capacity(count);
+        let mut children = Vec::with_capacity(if leaf { 0 } else { count + 1 });
+        if !leaf {
+            children.push(first_child);
+        }
+        let mut off = NODE_HEADER_LEN;
+        for _ in 0..count {
+            if off + 2 > buf.len() {
+                return Err("btree node truncated".into());
+            }
+            let len = u16::from_le_bytes(buf[off..off + 2].try_into().unwrap()) as usize;
+            off += 2;
+            if off + len > buf.len() {
+                return Err("btree node truncated".into());
+            }
+            keys.push(buf[off..off + len].to_vec());
+            off += len;
+            if !leaf {
+                if off + 4 > buf.len() {
+                    return Err("btree node truncated".into());
+                }
+                children.push(u32::from_le_bytes(buf[off..off + 4].try_into().unwrap()));
+                off += 4;
+            }
+        }
+        Ok(Self {
+            leaf,
+            keys,
+            children,
+            next,
+        })
+    }
+
+    /// Index of the child that may contain `key` (internal nodes only).
+    pub fn child_for(&self, key: &[u8]) -> usize {
+        self.keys.partition_point(|k| k.as_slice() <= key)
+    }
+}
+
+/// Order-preserving encoding of a non-NULL value; returns None for NULL,
+/// which is never indexed.
+pub fn encode_value(v: &ASTValue) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(16);
+    match v {
+        ASTValue::Null => return None,
+        ASTValue::Boolean(b) => {
+            out.push(TAG_BOOL);
+            out.push(*b as u8);
+        }
+        ASTValue::Int(i) => {
+            out.push(TAG_INT);
+            out.extend_from_slice(&((*i as u64) ^ (1 << 63)).to_be_bytes());
+        }
+        ASTValue::Float(f) => {
+            out.push(TAG_FLOAT);
+            let bits = f.to_bits();
+            let ordered = if bits >> 63 == 1 {
+                !bits
+            } else {
+                bits ^ (1 << 63)
+            };
+            out.extend_from_slice(&ordered.to_be_bytes());
+        }
+        ASTValue::Decimal(d) => {
+            out.push(TAG_DECIMAL);
+            encode_decimal(&mut out, d);
+        }
+        ASTValue::Date(d) => {
+            out.push(TAG_DATE);
+            out.extend_from_slice(&((*d as u32) ^ (1 << 31)).to_be_bytes());
+        }
+        ASTValue::Time(t) => {
+            out.push(TAG_TIME);
+            out.extend_from_slice(&((*t as u64) ^ (1 << 63)).to_be_bytes());
+        }
+        ASTValue::Timestamp(t) => {
+            out.push(TAG_TIMESTAMP);
+            out.extend_from_slice(&((*t as u64) ^ (1 << 63)).to_be_bytes());
+        }
+        ASTValue::String(s) => {
+            out.push(TAG_STRING);
+            // 0x00 is escaped as 0x00 0xFF and the value ends with 0x00 0x00,
+            // keeping the encoding prefix-free.
+            for &b in s.as_bytes() {
+                out.push(b);
+                if b == 0 {
+                    out.push(0xFF);
+                }
+            }
+            out.extend_from_slice(&[0, 0]);
+        }
+    }
+    Some(out)
+}
+
+// A decimal as sign, exponent and significant digits, so that equal values
+// of different scales (1.5, 1.50) encode alike. With the digits d1 d2 ... dn
+// (no trailing zeros) and the value 0.d1d2...dn * 10^e, a positive value is
+// 0x02, e (biased, big-endian), each digit + 1, then 0x00; a negative one is
+// 0x00 followed by the complement of the same bytes; zero is 0x01.
+fn encode_decimal(out: &mut Vec<u8>, d: &Decimal) {
+    if d.mantissa() == 0 {
+        out.push(0x01);
+        return;
+    }
+    let all = d.mantissa().unsigned_abs().to_string();
+    let exponent = all.len() as i16 - i16::from(d.scale());
+    let mut body = ((exponent as u16) ^ (1 << 15)).to_be_bytes().to_vec();
+    body.extend(all.trim_end_matches('0').bytes().map(|c| c - b'0' + 1));
+    body.push(0x00);
+    if d.mantissa() > 0 {
+        out.push(0x02);
+        out.extend_from_slice(&body);
+    } else {
+        out.push(0x00);
+        out.extend(body.iter().map(|b| !b));
+    }
+}
+
+/// Full index key for `v` stored at `row_id`.
+pub fn index_key(v: &ASTValue, row_id: RowId) -> Option<Vec<u8>> {
+    let mut key = encode_value(v)?;
+    key.extend_from_slice(&row_id.to_be_bytes());
+    Some(key)
+}
+
+/// Smallest and largest full keys holding value `v`, for any row id.
+pub fn value_key_bounds(v: &ASTValue) -> Option<(Vec<u8>, Vec<u8>)> {
+    let enc = encode_value(v)?;
+    let mut lo = enc.clone();
+    lo.extend_from_slice(&[0u8; 8]);
+    let mut hi = enc;
+    hi.extend_from_slice(&[0xFFu8; 8]);
+    Some((lo, hi))
+}
+
+pub fn key_row_id(key: &[u8]) -> RowId {
+    let tail = &key[key.len() - 8..];
+    u64::from_be_bytes(tail.try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_int_encoding_preserves_order() {
+        let vals = [i64::MIN, -5, -1, 0, 1, 42, i64::MAX];
+        let enc: Vec<_> = vals
+            .iter()
+            .map(|v| encode_value(&ASTValue::Int(*v)).unwrap())
+            .collect();
+        assert!(enc.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_float_and_string_encoding_preserve_order() {
+        let floats = [-10.5, -0.25, 0.0, 0.25, 3.0, 1e9];
+        let enc: Vec<_> = floats
+            .iter()
+            .map(|v| encode_value(&ASTValue::Float(*v)).unwrap())
+            .collect();
+        assert!(enc.windows(2).all(|w| w[0] < w[1]));
+
+        let strings = ["", "a", "a\0", "ab", "b"];
+        let enc: Vec<_> = strings
+            .iter()
+            .map(|s| encode_value(&ASTValue::String(s.to_string())).unwrap())
+            .collect();
+        assert!(enc.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_decimal_encoding_preserves_order() {
+        let vals = [
+            "-100", "-1.5", "-1.25", "-0.001", "0", "0.001", "0.1", "1.25", "1.5", "10",
+        ];
+        let enc: Vec<_> = vals
+            .iter()
+            .map(|v| encode_value(&ASTValue::Decimal(v.parse().unwrap())).unwrap())
+            .collect();
+        assert!(enc.windows(2).all(|w| w[0] < w[1]));
+
+        let same = |a: &str, b: &str| {
+            encode_value(&ASTValue::Decimal(a.parse().unwrap()))
+                == encode_value(&ASTValue::Decimal(b.parse().unwrap()))
+        };
+        assert!(same("1.5", "1.500"));
+        assert!(same("-20", "-20.0"));
+        assert!(same("0", "0.00"));
+    }
+
+    #[test]
+    fn test_node_roundtrip() {
+        let mut leaf = Node::new_leaf();
+        leaf.keys.push(index_key(&ASTValue::Int(1), 7).unwrap());
+        leaf.keys.push(index_key(&ASTValue::Int(2), 3).unwrap());
+        leaf.next = 9;
+        let back = Node::from_page(&leaf.to_page().unwrap()).unwrap();
+        assert_eq!(back, leaf);
+        assert_eq!(key_row_id(&back.keys[0]), 7);
+
+        let mut inner = Node::new_internal(4);
+        inner.keys.push(index_key(&ASTValue::Int(10), 1).unwrap());
+        inner.children.push(5);
+        let back = Node::from_page(&inner.to_page().unwrap()).unwrap();
+        assert_eq!(back, inner);
+        assert_eq!(back.child_for(&index_key(&ASTValue::Int(3), 1).unwrap()), 0);
+        assert_eq!(
+            back.child_for(&index_key(&ASTValue::Int(10), 1).unwrap()),
+            1
+        );
+    }
+
+    #[test]
+    fn test_meta_roundtrip() {
+        let meta = IndexMeta {
+            name: "idx_age".into(),
+            column: "age".into(),
+            root: 1,
+            page_count: 2,
+            unique: true,
+        };
+        assert_eq!(IndexMeta::from_page(&meta.to_page()).unwrap(), meta);
+    }
+}
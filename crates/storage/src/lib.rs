@@ -1,3 +1,4 @@
+pub mod btree;
 pub mod database;
 pub mod page;
 pub mod record;
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use sql::ast::ASTValue;
 
-use crate::{record::serialize_record_for_page, types::Column};
+use crate::table::Table;
 
 use super::record::Record;
 use std::collections::HashMap;
@@ -10,6 +10,12 @@ pub const PAGE_SIZE: usize = 8192; // 8KB page size
 pub const HEADER_LEN: usize = 18; // see PageHeader::write_into
 pub const SLOT_LEN: usize = 5; // off u16 + len u16 + flags u8
 
+/// Slot flag: the row was deleted or moved by an UPDATE.
+pub const SLOT_TOMBSTONE: u8 = 0x01;
+/// Slot flag: the payload starts with the u16 schema version it was written
+/// under. Payloads without it predate versioning and use version 0.
+pub const SLOT_VERSIONED: u8 = 0x02;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Page {
     pub id: u32,
@@ -109,7 +115,7 @@ impl PageHeader {
 struct Slot {
     off: u16,
     len: u16,
-    flags: u8, // 0 = visible, 1 = tombstone (reserved)
+    flags: u8, // SLOT_TOMBSTONE | SLOT_VERSIONED
 }
 
 impl Slot {
@@ -124,7 +130,7 @@ impl Page {
     // Serialize the current in-memory page (records) into a fixed 8 KiB page with a heap layout.
     // Pack records sequentially into the payload and write a slot directory at the end.
     // Assumes self.records are already sized to fit (your free_space tracking should guarantee).
-    pub fn to_bytes(&self, columns: &[Column]) -> Result<[u8; PAGE_SIZE], String> {
+    pub fn to_bytes(&self, table: &Table) -> Result<[u8; PAGE_SIZE], String> {
         let mut page = [0u8; PAGE_SIZE];
 
         let mut hdr = PageHeader::new(self.id);
@@ -139,7 +145,8 @@ impl Page {
         for rid in ids {
             let rec = self.records.get(&rid).expect("record disappeared");
 
-            let payload = serialize_record_for_page(rid, rec, columns)
+            let payload = table
+                .encode_row(rid, rec)
                 .map_err(|e| format!("serialize record {} failed: {}", rid, e))?;
             let plen = payload.len();
 
@@ -163,7 +170,7 @@ impl Page {
             let slot = Slot {
                 off: start as u16,
                 len: plen as u16,
-                flags: 0,
+                flags: SLOT_VERSIONED,
             };
             slot.write_into(&mut page[slot_dir_end..slot_dir_end + SLOT_LEN]);
 
@@ -219,8 +226,8 @@ fn read_header(buf: &[u8]) -> Result<ReadHeader, String> {
     Ok(ReadHeader { record_count: rc })
 }
 
-// Append a new payload; returns new slot_id
-pub fn page_append(buf: &mut [u8], payload: &[u8]) -> Result<u16, String> {
+// Append a new payload with slot `flags`; returns new slot_id
+pub fn page_append(buf: &mut [u8], payload: &[u8], flags: u8) -> Result<u16, String> {
     if buf.len() != PAGE_SIZE {
         return Err("invalid page buffer size".into());
     }
@@ -255,7 +262,7 @@ pub fn page_append(buf: &mut [u8], payload: &[u8]) -> Result<u16, String> {
     let l_le = (needed as u16).to_le_bytes();
     buf[new_slot_off..new_slot_off + 2].copy_from_slice(&o_le);
     buf[new_slot_off + 2..new_slot_off + 4].copy_from_slice(&l_le);
-    buf[new_slot_off + 4] = 0; // flags
+    buf[new_slot_off + 4] = flags;
 
     // Update header: record_count += 1
     let new_rc = (rc as u16) + 1;
@@ -264,12 +271,14 @@ pub fn page_append(buf: &mut [u8], payload: &[u8]) -> Result<u16, String> {
     Ok(rc as u16) // new slot id is old count
 }
 
-// Try to overwrite payload in place if it fits the old slot length
+// Try to overwrite payload in place if it fits the old slot length, replacing
+// the slot flags with `flags`.
 // Returns Ok(true) if overwritten; Ok(false) if not (caller must append+old-tombstone)
 pub fn page_overwrite_if_fits(
     buf: &mut [u8],
     slot_id: u16,
     new_payload: &[u8],
+    flags: u8,
 ) -> Result<bool, String> {
     let rc = u16::from_le_bytes(buf[12..14].try_into().unwrap()) as usize;
     if slot_id as usize >= rc {
@@ -279,11 +288,11 @@ pub fn page_overwrite_if_fits(
     let off = PAGE_SIZE - (slot_id as usize + 1) * SLOT_LEN;
     let o = u16::from_le_bytes(buf[off..off + 2].try_into().unwrap()) as usize;
     let l = u16::from_le_bytes(buf[off + 2..off + 4].try_into().unwrap()) as usize;
-    let _flags = buf[off + 4];
 
     if new_payload.len() > l {
         return Ok(false);
     }
+    buf[off + 4] = flags;
     // Overwrite in place (pad leftover with zeros if shorter)
     buf[o..o + new_payload.len()].copy_from_slice(new_payload);
     if new_payload.len() < l {
@@ -301,7 +310,7 @@ pub fn page_set_tombstone(buf: &mut [u8], slot_id: u16) -> Result<(), String> {
     }
 
     let off = PAGE_SIZE - (slot_id as usize + 1) * SLOT_LEN;
-    buf[off + 4] = 1; // set tombstone flag
+    buf[off + 4] |= SLOT_TOMBSTONE;
     Ok(())
 }
 #[cfg(test)]
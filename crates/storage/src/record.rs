@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
-use sql::ast::{ASTValue, Condition};
+use sql::{
+    ast::{ASTValue, Expr, ScalarFunc},
+    decimal::Decimal,
+    temporal::{self, MICROS_PER_DAY, parse_date, parse_time, parse_timestamp},
+};
 use std::collections::HashMap;
 use std::mem::size_of;
+use std::ops::RangeInclusive;
 use types::tokens::{DataType, Operator};
 
 use crate::types::RowId;
@@ -38,13 +43,11 @@ impl Record {
     pub fn validate(&self, columns: &[Column]) -> Result<(), String> {
         for column in columns {
             match self.data.get(&column.name) {
-                Some(value) => {
-                    if !Self::is_valid_type(value, &column.data_type) {
-                        return Err(format!(
-                            "Invalid type for column {}: expected {:?}, got {:?}",
-                            column.name, column.data_type, value
-                        ));
-                    }
+                Some(value) if !fits_type(value, &column.data_type) => {
+                    return Err(format!(
+                        "Invalid type for column {}: expected {:?}, got {:?}",
+                        column.name, column.data_type, value
+                    ));
                 }
                 None if !column.nullable => {
                     return Err(format!("Missing required column: {}", column.name));
@@ -54,80 +57,405 @@ impl Record {
         }
         Ok(())
     }
+}
 
-    fn is_valid_type(value: &ASTValue, expected_type: &DataType) -> bool {
-        // match (value, expected_type) {
-        //     (ASTValue::Int(_), DataType::Integer) => true,
-        //     (ASTValue::Float(_), DataType::Float) => true,
-        //     (ASTValue::String(_), DataType::Text) => true,
-        //     (ASTValue::Boolean(_), DataType::Boolean) => true,
-        //     (ASTValue::Null, _) => true,
-        //     _ => false,
-        // }
-        matches!(
-            (value, expected_type),
-            (ASTValue::Int(_), DataType::INTEGER)
-                | (ASTValue::Float(_), DataType::FLOAT)
-                | (ASTValue::String(_), DataType::TEXT)
-                | (ASTValue::Boolean(_), DataType::BOOLEAN)
-                | (ASTValue::Null, _)
-        )
+/// Whether `value` is stored as is in a column of `data_type`: it has the
+/// type's representation and lies within its range. NULL fits anywhere.
+pub fn fits_type(value: &ASTValue, data_type: &DataType) -> bool {
+    match (value, data_type) {
+        (ASTValue::Null, _) => true,
+        (ASTValue::Int(i), _) => integer_range(data_type).is_some_and(|r| r.contains(i)),
+        (ASTValue::Float(_), DataType::FLOAT | DataType::DOUBLE) => true,
+        (ASTValue::Float(f), DataType::REAL) => !f.is_finite() || f.abs() <= f64::from(f32::MAX),
+        (ASTValue::Decimal(d), DataType::DECIMAL(spec) | DataType::NUMERIC(spec)) => {
+            spec.is_none_or(|(p, s)| d.scale() == s && d.precision() <= u32::from(p))
+        }
+        (
+            ASTValue::String(_),
+            DataType::TEXT | DataType::CHAR | DataType::BLOB | DataType::JSON,
+        ) => true,
+        (ASTValue::Boolean(_), DataType::BOOLEAN) => true,
+        (ASTValue::Date(_), DataType::DATE) => true,
+        (ASTValue::Time(t), DataType::TIME) => (0..MICROS_PER_DAY).contains(t),
+        (ASTValue::Timestamp(_), DataType::TIMESTAMP | DataType::DATETIME) => true,
+        _ => false,
+    }
+}
+
+/// Values of an integer type. INTEGER is 64-bit like BIGINT.
+pub fn integer_range(data_type: &DataType) -> Option<RangeInclusive<i64>> {
+    match data_type {
+        DataType::TINYINT => Some(i8::MIN.into()..=i8::MAX.into()),
+        DataType::SMALLINT => Some(i16::MIN.into()..=i16::MAX.into()),
+        DataType::MEDIUMINT => Some(-(1 << 23)..=(1 << 23) - 1),
+        DataType::INTEGER | DataType::BIGINT => Some(i64::MIN..=i64::MAX),
+        _ => None,
+    }
+}
+
+/// `value` converted for a column of `data_type`. Integers widen to the
+/// floating-point and DECIMAL types, floats become the DECIMAL they print
+/// as, REAL rounds to single precision, DECIMAL(p,s) rounds half away from
+/// zero to `s` digits after the point, and strings are parsed for DATE,
+/// TIME and TIMESTAMP. A value too large for the type is an error; its
+/// integer digits are never truncated.
+pub fn coerce_value(value: ASTValue, data_type: &DataType) -> Result<ASTValue, String> {
+    let out_of_range = |value: &ASTValue| format!("{} is out of range for {}", value, data_type);
+    if let (ASTValue::Int(i), Some(range)) = (&value, integer_range(data_type)) {
+        return if range.contains(i) {
+            Ok(value)
+        } else {
+            Err(out_of_range(&value))
+        };
+    }
+    if let DataType::DECIMAL(spec) | DataType::NUMERIC(spec) = data_type {
+        let decimal = match &value {
+            ASTValue::Null => return Ok(value),
+            ASTValue::Int(i) => Some(Decimal::from(*i)),
+            ASTValue::Float(f) => Decimal::from_f64(*f),
+            ASTValue::Decimal(d) => Some(*d),
+            _ => return Err(format!("{} does not fit a {} column", value, data_type)),
+        };
+        let decimal = match (decimal, spec) {
+            (Some(d), Some((p, s))) => d.rescale(*s).filter(|d| d.precision() <= u32::from(*p)),
+            (d, None) => d,
+            (None, _) => None,
+        };
+        return decimal
+            .map(ASTValue::Decimal)
+            .ok_or_else(|| out_of_range(&value));
+    }
+    let coerced = match (&value, data_type) {
+        (ASTValue::Int(i), DataType::FLOAT | DataType::DOUBLE | DataType::REAL) => {
+            Some(ASTValue::Float(*i as f64))
+        }
+        (ASTValue::Decimal(d), DataType::FLOAT | DataType::DOUBLE | DataType::REAL) => {
+            Some(ASTValue::Float(d.to_f64()))
+        }
+        (ASTValue::String(s), DataType::DATE) => parse_date(s).map(ASTValue::Date),
+        (ASTValue::String(s), DataType::TIME) => parse_time(s).map(ASTValue::Time),
+        (ASTValue::String(s), DataType::TIMESTAMP | DataType::DATETIME) => {
+            parse_timestamp(s).map(ASTValue::Timestamp)
+        }
+        (ASTValue::Date(d), DataType::TIMESTAMP | DataType::DATETIME) => {
+            Some(ASTValue::Timestamp(i64::from(*d) * MICROS_PER_DAY))
+        }
+        _ => Some(value.clone()),
+    };
+    let coerced = match coerced {
+        Some(ASTValue::Float(f)) if *data_type == DataType::REAL => {
+            if f.is_finite() && f.abs() > f64::from(f32::MAX) {
+                return Err(out_of_range(&value));
+            }
+            ASTValue::Float(real_to_f64(f as f32))
+        }
+        Some(v) => v,
+        None => return Err(format!("{} is not a valid {}", value, data_type)),
+    };
+    if fits_type(&coerced, data_type) {
+        Ok(coerced)
+    } else {
+        Err(format!("{} does not fit a {} column", value, data_type))
+    }
+}
+
+/// `value` as stored in a column of `data_type`, if converting it does not
+/// change what it denotes: `20` for a DECIMAL(5,2) column is 20.00, while
+/// 1.005 would round and gives None. NULL gives None.
+pub fn coerce_exact(value: &ASTValue, data_type: &DataType) -> Option<ASTValue> {
+    let coerced = coerce_value(value.clone(), data_type).ok()?;
+    (compare_values(&coerced, value, &Operator::EQUALS) == ASTValue::Boolean(true))
+        .then_some(coerced)
+}
+
+// A REAL as the float closest to its shortest decimal form, so 1.1 stored
+// in a REAL reads back as 1.1 rather than 1.100000023841858.
+fn real_to_f64(f: f32) -> f64 {
+    f.to_string().parse().unwrap_or(f64::from(f))
+}
+
+/// What an expression sees besides its row. One context serves a whole
+/// statement, so every row of it reads the same `NOW()`.
+#[derive(Debug, Clone, Copy)]
+pub struct EvalContext {
+    /// Local time the statement started, in microseconds since the epoch.
+    pub now: i64,
+}
+
+impl EvalContext {
+    /// A context for a statement starting now.
+    pub fn now() -> Self {
+        Self {
+            now: temporal::now(),
+        }
     }
 }
 
 impl Record {
-    pub fn evaluate_condition(&self, condition: &Condition) -> bool {
-        match condition {
-            Condition::Comparison {
-                operator,
-                left,
-                right,
-            } => match operator {
-                Operator::AND => self.evaluate_condition(left) && self.evaluate_condition(right),
-                Operator::OR => self.evaluate_condition(left) || self.evaluate_condition(right),
-                _ => {
-                    let left = self.extract_condition_value(left);
-                    let right = self.extract_condition_value(right);
-
-                    match (left, right) {
-                        (Some(lv), Some(rv)) => self.compare_values(&lv, &rv, operator),
-                        _ => false,
+    /// Value of `expr` in this row. Columns are looked up by row key: bare
+    /// names in single-table rows, `u.age` in joined ones. Aggregate results
+    /// are stored under the call's text.
+    ///
+    /// Logic is three-valued as in SQL: a comparison with NULL is UNKNOWN,
+    /// returned as NULL, and AND / OR / NOT carry UNKNOWN through.
+    pub fn evaluate(&self, expr: &Expr, ctx: &EvalContext) -> Result<ASTValue, String> {
+        match expr {
+            Expr::Column(column) => self
+                .get_value(&column.to_string())
+                .cloned()
+                .ok_or_else(|| format!("Column '{}' missing in record", column)),
+            Expr::Value(value) => Ok(value.clone()),
+            Expr::Aggregate(call) => self
+                .get_value(&call.to_string())
+                .cloned()
+                .ok_or_else(|| format!("Aggregate '{}' missing in record", call)),
+            Expr::Negate(operand) => match self.evaluate(operand, ctx)? {
+                ASTValue::Int(i) => i
+                    .checked_neg()
+                    .map(ASTValue::Int)
+                    .ok_or_else(|| "Integer overflow".to_string()),
+                ASTValue::Float(f) => Ok(ASTValue::Float(-f)),
+                ASTValue::Decimal(d) => Ok(ASTValue::Decimal(-d)),
+                ASTValue::Null => Ok(ASTValue::Null),
+                other => Err(format!("Cannot negate {}", other)),
+            },
+            Expr::Not(operand) => Ok(truth_value(self.truth(operand, ctx)?.map(|b| !b))),
+            Expr::IsNull { expr, negated } => Ok(ASTValue::Boolean(
+                (self.evaluate(expr, ctx)? == ASTValue::Null) != *negated,
+            )),
+            Expr::Function { func, args } => match func {
+                ScalarFunc::Coalesce => {
+                    for arg in args {
+                        let value = self.evaluate(arg, ctx)?;
+                        if value != ASTValue::Null {
+                            return Ok(value);
+                        }
                     }
+                    Ok(ASTValue::Null)
+                }
+                ScalarFunc::NullIf => {
+                    let [a, b] = args.as_slice() else {
+                        return Err("NULLIF takes two arguments".to_string());
+                    };
+                    let a = self.evaluate(a, ctx)?;
+                    let equal = compare_values(&a, &self.evaluate(b, ctx)?, &Operator::EQUALS);
+                    Ok(if equal == ASTValue::Boolean(true) {
+                        ASTValue::Null
+                    } else {
+                        a
+                    })
+                }
+                ScalarFunc::Now | ScalarFunc::CurrentTimestamp => Ok(ASTValue::Timestamp(ctx.now)),
+                ScalarFunc::CurrentDate => {
+                    Ok(ASTValue::Date(ctx.now.div_euclid(MICROS_PER_DAY) as i32))
+                }
+                ScalarFunc::CurrentTime => Ok(ASTValue::Time(ctx.now.rem_euclid(MICROS_PER_DAY))),
+                ScalarFunc::Extract(field) | ScalarFunc::DateTrunc(field) => {
+                    let [arg] = args.as_slice() else {
+                        return Err(format!("{} takes one argument", func));
+                    };
+                    let value = self.evaluate(arg, ctx)?;
+                    let micros = match value {
+                        ASTValue::Null => return Ok(ASTValue::Null),
+                        ASTValue::Date(d) => i64::from(d) * MICROS_PER_DAY,
+                        ASTValue::Timestamp(ts) => ts,
+                        ASTValue::Time(t) if field.in_time() => t,
+                        other => return Err(format!("Cannot apply {} to {}", func, other)),
+                    };
+                    let invalid = || format!("Cannot apply {} to {}", func, micros);
+                    if let ScalarFunc::Extract(_) = func {
+                        return temporal::extract(*field, micros)
+                            .map(ASTValue::Int)
+                            .ok_or_else(invalid);
+                    }
+                    let start = temporal::trunc(*field, micros).ok_or_else(invalid)?;
+                    // A truncated DATE stays a DATE.
+                    Ok(match value {
+                        ASTValue::Date(_) => {
+                            ASTValue::Date(start.div_euclid(MICROS_PER_DAY) as i32)
+                        }
+                        ASTValue::Time(_) => ASTValue::Time(start),
+                        _ => ASTValue::Timestamp(start),
+                    })
+                }
+            },
+            Expr::Binary { op, left, right } => match op {
+                // FALSE AND x is FALSE and TRUE OR x is TRUE, even if x is
+                // UNKNOWN; x is not evaluated then.
+                Operator::AND => Ok(truth_value(match self.truth(left, ctx)? {
+                    Some(false) => Some(false),
+                    l => match (l, self.truth(right, ctx)?) {
+                        (_, Some(false)) => Some(false),
+                        (Some(true), Some(true)) => Some(true),
+                        _ => None,
+                    },
+                })),
+                Operator::OR => Ok(truth_value(match self.truth(left, ctx)? {
+                    Some(true) => Some(true),
+                    l => match (l, self.truth(right, ctx)?) {
+                        (_, Some(true)) => Some(true),
+                        (Some(false), Some(false)) => Some(false),
+                        _ => None,
+                    },
+                })),
+                Operator::PLUS | Operator::MINUS | Operator::ASTERISK | Operator::DIVIDE => {
+                    arithmetic(op, self.evaluate(left, ctx)?, self.evaluate(right, ctx)?)
                 }
+                Operator::BANG => Err(format!("Unsupported operator {}", op)),
+                _ => Ok(compare_values(
+                    &self.evaluate(left, ctx)?,
+                    &self.evaluate(right, ctx)?,
+                    op,
+                )),
             },
-            _ => false,
         }
     }
 
-    fn extract_condition_value(&self, condition: &Condition) -> Option<ASTValue> {
-        match condition {
-            Condition::Column(column_name) => self.get_value(column_name).cloned(),
-            Condition::Value(value) => Some(value.clone()),
-            _ => None,
+    /// Whether `expr` holds for this row. UNKNOWN (NULL) counts as false; a
+    /// value that is not a boolean is an error.
+    pub fn evaluate_condition(&self, expr: &Expr, ctx: &EvalContext) -> Result<bool, String> {
+        Ok(self.truth(expr, ctx)? == Some(true))
+    }
+
+    // Truth value of a condition: None for UNKNOWN.
+    fn truth(&self, expr: &Expr, ctx: &EvalContext) -> Result<Option<bool>, String> {
+        match self.evaluate(expr, ctx)? {
+            ASTValue::Boolean(b) => Ok(Some(b)),
+            ASTValue::Null => Ok(None),
+            other => Err(format!("Condition must be a boolean, got {}", other)),
         }
     }
+}
 
-    fn compare_values(&self, left: &ASTValue, right: &ASTValue, operator: &Operator) -> bool {
-        match (left, right) {
-            (ASTValue::Int(l), ASTValue::Int(r)) => match operator {
-                Operator::EQUALS => l == r,
-                Operator::NE => l != r,
-                Operator::LT => l < r,
-                Operator::GT => l > r,
-                Operator::LTorE => l <= r,
-                Operator::GTorE => l >= r,
-                _ => false,
-            },
-            (ASTValue::String(l), ASTValue::String(r)) => match operator {
-                Operator::EQUALS => l == r,
-                Operator::NE => l != r,
-                _ => false,
-            },
-            _ => false,
+fn truth_value(truth: Option<bool>) -> ASTValue {
+    truth.map_or(ASTValue::Null, ASTValue::Boolean)
+}
+
+// `+ - * /`. Integer arithmetic stays integer and fails on overflow. With a
+// decimal and no float it is exact (see Decimal for the scale of results);
+// mixing in a float makes the result a float. NULL in gives NULL out.
+fn arithmetic(op: &Operator, left: ASTValue, right: ASTValue) -> Result<ASTValue, String> {
+    match (&left, &right) {
+        (ASTValue::Null, _) | (_, ASTValue::Null) => Ok(ASTValue::Null),
+        (ASTValue::Int(a), ASTValue::Int(b)) => {
+            let res = match op {
+                Operator::PLUS => a.checked_add(*b),
+                Operator::MINUS => a.checked_sub(*b),
+                Operator::ASTERISK => a.checked_mul(*b),
+                _ if *b == 0 => return Err("Division by zero".to_string()),
+                _ => a.checked_div(*b),
+            };
+            res.map(ASTValue::Int)
+                .ok_or_else(|| "Integer overflow".to_string())
+        }
+        _ => {
+            if let (Some(a), Some(b)) = (as_decimal(&left), as_decimal(&right)) {
+                let res = match op {
+                    Operator::PLUS => a.checked_add(&b),
+                    Operator::MINUS => a.checked_sub(&b),
+                    Operator::ASTERISK => a.checked_mul(&b),
+                    _ if b.is_zero() => return Err("Division by zero".to_string()),
+                    _ => a.checked_div(&b),
+                };
+                return res
+                    .map(ASTValue::Decimal)
+                    .ok_or_else(|| "Decimal overflow".to_string());
+            }
+            let (Some(a), Some(b)) = (as_f64(&left), as_f64(&right)) else {
+                return Err(format!("Cannot apply {} to {} and {}", op, left, right));
+            };
+            Ok(ASTValue::Float(match op {
+                Operator::PLUS => a + b,
+                Operator::MINUS => a - b,
+                Operator::ASTERISK => a * b,
+                _ if b == 0.0 => return Err("Division by zero".to_string()),
+                _ => a / b,
+            }))
         }
     }
 }
 
+fn as_f64(v: &ASTValue) -> Option<f64> {
+    match v {
+        ASTValue::Int(i) => Some(*i as f64),
+        ASTValue::Float(f) => Some(*f),
+        ASTValue::Decimal(d) => Some(d.to_f64()),
+        _ => None,
+    }
+}
+
+fn as_decimal(v: &ASTValue) -> Option<Decimal> {
+    match v {
+        ASTValue::Int(i) => Some(Decimal::from(*i)),
+        ASTValue::Decimal(d) => Some(*d),
+        _ => None,
+    }
+}
+
+fn as_time(v: &ASTValue) -> Option<i64> {
+    match v {
+        ASTValue::Time(t) => Some(*t),
+        ASTValue::String(s) => parse_time(s),
+        _ => None,
+    }
+}
+
+fn as_timestamp(v: &ASTValue) -> Option<i64> {
+    match v {
+        ASTValue::Date(d) => Some(i64::from(*d) * MICROS_PER_DAY),
+        ASTValue::Timestamp(ts) => Some(*ts),
+        ASTValue::String(s) => parse_timestamp(s),
+        _ => None,
+    }
+}
+
+// Values of the same type compare by value. Numbers compare across types:
+// exactly between integers and decimals, as floats once a float is involved.
+// A DATE compares with a TIMESTAMP as its midnight, and a date or time with a
+// string as the string's value, so `d > '2026-01-01'` works without a cast.
+// A comparison with NULL is UNKNOWN (NULL); values of other different types
+// compare false.
+fn compare_values(left: &ASTValue, right: &ASTValue, operator: &Operator) -> ASTValue {
+    let ordering = match (left, right) {
+        (ASTValue::Null, _) | (_, ASTValue::Null) => return ASTValue::Null,
+        (ASTValue::Float(_), _) | (_, ASTValue::Float(_)) => match (as_f64(left), as_f64(right)) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        },
+        (ASTValue::Int(_) | ASTValue::Decimal(_), ASTValue::Int(_) | ASTValue::Decimal(_)) => {
+            as_decimal(left).partial_cmp(&as_decimal(right))
+        }
+        (ASTValue::Time(_), ASTValue::Time(_) | ASTValue::String(_))
+        | (ASTValue::String(_), ASTValue::Time(_)) => match (as_time(left), as_time(right)) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => None,
+        },
+        (
+            ASTValue::Date(_) | ASTValue::Timestamp(_),
+            ASTValue::Date(_) | ASTValue::Timestamp(_) | ASTValue::String(_),
+        )
+        | (ASTValue::String(_), ASTValue::Date(_) | ASTValue::Timestamp(_)) => {
+            match (as_timestamp(left), as_timestamp(right)) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                _ => None,
+            }
+        }
+        _ => left.partial_cmp(right),
+    };
+    let Some(o) = ordering else {
+        return ASTValue::Boolean(false);
+    };
+    ASTValue::Boolean(match operator {
+        Operator::EQUALS => o.is_eq(),
+        Operator::NE => o.is_ne(),
+        Operator::LT => o.is_lt(),
+        Operator::GT => o.is_gt(),
+        Operator::LTorE => o.is_le(),
+        Operator::GTorE => o.is_ge(),
+        _ => false,
+    })
+}
+
 // Serialize a Record to a compact row payload suitable for heap page storage.
 pub fn serialize_record_for_page(
     row_id: RowId,
@@ -155,45 +483,86 @@ pub fn serialize_record_for_page(
 
     for col in columns {
         let val = record.data.get(&col.name).unwrap_or(&ASTValue::Null);
-        match (val, &col.data_type) {
-            (ASTValue::Null, _) => {
-                // No bytes for NULL (presence indicated by bitmap)
-            }
-            (ASTValue::Int(i), DataType::INTEGER) => {
-                out.extend_from_slice(&(*i).to_le_bytes());
-            }
-            (ASTValue::Float(f), DataType::FLOAT) => {
-                out.extend_from_slice(&(*f).to_le_bytes());
-            }
-            (ASTValue::Boolean(b), DataType::BOOLEAN) => {
-                out.push(if *b { 1 } else { 0 });
-            }
-            (ASTValue::String(s), DataType::TEXT)
-            | (ASTValue::String(s), DataType::CHAR)
-            | (ASTValue::String(s), DataType::BLOB)
-            | (ASTValue::String(s), DataType::JSON) => {
-                let bytes = s.as_bytes();
-                let len = u32::try_from(bytes.len()).map_err(|_| "string too long")?;
-                out.extend_from_slice(&len.to_le_bytes());
-                out.extend_from_slice(bytes);
-            }
-            _ => {
-                return Err(format!(
-                    "type mismatch for column '{}' (value: {:?}, expected: {:?})",
-                    col.name, val, col.data_type
-                ));
-            }
+        // No bytes for NULL (presence indicated by bitmap)
+        if *val == ASTValue::Null {
+            continue;
         }
+        if !fits_type(val, &col.data_type) {
+            return Err(format!(
+                "type mismatch for column '{}' (value: {:?}, expected: {:?})",
+                col.name, val, col.data_type
+            ));
+        }
+        encode_value(&mut out, val, &col.data_type)?;
     }
 
     Ok(out)
 }
 
+// Bytes of a non-NULL value that fits `data_type`. Integers take the width of
+// their type (MEDIUMINT three bytes), REAL four bytes, DECIMAL its scale
+// (unless the column fixes it), a byte count and the fewest two's-complement
+// bytes holding the mantissa, DATE four bytes and TIME / TIMESTAMP eight;
+// all little-endian.
+fn encode_value(out: &mut Vec<u8>, val: &ASTValue, data_type: &DataType) -> Result<(), String> {
+    match (val, data_type) {
+        (ASTValue::Int(i), DataType::TINYINT) => out.extend_from_slice(&(*i as i8).to_le_bytes()),
+        (ASTValue::Int(i), DataType::SMALLINT) => out.extend_from_slice(&(*i as i16).to_le_bytes()),
+        (ASTValue::Int(i), DataType::MEDIUMINT) => {
+            out.extend_from_slice(&(*i as i32).to_le_bytes()[..3])
+        }
+        (ASTValue::Int(i), _) => out.extend_from_slice(&i.to_le_bytes()),
+        (ASTValue::Float(f), DataType::REAL) => out.extend_from_slice(&(*f as f32).to_le_bytes()),
+        (ASTValue::Float(f), _) => out.extend_from_slice(&f.to_le_bytes()),
+        (ASTValue::Decimal(d), _) => {
+            let bytes = d.mantissa().to_le_bytes();
+            // Drop high bytes that only repeat the sign.
+            let mut len = bytes.len();
+            while len > 1 && {
+                let (top, next) = (bytes[len - 1], bytes[len - 2]);
+                (top == 0 && next < 0x80) || (top == 0xFF && next >= 0x80)
+            } {
+                len -= 1;
+            }
+            if matches!(data_type, DataType::DECIMAL(None) | DataType::NUMERIC(None)) {
+                out.push(d.scale());
+            }
+            out.push(len as u8);
+            out.extend_from_slice(&bytes[..len]);
+        }
+        (ASTValue::Boolean(b), _) => out.push(if *b { 1 } else { 0 }),
+        (ASTValue::String(s), _) => {
+            let bytes = s.as_bytes();
+            let len = u32::try_from(bytes.len()).map_err(|_| "string too long")?;
+            out.extend_from_slice(&len.to_le_bytes());
+            out.extend_from_slice(bytes);
+        }
+        (ASTValue::Date(d), _) => out.extend_from_slice(&d.to_le_bytes()),
+        (ASTValue::Time(t) | ASTValue::Timestamp(t), _) => out.extend_from_slice(&t.to_le_bytes()),
+        (ASTValue::Null, _) => {}
+    }
+    Ok(())
+}
+
 pub fn deserialize_record_for_page(
     payload: &[u8],
     columns: &[Column],
 ) -> Result<(RowId, Record), String> {
-    let n = columns.len();
+    let types: Vec<&DataType> = columns.iter().map(|c| &c.data_type).collect();
+    let (row_id, values) = decode_payload(payload, &types)?;
+    let mut rec = Record::new(row_id);
+    for (col, value) in columns.iter().zip(values) {
+        rec.set_value(&col.name, value);
+    }
+    Ok((row_id, rec))
+}
+
+// Decode the row id and one value per entry of `types`, in payload order.
+pub(crate) fn decode_payload(
+    payload: &[u8],
+    types: &[&DataType],
+) -> Result<(RowId, Vec<ASTValue>), String> {
+    let n = types.len();
     let bitmap_bytes = n.div_ceil(8);
     if payload.len() < bitmap_bytes + size_of::<RowId>() {
         return Err("payload too short for null bitmap".into());
@@ -204,68 +573,87 @@ pub fn deserialize_record_for_page(
 
     let (bitmap, mut p) = payload.split_at(bitmap_bytes);
 
-    let mut rec = Record::new(row_id);
-    for (i, col) in columns.iter().enumerate() {
+    let mut values = Vec::with_capacity(n);
+    for (i, data_type) in types.iter().enumerate() {
         let byte = i / 8;
         let bit = i % 8;
         let is_null = (bitmap[byte] & (1 << bit)) != 0;
         if is_null {
-            rec.set_value(&col.name, ASTValue::Null);
+            values.push(ASTValue::Null);
             continue;
         }
-        match col.data_type {
-            DataType::INTEGER => {
-                if p.len() < std::mem::size_of::<i64>() {
-                    return Err("payload truncated (INTEGER)".into());
-                }
-                let (b, r) = p.split_at(8);
-                let v = i64::from_le_bytes(b.try_into().unwrap());
-                rec.set_value(&col.name, ASTValue::Int(v));
-                p = r;
-            }
-            DataType::FLOAT => {
-                if p.len() < std::mem::size_of::<f64>() {
-                    return Err("payload truncated (FLOAT)".into());
-                }
-                let (b, r) = p.split_at(8);
-                let v = f64::from_le_bytes(b.try_into().unwrap());
-                rec.set_value(&col.name, ASTValue::Float(v));
-                p = r;
-            }
-            DataType::BOOLEAN => {
-                if p.is_empty() {
-                    return Err("payload truncated (BOOLEAN)".into());
-                }
-                let (b, r) = p.split_at(1);
-                rec.set_value(&col.name, ASTValue::Boolean(b[0] != 0));
-                p = r;
-            }
-            DataType::TEXT | DataType::CHAR | DataType::BLOB | DataType::JSON => {
-                if p.len() < 4 {
-                    return Err("payload truncated (len32)".into());
-                }
-                let (lb, r1) = p.split_at(4);
-                let len = u32::from_le_bytes(lb.try_into().unwrap()) as usize;
-                if r1.len() < len {
-                    return Err("payload truncated (varlen bytes)".into());
-                }
-                let (vb, r2) = r1.split_at(len);
-                let s = String::from_utf8(vb.to_vec())
-                    .map_err(|_| "invalid utf-8 in TEXT/CHAR/JSON".to_string())?;
-                rec.set_value(&col.name, ASTValue::String(s));
-                p = r2;
-            }
-            _ => {
-                return Err(format!("deserialize unsupported type {:?}", col.data_type));
+        values.push(decode_value(&mut p, data_type)?);
+    }
+    Ok((row_id, values))
+}
+
+// Decode one non-NULL value of `data_type` off the front of `p`.
+fn decode_value(p: &mut &[u8], data_type: &DataType) -> Result<ASTValue, String> {
+    let mut take = |n: usize| -> Result<&[u8], String> {
+        if p.len() < n {
+            return Err(format!("payload truncated ({})", data_type));
+        }
+        let (b, r) = p.split_at(n);
+        *p = r;
+        Ok(b)
+    };
+    Ok(match data_type {
+        DataType::TINYINT => ASTValue::Int(i8::from_le_bytes(take(1)?.try_into().unwrap()).into()),
+        DataType::SMALLINT => {
+            ASTValue::Int(i16::from_le_bytes(take(2)?.try_into().unwrap()).into())
+        }
+        DataType::MEDIUMINT => {
+            let b = take(3)?;
+            // Sign-extend from 24 bits.
+            ASTValue::Int((i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8).into())
+        }
+        DataType::INTEGER | DataType::BIGINT => {
+            ASTValue::Int(i64::from_le_bytes(take(8)?.try_into().unwrap()))
+        }
+        DataType::REAL => ASTValue::Float(real_to_f64(f32::from_le_bytes(
+            take(4)?.try_into().unwrap(),
+        ))),
+        DataType::FLOAT | DataType::DOUBLE => {
+            ASTValue::Float(f64::from_le_bytes(take(8)?.try_into().unwrap()))
+        }
+        DataType::DECIMAL(spec) | DataType::NUMERIC(spec) => {
+            let scale = match spec {
+                Some((_, s)) => *s,
+                None => take(1)?[0],
+            };
+            let len = usize::from(take(1)?[0]);
+            if !(1..=16).contains(&len) {
+                return Err(format!("bad DECIMAL length {}", len));
             }
+            let b = take(len)?;
+            let fill = if b[len - 1] >= 0x80 { 0xFF } else { 0 };
+            let mut bytes = [fill; 16];
+            bytes[..len].copy_from_slice(b);
+            let d =
+                Decimal::new(i128::from_le_bytes(bytes), scale).ok_or("DECIMAL out of range")?;
+            ASTValue::Decimal(d)
         }
-    }
-    Ok((row_id, rec))
+        DataType::BOOLEAN => ASTValue::Boolean(take(1)?[0] != 0),
+        DataType::TEXT | DataType::CHAR | DataType::BLOB | DataType::JSON => {
+            let len = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+            let s = String::from_utf8(take(len)?.to_vec())
+                .map_err(|_| "invalid utf-8 in TEXT/CHAR/JSON".to_string())?;
+            ASTValue::String(s)
+        }
+        DataType::DATE => ASTValue::Date(i32::from_le_bytes(take(4)?.try_into().unwrap())),
+        DataType::TIME => ASTValue::Time(i64::from_le_bytes(take(8)?.try_into().unwrap())),
+        DataType::TIMESTAMP | DataType::DATETIME => {
+            ASTValue::Timestamp(i64::from_le_bytes(take(8)?.try_into().unwrap()))
+        }
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sql::{ast::ColumnRef, temporal::DateField};
+
+    const CTX: EvalContext = EvalContext { now: 0 };
 
     #[test]
     fn test_record_creation() {
@@ -306,4 +694,324 @@ mod tests {
         record.set_value("id", ASTValue::String("invalid".to_string()));
         assert!(record.validate(&columns).is_err());
     }
+
+    #[test]
+    fn test_record_evaluate() {
+        let mut record = Record::new(1);
+        record.set_value("price", ASTValue::Int(7));
+        record.set_value("qty", ASTValue::Int(3));
+        record.set_value("note", ASTValue::Null);
+        let column = |name: &str| Expr::Column(ColumnRef::new(name));
+        let int = |i: i64| Expr::Value(ASTValue::Int(i));
+
+        // price * qty - 1
+        let total = Expr::binary(
+            Operator::MINUS,
+            Expr::binary(Operator::ASTERISK, column("price"), column("qty")),
+            int(1),
+        );
+        assert_eq!(record.evaluate(&total, &CTX), Ok(ASTValue::Int(20)));
+
+        // price / 2.0 mixes in a float
+        let half = Expr::binary(
+            Operator::DIVIDE,
+            column("price"),
+            Expr::Value(ASTValue::Float(2.0)),
+        );
+        assert_eq!(record.evaluate(&half, &CTX), Ok(ASTValue::Float(3.5)));
+
+        // NULL in, NULL out
+        let null_sum = Expr::binary(Operator::PLUS, column("note"), int(1));
+        assert_eq!(record.evaluate(&null_sum, &CTX), Ok(ASTValue::Null));
+
+        // NOT (price > qty) compares two columns
+        let not_gt = Expr::Not(Box::new(Expr::binary(
+            Operator::GT,
+            column("price"),
+            column("qty"),
+        )));
+        assert_eq!(record.evaluate_condition(&not_gt, &CTX), Ok(false));
+
+        let by_zero = Expr::binary(Operator::DIVIDE, column("price"), int(0));
+        assert!(record.evaluate(&by_zero, &CTX).is_err());
+        let overflow = Expr::binary(Operator::PLUS, int(i64::MAX), int(1));
+        assert!(record.evaluate(&overflow, &CTX).is_err());
+        assert!(record.evaluate_condition(&column("price"), &CTX).is_err());
+    }
+
+    #[test]
+    fn test_record_three_valued_logic() {
+        let mut record = Record::new(1);
+        record.set_value("n", ASTValue::Int(1));
+        record.set_value("missing", ASTValue::Null);
+        let column = |name: &str| Expr::Column(ColumnRef::new(name));
+        let int = |i: i64| Expr::Value(ASTValue::Int(i));
+        let boolean = |b: bool| Expr::Value(ASTValue::Boolean(b));
+        let unknown = || Expr::binary(Operator::EQUALS, column("missing"), int(1));
+
+        assert_eq!(record.evaluate(&unknown(), &CTX), Ok(ASTValue::Null));
+        assert_eq!(
+            record.evaluate(&Expr::Not(Box::new(unknown())), &CTX),
+            Ok(ASTValue::Null)
+        );
+        assert_eq!(record.evaluate_condition(&unknown(), &CTX), Ok(false));
+
+        let and = |r: Expr| Expr::binary(Operator::AND, unknown(), r);
+        assert_eq!(
+            record.evaluate(&and(boolean(false)), &CTX),
+            Ok(ASTValue::Boolean(false))
+        );
+        assert_eq!(
+            record.evaluate(&and(boolean(true)), &CTX),
+            Ok(ASTValue::Null)
+        );
+        let or = |r: Expr| Expr::binary(Operator::OR, unknown(), r);
+        assert_eq!(
+            record.evaluate(&or(boolean(true)), &CTX),
+            Ok(ASTValue::Boolean(true))
+        );
+        assert_eq!(
+            record.evaluate(&or(boolean(false)), &CTX),
+            Ok(ASTValue::Null)
+        );
+
+        let is_null = |e: Expr, negated: bool| Expr::IsNull {
+            expr: Box::new(e),
+            negated,
+        };
+        assert_eq!(
+            record.evaluate(&is_null(column("missing"), false), &CTX),
+            Ok(ASTValue::Boolean(true))
+        );
+        assert_eq!(
+            record.evaluate(&is_null(column("n"), true), &CTX),
+            Ok(ASTValue::Boolean(true))
+        );
+
+        let call = |func: ScalarFunc, args: Vec<Expr>| Expr::Function { func, args };
+        assert_eq!(
+            record.evaluate(
+                &call(ScalarFunc::Coalesce, vec![column("missing"), int(7)]),
+                &CTX
+            ),
+            Ok(ASTValue::Int(7))
+        );
+        assert_eq!(
+            record.evaluate(&call(ScalarFunc::NullIf, vec![column("n"), int(1)]), &CTX),
+            Ok(ASTValue::Null)
+        );
+        assert_eq!(
+            record.evaluate(&call(ScalarFunc::NullIf, vec![column("n"), int(2)]), &CTX),
+            Ok(ASTValue::Int(1))
+        );
+    }
+
+    #[test]
+    fn test_every_type_round_trips() {
+        let string = |s: &str| ASTValue::String(s.to_string());
+        let cases = [
+            ("a", DataType::TINYINT, ASTValue::Int(-128)),
+            ("b", DataType::SMALLINT, ASTValue::Int(32767)),
+            ("c", DataType::MEDIUMINT, ASTValue::Int(-8388608)),
+            ("d", DataType::BIGINT, ASTValue::Int(9_000_000_000)),
+            ("e", DataType::REAL, ASTValue::Float(1.1)),
+            ("f", DataType::DOUBLE, ASTValue::Int(2)),
+            ("g", DataType::DECIMAL(None), string("x")),
+            ("h", DataType::NUMERIC(Some((5, 2))), ASTValue::Float(0.05)),
+            ("i", DataType::DATE, string("2026-10-17")),
+            ("j", DataType::TIME, string("23:59:59.5")),
+            ("k", DataType::TIMESTAMP, string("1969-12-31 23:00:00")),
+            ("l", DataType::JSON, string("{}")),
+        ];
+        let big: Decimal = "-123456789012345678901234567890.125".parse().unwrap();
+
+        let mut columns = Vec::new();
+        let mut record = Record::new(9);
+        for (name, data_type, value) in cases {
+            let value = if name == "g" {
+                ASTValue::Decimal(big)
+            } else {
+                coerce_value(value, &data_type).unwrap()
+            };
+            record.set_value(name, value);
+            columns.push(Column::new(name.to_string(), data_type, true));
+        }
+        let payload = serialize_record_for_page(9, &record, &columns).unwrap();
+        let (row_id, decoded) = deserialize_record_for_page(&payload, &columns).unwrap();
+        assert_eq!(row_id, 9);
+        for col in &columns {
+            assert_eq!(decoded.get_value(&col.name), record.get_value(&col.name));
+        }
+        assert_eq!(decoded.get_value("e"), Some(&ASTValue::Float(1.1)));
+        assert_eq!(decoded.get_value("f"), Some(&ASTValue::Float(2.0)));
+        assert_eq!(
+            decoded.get_value("g").map(|v| v.to_string()),
+            Some(big.to_string())
+        );
+    }
+
+    #[test]
+    fn test_coerce_value_checks_ranges() {
+        assert!(coerce_value(ASTValue::Int(128), &DataType::TINYINT).is_err());
+        assert!(coerce_value(ASTValue::Int(-32769), &DataType::SMALLINT).is_err());
+        assert!(coerce_value(ASTValue::Int(1 << 23), &DataType::MEDIUMINT).is_err());
+        assert!(coerce_value(ASTValue::Float(1e39), &DataType::REAL).is_err());
+        assert!(coerce_value(ASTValue::String("24:00".into()), &DataType::TIME).is_err());
+        assert!(coerce_value(ASTValue::String("x".into()), &DataType::DATE).is_err());
+        assert!(coerce_value(ASTValue::Float(1.5), &DataType::INTEGER).is_err());
+        assert_eq!(
+            coerce_value(ASTValue::Int(3), &DataType::DOUBLE),
+            Ok(ASTValue::Float(3.0))
+        );
+        assert_eq!(
+            coerce_value(ASTValue::Null, &DataType::DATE),
+            Ok(ASTValue::Null)
+        );
+
+        let money = DataType::DECIMAL(Some((5, 2)));
+        let decimal = |s: &str| ASTValue::Decimal(s.parse().unwrap());
+        let stored = |v: ASTValue| coerce_value(v, &money).map(|v| v.to_string());
+        assert_eq!(stored(decimal("1.005")), Ok("1.01".to_string()));
+        assert_eq!(stored(decimal("-1.005")), Ok("-1.01".to_string()));
+        assert_eq!(stored(ASTValue::Int(20)), Ok("20.00".to_string()));
+        assert_eq!(stored(decimal("999.994")), Ok("999.99".to_string()));
+        assert!(stored(decimal("999.995")).is_err());
+        assert!(stored(ASTValue::Int(1000)).is_err());
+    }
+
+    #[test]
+    fn test_decimal_arithmetic_is_exact() {
+        let mut record = Record::new(1);
+        record.set_value("price", ASTValue::Decimal("19.99".parse().unwrap()));
+        record.set_value("qty", ASTValue::Int(3));
+        let column = |name: &str| Expr::Column(ColumnRef::new(name));
+        let decimal = |s: &str| Expr::Value(ASTValue::Decimal(s.parse().unwrap()));
+        let eval = |op, left, right| {
+            record
+                .evaluate(&Expr::binary(op, left, right), &CTX)
+                .map(|v| v.to_string())
+        };
+
+        assert_eq!(
+            eval(Operator::ASTERISK, column("price"), column("qty")),
+            Ok("59.97".to_string())
+        );
+        assert_eq!(
+            eval(Operator::PLUS, decimal("0.1"), decimal("0.2")),
+            Ok("0.3".to_string())
+        );
+        assert_eq!(
+            eval(Operator::MINUS, column("price"), decimal("0.005")),
+            Ok("19.985".to_string())
+        );
+        assert_eq!(
+            eval(Operator::ASTERISK, column("price"), decimal("1.07")),
+            Ok("21.3893".to_string())
+        );
+        // Quotients keep at least six fractional digits, rounded half up.
+        assert_eq!(
+            eval(Operator::DIVIDE, decimal("2"), column("qty")),
+            Ok("0.666667".to_string())
+        );
+        assert_eq!(
+            eval(Operator::DIVIDE, decimal("-1"), decimal("8.00")),
+            Ok("-0.125000".to_string())
+        );
+        assert!(eval(Operator::DIVIDE, column("price"), decimal("0.00")).is_err());
+        // A float makes the result a float.
+        assert_eq!(
+            record.evaluate(
+                &Expr::binary(
+                    Operator::ASTERISK,
+                    column("qty"),
+                    Expr::Value(ASTValue::Float(0.5))
+                ),
+                &CTX
+            ),
+            Ok(ASTValue::Float(1.5))
+        );
+    }
+
+    #[test]
+    fn test_temporal_comparisons_and_functions() {
+        let mut record = Record::new(1);
+        record.set_value("d", ASTValue::Date(parse_date("2026-10-17").unwrap()));
+        record.set_value(
+            "ts",
+            ASTValue::Timestamp(parse_timestamp("2026-10-17 14:35:12.5").unwrap()),
+        );
+        record.set_value("t", ASTValue::Time(parse_time("09:30").unwrap()));
+        let column = |name: &str| Expr::Column(ColumnRef::new(name));
+        let string = |s: &str| Expr::Value(ASTValue::String(s.to_string()));
+        let compare = |op, left, right| record.evaluate(&Expr::binary(op, left, right), &CTX);
+
+        assert_eq!(
+            compare(Operator::GT, column("d"), string("2026-01-01")),
+            Ok(ASTValue::Boolean(true))
+        );
+        assert_eq!(
+            compare(Operator::EQUALS, string("2026-10-17"), column("d")),
+            Ok(ASTValue::Boolean(true))
+        );
+        // A DATE is its midnight next to a TIMESTAMP.
+        assert_eq!(
+            compare(Operator::LT, column("d"), column("ts")),
+            Ok(ASTValue::Boolean(true))
+        );
+        assert_eq!(
+            compare(Operator::LTorE, column("t"), string("09:30:00")),
+            Ok(ASTValue::Boolean(true))
+        );
+        assert_eq!(
+            compare(Operator::EQUALS, column("d"), string("not a date")),
+            Ok(ASTValue::Boolean(false))
+        );
+
+        let call = |func: ScalarFunc, arg: Expr| {
+            record
+                .evaluate(
+                    &Expr::Function {
+                        func,
+                        args: vec![arg],
+                    },
+                    &CTX,
+                )
+                .map(|v| v.to_string())
+        };
+        let extract = |field, arg| call(ScalarFunc::Extract(field), arg);
+        let trunc = |field, arg| call(ScalarFunc::DateTrunc(field), arg);
+        assert_eq!(
+            extract(DateField::Year, column("d")),
+            Ok("2026".to_string())
+        );
+        assert_eq!(
+            extract(DateField::Quarter, column("ts")),
+            Ok("4".to_string())
+        );
+        assert_eq!(extract(DateField::Dow, column("d")), Ok("6".to_string()));
+        assert_eq!(
+            extract(DateField::Minute, column("t")),
+            Ok("30".to_string())
+        );
+        assert!(extract(DateField::Year, column("t")).is_err());
+        assert_eq!(
+            trunc(DateField::Month, column("d")),
+            Ok("DATE '2026-10-01'".to_string())
+        );
+        assert_eq!(
+            trunc(DateField::Week, column("ts")),
+            Ok("TIMESTAMP '2026-10-12 00:00:00'".to_string())
+        );
+        assert_eq!(
+            trunc(DateField::Hour, column("ts")),
+            Ok("TIMESTAMP '2026-10-17 14:00:00'".to_string())
+        );
+        assert_eq!(
+            call(
+                ScalarFunc::Extract(DateField::Day),
+                Expr::Value(ASTValue::Null)
+            ),
+            Ok("NULL".to_string())
+        );
+    }
 }
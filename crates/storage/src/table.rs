@@ -1,26 +1,43 @@
-use super::page::Page;
-use super::record::Record;
-use super::types::Column;
+use super::page::{Page, SLOT_VERSIONED};
+use super::record::{
+    Record, decode_payload, deserialize_record_for_page, serialize_record_for_page,
+};
+use super::types::{Column, LayoutColumn, RowId};
 use serde::{Deserialize, Serialize};
+use sql::ast::{ASTValue, ForeignKey};
 use std::collections::HashMap;
+use types::tokens::DataType;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Table {
     pub name: String,
     pub columns: Vec<Column>,
+    pub foreign_keys: Vec<ForeignKey>,
     pub pages: HashMap<u32, Page>,
     pub next_page_id: u32,
     pub next_record_id: u64,
+    /// Bumped by every ALTER TABLE that changes the payload layout.
+    pub schema_version: u16,
+    /// Every column stored in some schema version, live or dropped, in
+    /// payload order. Live entries match `columns` by name and order.
+    pub layout: Vec<LayoutColumn>,
 }
 
 impl Table {
     pub fn new(name: String, columns: Vec<Column>) -> Self {
+        let layout = columns
+            .iter()
+            .map(|c| LayoutColumn::new(c.name.clone(), c.data_type.clone(), 0, ASTValue::Null))
+            .collect();
         let mut table = Self {
             name,
             columns,
+            foreign_keys: Vec::new(),
             pages: HashMap::new(),
             next_page_id: 0,
             next_record_id: 0,
+            schema_version: 0,
+            layout,
         };
 
         table.add_page();
@@ -34,12 +51,11 @@ impl Table {
         self.next_record_id += 1;
 
         for page_id in 0..self.next_page_id {
-            if let Some(page) = self.pages.get_mut(&page_id) {
-                if !page.is_full(std::mem::size_of::<Record>())
-                    && page.insert_record(record.clone()).is_ok()
-                {
-                    return Ok(record.id);
-                }
+            if let Some(page) = self.pages.get_mut(&page_id)
+                && !page.is_full(std::mem::size_of::<Record>())
+                && page.insert_record(record.clone()).is_ok()
+            {
+                return Ok(record.id);
             }
         }
 
@@ -108,6 +124,121 @@ impl Table {
     pub fn pages(&self) -> impl Iterator<Item = (&u32, &Page)> {
         self.pages.iter()
     }
+
+    /// Heap payload for a row, stamped with the current schema version. It
+    /// goes in a slot flagged `SLOT_VERSIONED`.
+    pub fn encode_row(&self, row_id: RowId, rec: &Record) -> Result<Vec<u8>, String> {
+        let mut out = self.schema_version.to_le_bytes().to_vec();
+        out.extend(serialize_record_for_page(row_id, rec, &self.columns)?);
+        Ok(out)
+    }
+
+    /// Decode a heap payload stored in a slot with `flags`. A payload written
+    /// under an older schema version is read with that version's layout:
+    /// dropped columns are skipped and columns added since take the value
+    /// they were added with.
+    pub fn decode_row(&self, flags: u8, payload: &[u8]) -> Result<(RowId, Record), String> {
+        let (version, payload) = if flags & SLOT_VERSIONED != 0 {
+            if payload.len() < 2 {
+                return Err("payload too short for schema version".into());
+            }
+            let (v, rest) = payload.split_at(2);
+            (u16::from_le_bytes(v.try_into().unwrap()), rest)
+        } else {
+            (0, payload)
+        };
+        if version == self.schema_version {
+            return deserialize_record_for_page(payload, &self.columns);
+        }
+        if version > self.schema_version {
+            return Err(format!(
+                "row written under schema version {} but table {} is at version {}",
+                version, self.name, self.schema_version
+            ));
+        }
+
+        let types: Vec<&DataType> = self
+            .layout
+            .iter()
+            .filter(|c| c.stored_in(version))
+            .map(|c| &c.data_type)
+            .collect();
+        let (row_id, values) = decode_payload(payload, &types)?;
+        let mut values = values.into_iter();
+        let mut rec = Record::new(row_id);
+        for col in &self.layout {
+            let value = if col.stored_in(version) {
+                values.next().unwrap_or(ASTValue::Null)
+            } else {
+                col.missing.clone()
+            };
+            if col.dropped_in.is_none() {
+                rec.set_value(&col.name, value);
+            }
+        }
+        Ok((row_id, rec))
+    }
+
+    /// Append `column`. Rows written before this read it as `missing`.
+    pub fn add_column(&mut self, column: Column, missing: ASTValue) -> Result<(), String> {
+        if self.columns.iter().any(|c| c.name == column.name) {
+            return Err(format!("column {} already exists", column.name));
+        }
+        let version = self.next_schema_version()?;
+        self.layout.push(LayoutColumn::new(
+            column.name.clone(),
+            column.data_type.clone(),
+            version,
+            missing,
+        ));
+        self.columns.push(column);
+        self.schema_version = version;
+        Ok(())
+    }
+
+    /// Remove the column `name`. Its bytes stay in older payloads until the
+    /// row is rewritten.
+    pub fn drop_column(&mut self, name: &str) -> Result<Column, String> {
+        let Some(pos) = self.columns.iter().position(|c| c.name == name) else {
+            return Err(format!("column {} does not exist", name));
+        };
+        let version = self.next_schema_version()?;
+        if let Some(entry) = self
+            .layout
+            .iter_mut()
+            .find(|c| c.dropped_in.is_none() && c.name == name)
+        {
+            entry.dropped_in = Some(version);
+        }
+        self.schema_version = version;
+        Ok(self.columns.remove(pos))
+    }
+
+    /// Rename a column. The payload layout is unchanged, so this does not
+    /// bump the schema version.
+    pub fn rename_column(&mut self, from: &str, to: &str) -> Result<(), String> {
+        if self.columns.iter().any(|c| c.name == to) {
+            return Err(format!("column {} already exists", to));
+        }
+        let Some(col) = self.columns.iter_mut().find(|c| c.name == from) else {
+            return Err(format!("column {} does not exist", from));
+        };
+        col.name = to.to_string();
+        if let Some(entry) = self
+            .layout
+            .iter_mut()
+            .find(|c| c.dropped_in.is_none() && c.name == from)
+        {
+            entry.name = to.to_string();
+        }
+        Ok(())
+    }
+
+    fn next_schema_version(&self) -> Result<u16, String> {
+        self.schema_version
+            .checked_add(1)
+            .ok_or_else(|| format!("table {} has run out of schema versions", self.name))
+    }
 }
 
 #[cfg(test)]
@@ -165,4 +296,37 @@ mod tests {
 
         assert!(table.insert_record(invalid_record).is_err());
     }
+
+    #[test]
+    fn test_decode_row_across_schema_versions() {
+        let mut table = create_test_table();
+        let old = table.encode_row(7, &create_test_record()).unwrap();
+
+        table.drop_column("name").unwrap();
+        table
+            .add_column(
+                Column::new("name".to_string(), DataType::INTEGER, true),
+                ASTValue::Int(0),
+            )
+            .unwrap();
+        table.rename_column("age", "years").unwrap();
+        assert_eq!(table.schema_version, 2);
+
+        // The row written at version 0 keeps its values, drops the old
+        // `name` and reads the re-added one as its missing value.
+        let (row_id, rec) = table.decode_row(SLOT_VERSIONED, &old).unwrap();
+        assert_eq!(row_id, 7);
+        assert_eq!(rec.get_value("id"), Some(&ASTValue::Int(1)));
+        assert_eq!(rec.get_value("years"), Some(&ASTValue::Int(25)));
+        assert_eq!(rec.get_value("name"), Some(&ASTValue::Int(0)));
+        assert_eq!(rec.data.len(), 3);
+
+        // Unstamped payloads predate versioning and decode as version 0.
+        let (_, legacy) = table.decode_row(0, &old[2..]).unwrap();
+        assert_eq!(legacy.data, rec.data);
+
+        let new = table.encode_row(8, &rec).unwrap();
+        let (_, again) = table.decode_row(SLOT_VERSIONED, &new).unwrap();
+        assert_eq!(again.data, rec.data);
+    }
 }
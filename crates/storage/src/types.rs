@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use sql::ast::{ASTValue, DefaultExpr};
 use types::tokens::DataType;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -6,6 +7,10 @@ pub struct Column {
     pub name: String,
     pub data_type: DataType,
     pub nullable: bool,
+    pub primary_key: bool,
+    pub unique: bool,
+    pub auto_increment: bool,
+    pub default: Option<DefaultExpr>,
 }
 
 impl Column {
@@ -14,10 +19,45 @@ impl Column {
             name,
             data_type,
             nullable,
+            primary_key: false,
+            unique: false,
+            auto_increment: false,
+            default: None,
         }
     }
 }
 
+/// A column as laid out in heap payloads. A payload written under schema
+/// version `v` holds, in layout order, every column stored in `v`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutColumn {
+    pub name: String,
+    pub data_type: DataType,
+    /// Schema version that added the column (0 for CREATE TABLE).
+    pub added_in: u16,
+    /// Schema version that dropped the column, if it was dropped.
+    pub dropped_in: Option<u16>,
+    /// Value read for this column from payloads older than `added_in`.
+    pub missing: ASTValue,
+}
+
+impl LayoutColumn {
+    pub fn new(name: String, data_type: DataType, added_in: u16, missing: ASTValue) -> Self {
+        Self {
+            name,
+            data_type,
+            added_in,
+            dropped_in: None,
+            missing,
+        }
+    }
+
+    /// Whether payloads written under `version` hold this column.
+    pub fn stored_in(&self, version: u16) -> bool {
+        self.added_in <= version && self.dropped_in.is_none_or(|d| d > version)
+    }
+}
+
 pub type RowId = u64;
 
 // Physical tuple location in the heap
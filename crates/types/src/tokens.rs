@@ -9,18 +9,19 @@ pub enum Token {
     EOF,
 
     // Literal types
-    IDENT(Vec<char>), // Identifiers
-    INT(Vec<char>),   // Integer literals
+    IDENT(Vec<char>),  // Identifiers, bare or "double-quoted"
+    INT(Vec<char>),    // Integer literals
+    FLOAT(Vec<char>),  // Literals with a decimal point or exponent: 1.5, .5, 2e-3
+    STRING(Vec<char>), // 'String' literals, quotes removed and '' unescaped
 
     // Operators and delimiters
-    COMMA(char),       // ','
-    SEMICOLON(char),   // ';'
-    LPAREN(char),      // '('
-    RPAREN(char),      // ')'
-    LBRACE(char),      // '{'
-    RBRACE(char),      // '}'
-    QUOTE(char),       // '"'
-    SINGLEQUOTE(char), // '\''
+    COMMA(char),     // ','
+    DOT(char),       // '.'
+    SEMICOLON(char), // ';'
+    LPAREN(char),    // '('
+    RPAREN(char),    // ')'
+    LBRACE(char),    // '{'
+    RBRACE(char),    // '}'
 
     // Boolean literals
     TRUE,
@@ -43,6 +44,8 @@ pub enum Command {
     SELECT,
     FROM,
     WHERE,
+    GROUP,
+    HAVING,
     ORDER,
     ASC,
     DESC,
@@ -58,6 +61,13 @@ pub enum Command {
     USE,
     SHOW,
     SET,
+    BEGIN,
+    COMMIT,
+    ROLLBACK,
+    TRANSACTION,
+    INDEX,
+    EXPLAIN,
+    TRUNCATE,
 }
 
 // Helper keywords
@@ -87,6 +97,21 @@ pub enum Helper {
     ADDS,
     DEFAULT,
     AUTOINCREMENT,
+    CASCADE,
+    RESTRICT,
+    JOIN,
+    INNER,
+    LEFT,
+    OUTER,
+    AS,
+    IS,
+    IF,
+    EXISTS,
+    RENAME,
+    TO,
+    NULLS,
+    FIRST,
+    LAST,
 }
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
@@ -102,10 +127,12 @@ pub enum DataType {
     CHAR,
     BLOB,
     JSON,
-    DECIMAL,
+    /// Precision and scale as in `DECIMAL(10, 2)`; None when written without
+    /// them, which admits any value of up to 38 digits.
+    DECIMAL(Option<(u8, u8)>),
     DOUBLE,
     REAL,
-    NUMERIC,
+    NUMERIC(Option<(u8, u8)>),
     TINYINT,
     SMALLINT,
     MEDIUMINT,
@@ -128,6 +155,25 @@ pub enum Operator {
     OR,       // 'OR'
 }
 
+impl DataType {
+    /// The integer, floating-point and DECIMAL types.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            DataType::TINYINT
+                | DataType::SMALLINT
+                | DataType::MEDIUMINT
+                | DataType::INTEGER
+                | DataType::BIGINT
+                | DataType::REAL
+                | DataType::FLOAT
+                | DataType::DOUBLE
+                | DataType::DECIMAL(_)
+                | DataType::NUMERIC(_)
+        )
+    }
+}
+
 impl fmt::Display for DataType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -142,10 +188,12 @@ impl fmt::Display for DataType {
             DataType::CHAR => write!(f, "CHAR"),
             DataType::BLOB => write!(f, "BLOB"),
             DataType::JSON => write!(f, "JSON"),
-            DataType::DECIMAL => write!(f, "DECIMAL"),
+            DataType::DECIMAL(None) => write!(f, "DECIMAL"),
+            DataType::DECIMAL(Some((p, s))) => write!(f, "DECIMAL({},{})", p, s),
             DataType::DOUBLE => write!(f, "DOUBLE"),
             DataType::REAL => write!(f, "REAL"),
-            DataType::NUMERIC => write!(f, "NUMERIC"),
+            DataType::NUMERIC(None) => write!(f, "NUMERIC"),
+            DataType::NUMERIC(Some((p, s))) => write!(f, "NUMERIC({},{})", p, s),
             DataType::TINYINT => write!(f, "TINYINT"),
             DataType::SMALLINT => write!(f, "SMALLINT"),
             DataType::MEDIUMINT => write!(f, "MEDIUMINT"),
@@ -154,6 +202,26 @@ impl fmt::Display for DataType {
     }
 }
 
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operator::EQUALS => write!(f, "="),
+            Operator::NE => write!(f, "!="),
+            Operator::LT => write!(f, "<"),
+            Operator::GT => write!(f, ">"),
+            Operator::LTorE => write!(f, "<="),
+            Operator::GTorE => write!(f, ">="),
+            Operator::PLUS => write!(f, "+"),
+            Operator::MINUS => write!(f, "-"),
+            Operator::DIVIDE => write!(f, "/"),
+            Operator::BANG => write!(f, "!"),
+            Operator::ASTERISK => write!(f, "*"),
+            Operator::AND => write!(f, "AND"),
+            Operator::OR => write!(f, "OR"),
+        }
+    }
+}
+
 impl fmt::Display for Helper {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)